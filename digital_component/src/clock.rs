@@ -0,0 +1,218 @@
+use crate::{BitState, ComponentLogic, Graph};
+
+/// Square wave counted in simulation ticks: `On` for `duty` ticks of every
+/// `period`, starting `phase` ticks after tick 0
+#[derive(Clone, Debug, PartialEq)]
+pub struct Clock {
+    period: usize,
+    phase: usize,
+    duty: usize,
+}
+
+impl Clock {
+    /// Clock with a 50% duty cycle rising at tick 0
+    pub fn new(period: usize) -> Clock {
+        assert!(period > 0, "a clock period must be at least one tick");
+        Clock {
+            period,
+            phase: 0,
+            duty: period.div_ceil(2),
+        }
+    }
+
+    pub fn with_phase(mut self, phase: usize) -> Clock {
+        self.phase = phase;
+        self
+    }
+
+    pub fn with_duty(mut self, duty: usize) -> Clock {
+        assert!(
+            duty <= self.period,
+            "a clock can not be on for longer than its period"
+        );
+        self.duty = duty;
+        self
+    }
+
+    pub fn period(&self) -> usize {
+        self.period
+    }
+
+    pub fn state(&self, tick: usize) -> BitState {
+        let since_rise = (tick + self.period - self.phase % self.period) % self.period;
+        if since_rise < self.duty {
+            BitState::On
+        } else {
+            BitState::Off
+        }
+    }
+}
+
+/// Simulates a circuit tick by tick with any number of its inputs driven by
+/// clocks and the rest holding the values they are set to
+pub struct Scheduler {
+    logic: Box<ComponentLogic>,
+    inputs: Vec<BitState>,
+    outputs: Vec<BitState>,
+    clocks: Vec<(usize, Clock)>,
+    tick: usize,
+}
+
+impl Scheduler {
+    pub fn new(graph: Graph) -> Scheduler {
+        let inputs = vec![BitState::Undefined; graph.input_num()];
+        let outputs = vec![BitState::Undefined; graph.output_num()];
+        Scheduler {
+            logic: graph.finalize(),
+            inputs,
+            outputs,
+            clocks: vec![],
+            tick: 0,
+        }
+    }
+
+    /// Drives the input with the clock from the next tick on, replacing the
+    /// clock driving it so far if there is one
+    pub fn add_clock(&mut self, input: usize, clock: Clock) {
+        assert!(input < self.inputs.len(), "there is no input {input}");
+        self.clocks.retain(|(clocked, _)| *clocked != input);
+        self.clocks.push((input, clock));
+    }
+
+    /// The clock driving the input, if any
+    pub fn clock(&self, input: usize) -> Option<&Clock> {
+        self.clocks
+            .iter()
+            .find(|(clocked, _)| *clocked == input)
+            .map(|(_, clock)| clock)
+    }
+
+    pub fn set_input(&mut self, input: usize, value: BitState) {
+        self.inputs[input] = value;
+    }
+
+    /// The number of ticks simulated so far
+    pub fn tick(&self) -> usize {
+        self.tick
+    }
+
+    pub fn inputs(&self) -> &[BitState] {
+        &self.inputs
+    }
+
+    pub fn outputs(&self) -> &[BitState] {
+        &self.outputs
+    }
+
+    /// Sets the clocked inputs to the states of their clocks at the current
+    /// tick, all at once, and evaluates the circuit
+    pub fn step(&mut self) -> &[BitState] {
+        for (input, clock) in &self.clocks {
+            self.inputs[*input] = clock.state(self.tick);
+        }
+        (self.logic)(&self.inputs, &mut self.outputs);
+        self.tick += 1;
+        &self.outputs
+    }
+
+    pub fn run(&mut self, ticks: usize) -> &[BitState] {
+        for _ in 0..ticks {
+            self.step();
+        }
+        &self.outputs
+    }
+
+    /// Runs as many ticks as `cycles` periods of the clock driving the input
+    pub fn run_cycles(&mut self, input: usize, cycles: usize) -> &[BitState] {
+        let period = self
+            .clock(input)
+            .unwrap_or_else(|| panic!("no clock drives input {input}"))
+            .period;
+        self.run(cycles * period)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{ComponentInput, ComponentOutput, DigitalComponent, NodeKind};
+    use BitState::*;
+
+    #[test]
+    fn generates_square_waves() {
+        let waveform = |clock: Clock| (0..8).map(|tick| clock.state(tick)).collect::<Vec<_>>();
+
+        assert_eq!(
+            waveform(Clock::new(4)),
+            vec![On, On, Off, Off, On, On, Off, Off]
+        );
+        assert_eq!(
+            waveform(Clock::new(4).with_phase(1).with_duty(1)),
+            vec![Off, On, Off, Off, Off, On, Off, Off]
+        );
+        assert_eq!(
+            waveform(Clock::new(3).with_phase(5)),
+            vec![On, Off, On, On, Off, On, On, Off]
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "longer than its period")]
+    fn rejects_duty_longer_than_period() {
+        Clock::new(4).with_duty(5);
+    }
+
+    /// Counts the rising edges on input 0 while input 1 is `On`, in two bits
+    fn counter() -> Graph {
+        let mut graph = Graph::default();
+        let mut previous = Off;
+        let mut count = 0;
+        let counter = graph.add_component(DigitalComponent::new(
+            2,
+            2,
+            Box::new(move |input: &[BitState], output: &mut [BitState]| {
+                if previous == Off && input[0] == On && input[1] == On {
+                    count += 1;
+                }
+                previous = input[0];
+                output[0] = if count & 2 != 0 { On } else { Off };
+                output[1] = if count & 1 != 0 { On } else { Off };
+            }),
+        ));
+        for pin in 0..2 {
+            let input = graph.add_node(NodeKind::Input(pin));
+            let component_input =
+                graph.add_node(NodeKind::ComponentInput(ComponentInput::new(counter, pin)));
+            graph.add_edge(&input, &component_input);
+            let output = graph.add_node(NodeKind::Output(pin));
+            let component_output = graph.add_node(NodeKind::ComponentOutput(ComponentOutput::new(
+                counter, pin,
+            )));
+            graph.add_edge(&output, &component_output);
+        }
+        graph
+    }
+
+    #[test]
+    fn drives_inputs_with_several_clocks() {
+        let mut scheduler = Scheduler::new(counter());
+        scheduler.add_clock(0, Clock::new(2));
+        // counting is enabled every other cycle of the faster clock
+        scheduler.add_clock(1, Clock::new(4).with_phase(3).with_duty(2));
+
+        assert_eq!(scheduler.run_cycles(0, 4), &[On, Off]);
+        assert_eq!(scheduler.tick(), 8);
+        assert_eq!(scheduler.run_cycles(0, 1), &[On, On]);
+        assert_eq!(scheduler.inputs(), &[Off, Off]);
+    }
+
+    #[test]
+    fn holds_inputs_without_clocks() {
+        let mut scheduler = Scheduler::new(counter());
+        scheduler.add_clock(0, Clock::new(2));
+        scheduler.set_input(1, Off);
+        assert_eq!(scheduler.run(4), &[Off, Off]);
+        scheduler.set_input(1, On);
+        assert_eq!(scheduler.run(4), &[On, Off]);
+    }
+}
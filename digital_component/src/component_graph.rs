@@ -1,8 +1,10 @@
 use super::{BitState, ComponentLogic, DigitalComponent};
 use crate::debug_logger::{begin_context, end_context, log};
+use std::cell::RefCell;
 use std::collections::{HashMap, HashSet};
 use std::fmt::{Debug, Error, Formatter};
 use std::hash::Hash;
+use std::rc::Rc;
 
 #[derive(Eq, PartialEq, Clone, Hash)]
 pub enum NodeKind {
@@ -11,6 +13,9 @@ pub enum NodeKind {
     Input(usize),
     Output(usize),
     Joint,
+    /// Weak driver giving its net the value when nothing else drives it to
+    /// `On` or `Off`, i.e. a pull-up or a pull-down resistor
+    Pull(BitState),
 }
 
 pub type ComponentInput = ComponentPin;
@@ -18,8 +23,8 @@ pub type ComponentOutput = ComponentPin;
 
 #[derive(Eq, PartialEq, Hash, Debug, Clone)]
 pub struct ComponentPin {
-    component: DigitalComponentRef,
-    pin: usize,
+    pub(crate) component: DigitalComponentRef,
+    pub(crate) pin: usize,
 }
 
 impl ComponentPin {
@@ -40,6 +45,7 @@ impl Debug for NodeKind {
                 NodeKind::Input(idx) => format!("input({})", idx),
                 NodeKind::Output(idx) => format!("output({})", idx),
                 NodeKind::Joint => "joint".to_string(),
+                NodeKind::Pull(value) => format!("pull({:?})", value),
             }
         ))
     }
@@ -50,8 +56,8 @@ pub type DigitalComponentRef = usize;
 
 #[derive(PartialEq, Eq, Default)]
 pub struct Graph {
-    components: Vec<DigitalComponent>,
-    nodes: Vec<NodeKind>,
+    pub(crate) components: Vec<DigitalComponent>,
+    pub(crate) nodes: Vec<NodeKind>,
     adjacency: Vec<HashSet<GraphNodeRef>>,
 }
 
@@ -72,7 +78,31 @@ impl Graph {
         self.nodes.len() - 1
     }
 
-    fn find_disjointed_node_sets(&self) -> Vec<GraphNodeRef> {
+    /// Number of outer inputs, i.e. one past the highest [`NodeKind::Input`] index
+    pub fn input_num(&self) -> usize {
+        self.nodes
+            .iter()
+            .filter_map(|node| match node {
+                NodeKind::Input(idx) => Some(idx + 1),
+                _ => None,
+            })
+            .max()
+            .unwrap_or(0)
+    }
+
+    /// Number of outer outputs, i.e. one past the highest [`NodeKind::Output`] index
+    pub fn output_num(&self) -> usize {
+        self.nodes
+            .iter()
+            .filter_map(|node| match node {
+                NodeKind::Output(idx) => Some(idx + 1),
+                _ => None,
+            })
+            .max()
+            .unwrap_or(0)
+    }
+
+    pub(crate) fn find_disjointed_node_sets(&self) -> Vec<GraphNodeRef> {
         let mut uf_component_indices: Vec<_> = (0..self.nodes.len()).collect();
         for node_idx in 0..self.nodes.len() {
             let mut this_idx = node_idx;
@@ -108,21 +138,21 @@ impl Graph {
         source_node_type_check: &dyn Fn(&NodeKind) -> bool,
         dest_node_type_check: &dyn Fn(&NodeKind) -> bool,
     ) -> HashMap<GraphNodeRef, HashSet<GraphNodeRef>> {
-        let mut mapping = HashMap::new();
+        let mut dest_nodes_by_set: HashMap<GraphNodeRef, HashSet<GraphNodeRef>> = HashMap::new();
+        for (idx, node) in self.nodes.iter().enumerate() {
+            if dest_node_type_check(node) {
+                dest_nodes_by_set
+                    .entry(uf_component_indices[idx])
+                    .or_default()
+                    .insert(idx);
+            }
+        }
 
-        for idx in 0..self.nodes.len() {
-            if source_node_type_check(&self.nodes[idx]) {
-                let node_set_idx = uf_component_indices[idx];
-                let dest = uf_component_indices
-                    .iter()
-                    .enumerate()
-                    .filter(|(_, set_idx)| **set_idx == node_set_idx)
-                    .filter_map(|(neighbour_idx, _)| {
-                        Some(neighbour_idx).filter(|idx| dest_node_type_check(&self.nodes[*idx]))
-                    })
-                    .collect::<HashSet<_>>();
-                if !dest.is_empty() {
-                    mapping.insert(idx, dest);
+        let mut mapping = HashMap::new();
+        for (idx, node) in self.nodes.iter().enumerate() {
+            if source_node_type_check(node) {
+                if let Some(dest) = dest_nodes_by_set.get(&uf_component_indices[idx]) {
+                    mapping.insert(idx, dest.clone());
                 }
             }
         }
@@ -162,16 +192,53 @@ impl Graph {
         )
     }
 
-    pub fn finalize(mut self) -> Box<ComponentLogic> {
+    fn outer_passthrough_mapping(
+        &self,
+        uf_component_indices: &[usize],
+    ) -> HashMap<GraphNodeRef, HashSet<GraphNodeRef>> {
+        self.find_mapping(
+            uf_component_indices,
+            &|node| matches!(node, NodeKind::Output { .. }),
+            &|node| matches!(node, NodeKind::Input { .. }),
+        )
+    }
+
+    pub fn finalize(self) -> Box<ComponentLogic> {
+        self.finalize_with_probe(None)
+    }
+
+    /// Like [`Graph::finalize`], also returning a [`Probe`] that holds the
+    /// state of every node as of the end of the last call of the logic
+    pub fn finalize_probed(self) -> (Box<ComponentLogic>, Probe) {
+        let probe = Probe::new(self.nodes.len());
+        (self.finalize_with_probe(Some(probe.clone())), probe)
+    }
+
+    fn finalize_with_probe(mut self, probe: Option<Probe>) -> Box<ComponentLogic> {
         begin_context();
         log(format_args!("{:?}", &self));
         let uf_component_indices = self.find_disjointed_node_sets();
         let outputs_to_inputs = self.output_to_input_mapping(&uf_component_indices);
         let outer_input_mapping = self.outer_input_mapping(&uf_component_indices);
         let outer_output_mapping = self.outer_output_mapping(&uf_component_indices);
+        let outer_passthrough_mapping = self.outer_passthrough_mapping(&uf_component_indices);
 
         let nodes = self.nodes;
 
+        // the pull on the net of every node, if there is one
+        let net_pulls = nodes
+            .iter()
+            .enumerate()
+            .filter_map(|(idx, node)| match node {
+                NodeKind::Pull(value) => Some((uf_component_indices[idx], *value)),
+                _ => None,
+            })
+            .collect::<HashMap<_, _>>();
+        let pulls = uf_component_indices
+            .iter()
+            .map(|net| net_pulls.get(net).copied())
+            .collect::<Vec<_>>();
+
         let mut nested_components_inputs = self
             .components
             .iter()
@@ -184,20 +251,51 @@ impl Graph {
             .map(|component| vec![BitState::Undefined; component.get_output_num()])
             .collect::<Vec<_>>();
 
+        // components without inputs, like constant drivers, never become dirty
+        // so they have to be evaluated once up front
+        let mut sources = self
+            .components
+            .iter()
+            .enumerate()
+            .filter(|(_, component)| component.get_input_num() == 0)
+            .map(|(idx, _)| idx)
+            .collect::<Vec<_>>();
+        // as are the ones with pulled inputs nothing drives
+        for (idx, node) in nodes.iter().enumerate() {
+            if let (NodeKind::ComponentInput(ComponentInput { component, pin }), Some(value)) =
+                (node, pulls[idx])
+            {
+                nested_components_inputs[*component][*pin] = value;
+                if !sources.contains(component) {
+                    sources.push(*component);
+                }
+            }
+        }
+
+        // the node of every output pin of every component, looked up once
+        // rather than on every evaluation
+        let mut component_output_nodes = self
+            .components
+            .iter()
+            .map(|component| vec![None; component.get_output_num()])
+            .collect::<Vec<_>>();
+        for (idx, node) in nodes.iter().enumerate() {
+            if let NodeKind::ComponentOutput(ComponentOutput { component, pin }) = node {
+                component_output_nodes[*component][*pin] = Some(idx);
+            }
+        }
+
         let component_logic = move |input_bits: &[BitState], output_bits: &mut [BitState]| {
             begin_context();
-            let node_to_idx = nodes
-                .iter()
-                .enumerate()
-                .map(|(idx, node)| (node, idx))
-                .collect::<HashMap<_, _>>();
 
             let mut dirty_components = propagate_outer_input(
                 &nodes,
                 &mut nested_components_inputs,
                 input_bits,
                 &outer_input_mapping,
+                &pulls,
             );
+            dirty_components.append(&mut sources);
 
             while let Some(nested_component) = dirty_components.pop() {
                 let outputs = &mut nested_components_outputs[nested_component];
@@ -210,28 +308,27 @@ impl Graph {
 
                 // propagate the signal to dependant components
                 for (pin, output_bit) in outputs.iter().enumerate() {
-                    let output_pin = ComponentOutput::new(nested_component, pin);
-                    let output_pin_idx = node_to_idx
-                        .get(&NodeKind::ComponentOutput(output_pin.clone()))
+                    let output_pin_idx = component_output_nodes[nested_component][pin]
                         .unwrap_or_else(|| {
                             panic!(
                                 "we processed this output, it must be present among the nodes {:?}",
-                                &output_pin
+                                ComponentOutput::new(nested_component, pin)
                             )
                         });
-                    let connected_input_indices = outputs_to_inputs.get(output_pin_idx);
-                    for connected_input_node in connected_input_indices
+                    let connected_input_indices = outputs_to_inputs.get(&output_pin_idx);
+                    for (input_idx, connected_input_node) in connected_input_indices
                         .iter()
                         .flat_map(|x| *x)
-                        .map(|input_idx| &nodes[*input_idx])
+                        .map(|input_idx| (*input_idx, &nodes[*input_idx]))
                     {
                         if let NodeKind::ComponentInput(connected_input_node) = connected_input_node
                         {
                             let connected_inputs_bits = nested_components_inputs
                                 .get_mut(connected_input_node.component)
                                 .unwrap();
-                            if connected_inputs_bits[connected_input_node.pin] != *output_bit {
-                                connected_inputs_bits[connected_input_node.pin] = *output_bit;
+                            let input_bit = pulled(*output_bit, pulls[input_idx]);
+                            if connected_inputs_bits[connected_input_node.pin] != input_bit {
+                                connected_inputs_bits[connected_input_node.pin] = input_bit;
                                 dirty_components.push(connected_input_node.component);
                             }
                         } else {
@@ -244,10 +341,23 @@ impl Graph {
             propagate_to_outer_output(
                 &nodes,
                 &mut nested_components_outputs,
+                input_bits,
                 output_bits,
                 &outer_output_mapping,
+                &outer_passthrough_mapping,
+                &pulls,
             );
 
+            if let Some(probe) = &probe {
+                probe.record(
+                    &nodes,
+                    &uf_component_indices,
+                    input_bits,
+                    &nested_components_outputs,
+                    &pulls,
+                );
+            }
+
             log(format_args!("output mapping: {:?}", &outer_output_mapping));
             log(format_args!("output bits: {:?}", &output_bits));
             end_context();
@@ -257,11 +367,77 @@ impl Graph {
     }
 }
 
+/// Node states of a finalized graph, shared with its logic
+#[derive(Clone)]
+pub struct Probe {
+    states: Rc<RefCell<Vec<BitState>>>,
+}
+
+impl Probe {
+    fn new(node_num: usize) -> Probe {
+        Probe {
+            states: Rc::new(RefCell::new(vec![BitState::Undefined; node_num])),
+        }
+    }
+
+    /// State of the net the node is on, `On` winning over `Off` when
+    /// the net has more than one driver and a pull deciding it when none
+    /// of them drives it
+    pub fn state(&self, node: GraphNodeRef) -> BitState {
+        self.states.borrow()[node]
+    }
+
+    fn record(
+        &self,
+        nodes: &[NodeKind],
+        uf_component_indices: &[GraphNodeRef],
+        input_bits: &[BitState],
+        nested_components_outputs: &[Vec<BitState>],
+        pulls: &[Option<BitState>],
+    ) {
+        let mut net_states: HashMap<GraphNodeRef, BitState> = HashMap::new();
+        for (idx, node) in nodes.iter().enumerate() {
+            let driven = match node {
+                NodeKind::Input(input_idx) => input_bits[*input_idx],
+                NodeKind::ComponentOutput(ComponentOutput { component, pin }) => {
+                    nested_components_outputs[*component][*pin]
+                }
+                _ => continue,
+            };
+            let net_state = net_states
+                .entry(uf_component_indices[idx])
+                .or_insert(BitState::Undefined);
+            *net_state = match (driven, *net_state) {
+                (BitState::On, _) | (_, BitState::On) => BitState::On,
+                (BitState::Off, _) | (_, BitState::Off) => BitState::Off,
+                _ => BitState::Undefined,
+            };
+        }
+        let mut states = self.states.borrow_mut();
+        for (idx, state) in states.iter_mut().enumerate() {
+            let driven = net_states
+                .get(&uf_component_indices[idx])
+                .copied()
+                .unwrap_or(BitState::Undefined);
+            *state = pulled(driven, pulls[idx]);
+        }
+    }
+}
+
+/// The value of a net driven to `driven`, given the pull on it
+fn pulled(driven: BitState, pull: Option<BitState>) -> BitState {
+    match (driven, pull) {
+        (BitState::Undefined, Some(pull)) => pull,
+        _ => driven,
+    }
+}
+
 fn propagate_outer_input(
     nodes: &[NodeKind],
     nested_components_state: &mut [Vec<BitState>],
     input_bits: &[BitState],
     outer_input_mapping: &HashMap<GraphNodeRef, HashSet<GraphNodeRef>>,
+    pulls: &[Option<BitState>],
 ) -> Vec<DigitalComponentRef> {
     let mut touched_input_components = vec![];
     let mut touched_input_set = HashSet::new();
@@ -280,7 +456,7 @@ fn propagate_outer_input(
             nodes[*nested_input_ref].clone(),
         ) {
             let inputs = &mut nested_components_state[component];
-            inputs[pin] = input_bits[input_idx];
+            inputs[pin] = pulled(input_bits[input_idx], pulls[*nested_input_ref]);
             if !touched_input_set.contains(&component) {
                 touched_input_components.push(component);
                 touched_input_set.insert(component);
@@ -293,8 +469,11 @@ fn propagate_outer_input(
 fn propagate_to_outer_output(
     nodes: &[NodeKind],
     nested_components_outputs: &mut [Vec<BitState>],
+    input_bits: &[BitState],
     output_bits: &mut [BitState],
     outer_output_mapping: &HashMap<GraphNodeRef, HashSet<GraphNodeRef>>,
+    outer_passthrough_mapping: &HashMap<GraphNodeRef, HashSet<GraphNodeRef>>,
+    pulls: &[Option<BitState>],
 ) {
     let mut new_output = vec![BitState::Undefined; output_bits.len()];
     for (outer_output_ref, nested_output_ref) in
@@ -323,6 +502,29 @@ fn propagate_to_outer_output(
             new_output[output_idx] = new_output_value;
         }
     }
+    // outer inputs wired straight to outer outputs
+    for (outer_output_ref, outer_input_ref) in
+        outer_passthrough_mapping
+            .iter()
+            .flat_map(|(output_idx, node_set)| {
+                node_set.iter().map(move |node_ref| (output_idx, node_ref))
+            })
+    {
+        if let (NodeKind::Output(output_idx), NodeKind::Input(input_idx)) =
+            (&nodes[*outer_output_ref], &nodes[*outer_input_ref])
+        {
+            new_output[*output_idx] = match (input_bits[*input_idx], new_output[*output_idx]) {
+                (BitState::On, _) => BitState::On,
+                (BitState::Off, BitState::Undefined) => BitState::Off,
+                _ => output_bits[*output_idx],
+            };
+        }
+    }
+    for (idx, node) in nodes.iter().enumerate() {
+        if let NodeKind::Output(output_idx) = node {
+            new_output[*output_idx] = pulled(new_output[*output_idx], pulls[idx]);
+        }
+    }
     for (output_bit, new_output_bit) in output_bits.iter_mut().zip(new_output.iter()) {
         *output_bit = *new_output_bit;
     }
@@ -571,4 +773,87 @@ mod tests {
         comp_logic(&[BitState::On, BitState::Off], &mut output);
         assert_eq!(output, vec![BitState::Off, BitState::Off]);
     }
+
+    #[test]
+    fn passes_inputs_through_and_evaluates_sources() {
+        let mut graph = Graph::default();
+        let source = graph.add_component(DigitalComponent::new(
+            0,
+            1,
+            Box::new(|_: &[BitState], output: &mut [BitState]| output[0] = BitState::On),
+        ));
+        let source_output =
+            graph.add_node(NodeKind::ComponentOutput(ComponentOutput::new(source, 0)));
+        let input = graph.add_node(NodeKind::Input(0));
+        let output_0 = graph.add_node(NodeKind::Output(0));
+        let output_1 = graph.add_node(NodeKind::Output(1));
+        graph.add_edge(&input, &output_0);
+        graph.add_edge(&source_output, &output_1);
+
+        let mut comp_logic = graph.finalize();
+        let mut output = vec![BitState::Undefined; 2];
+        comp_logic(&[BitState::Off], &mut output);
+        assert_eq!(output, vec![BitState::Off, BitState::On]);
+        comp_logic(&[BitState::On], &mut output);
+        assert_eq!(output, vec![BitState::On, BitState::On]);
+    }
+
+    #[test]
+    fn probes_node_states() {
+        let mut graph = Graph::default();
+        let inverter = graph.add_component(DigitalComponent::new(1, 1, Box::new(test)));
+        let inverter_input =
+            graph.add_node(NodeKind::ComponentInput(ComponentInput::new(inverter, 0)));
+        let inverter_output =
+            graph.add_node(NodeKind::ComponentOutput(ComponentOutput::new(inverter, 0)));
+        let input = graph.add_node(NodeKind::Input(0));
+        let joint = graph.add_node(NodeKind::Joint);
+        let output = graph.add_node(NodeKind::Output(0));
+        let unconnected = graph.add_node(NodeKind::Joint);
+        graph.add_edge(&input, &joint);
+        graph.add_edge(&joint, &inverter_input);
+        graph.add_edge(&inverter_output, &output);
+
+        let (mut comp_logic, probe) = graph.finalize_probed();
+        let mut outputs = vec![BitState::Undefined];
+        comp_logic(&[BitState::On], &mut outputs);
+
+        assert_eq!(probe.state(joint), BitState::On);
+        assert_eq!(probe.state(inverter_input), BitState::On);
+        assert_eq!(probe.state(output), BitState::Off);
+        assert_eq!(probe.state(unconnected), BitState::Undefined);
+
+        comp_logic(&[BitState::Off], &mut outputs);
+        assert_eq!(probe.state(joint), BitState::Off);
+        assert_eq!(probe.state(output), BitState::On);
+    }
+
+    #[test]
+    fn resolves_pulled_nets() {
+        let mut graph = Graph::default();
+        let inverter = graph.add_component(DigitalComponent::new(1, 1, Box::new(test)));
+        let inverter_input =
+            graph.add_node(NodeKind::ComponentInput(ComponentInput::new(inverter, 0)));
+        let inverter_output =
+            graph.add_node(NodeKind::ComponentOutput(ComponentOutput::new(inverter, 0)));
+        let input = graph.add_node(NodeKind::Input(0));
+        let pull_up = graph.add_node(NodeKind::Pull(BitState::On));
+        let pull_down = graph.add_node(NodeKind::Pull(BitState::Off));
+        let output_0 = graph.add_node(NodeKind::Output(0));
+        let output_1 = graph.add_node(NodeKind::Output(1));
+        graph.add_edge(&input, &inverter_input);
+        graph.add_edge(&pull_up, &inverter_input);
+        graph.add_edge(&inverter_output, &output_0);
+        graph.add_edge(&pull_down, &output_1);
+
+        let (mut comp_logic, probe) = graph.finalize_probed();
+        let mut outputs = vec![BitState::Undefined; 2];
+        comp_logic(&[BitState::Undefined], &mut outputs);
+        assert_eq!(outputs, vec![BitState::Off, BitState::Off]);
+        assert_eq!(probe.state(input), BitState::On);
+
+        comp_logic(&[BitState::Off], &mut outputs);
+        assert_eq!(outputs, vec![BitState::On, BitState::Off]);
+        assert_eq!(probe.state(pull_up), BitState::Off);
+    }
 }
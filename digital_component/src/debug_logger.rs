@@ -7,7 +7,7 @@ pub struct DebugLogger {
 
 static mut LOGGER: DebugLogger = DebugLogger {
     current_depth: 0,
-    log_depth: 1,
+    log_depth: 0,
 };
 
 pub fn begin_context() {
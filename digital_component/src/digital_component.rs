@@ -14,6 +14,7 @@ pub struct DigitalComponent {
     input_num: usize,
     output_num: usize,
     func: Box<ComponentLogic>,
+    name: Option<String>,
 }
 
 impl PartialEq for DigitalComponent {
@@ -36,9 +37,59 @@ impl DigitalComponent {
             input_num,
             output_num,
             func,
+            name: None,
         }
     }
 
+    /// Component without inputs permanently driving `value` on its only output
+    pub fn constant(value: BitState) -> DigitalComponent {
+        let name = match value {
+            BitState::On => "vcc",
+            BitState::Off => "gnd",
+            BitState::Undefined => "undefined",
+        };
+        DigitalComponent::new(
+            0,
+            1,
+            Box::new(move |_: &[BitState], output: &mut [BitState]| output[0] = value),
+        )
+        .with_name(name)
+    }
+
+    /// Component passing its input through to its output, except that it
+    /// turns [`BitState::Undefined`] into `value`, the way a pull resolves a
+    /// net that something else drives too
+    pub fn pull(value: BitState) -> DigitalComponent {
+        let name = match value {
+            BitState::On => "pullup",
+            BitState::Off => "pulldown",
+            BitState::Undefined => "pull",
+        };
+        DigitalComponent::new(
+            1,
+            1,
+            Box::new(move |input: &[BitState], output: &mut [BitState]| {
+                output[0] = match input[0] {
+                    BitState::Undefined => value,
+                    driven => driven,
+                }
+            }),
+        )
+        .with_name(name)
+    }
+
+    /// Names the component after the definition it was instantiated from,
+    /// e.g. `"and"` or `"cascade_and(3)"`, so it can be resolved back to its
+    /// structure later on
+    pub fn with_name(mut self, name: &str) -> DigitalComponent {
+        self.name = Some(name.to_string());
+        self
+    }
+
+    pub fn get_name(&self) -> Option<&str> {
+        self.name.as_deref()
+    }
+
     pub fn get_input_num(&self) -> usize {
         self.input_num
     }
@@ -0,0 +1,321 @@
+use crate::logic_network::{to_bit_state, LogicNetwork, LogicNetworkError};
+use crate::netlist::ComponentResolver;
+use crate::{BitState, Graph};
+use std::collections::HashMap;
+
+/// Circuits with up to that many inputs are compared by simulating all the
+/// input combinations, wider ones with binary decision diagrams
+pub const MAX_EXHAUSTIVE_INPUTS: usize = 16;
+
+/// Binary decision diagrams growing beyond that are given up on
+pub const MAX_BDD_NODES: usize = 1 << 22;
+
+#[derive(Debug, PartialEq)]
+pub enum Equivalence {
+    Equivalent,
+    /// the circuits produce different outputs for these inputs
+    Counterexample {
+        inputs: Vec<BitState>,
+        left: Vec<BitState>,
+        right: Vec<BitState>,
+    },
+}
+
+#[derive(Debug, PartialEq)]
+pub enum EquivalenceError {
+    /// the circuits do not have the same number of inputs and outputs
+    PortMismatch {
+        left: (usize, usize),
+        right: (usize, usize),
+    },
+    NotCombinational(LogicNetworkError),
+    TooComplex,
+}
+
+/// Proves the two circuits produce the same outputs for all the inputs or
+/// finds the inputs for which they do not
+pub fn check_equivalence(
+    left: Graph,
+    right: Graph,
+    resolve: &ComponentResolver<'_>,
+) -> Result<Equivalence, EquivalenceError> {
+    let left = LogicNetwork::new(&mut left.flatten(resolve))
+        .map_err(EquivalenceError::NotCombinational)?;
+    let right = LogicNetwork::new(&mut right.flatten(resolve))
+        .map_err(EquivalenceError::NotCombinational)?;
+    left.check_equivalence(&right)
+}
+
+impl LogicNetwork {
+    pub fn check_equivalence(&self, other: &LogicNetwork) -> Result<Equivalence, EquivalenceError> {
+        if self.inputs.len() != other.inputs.len() || self.outputs.len() != other.outputs.len() {
+            return Err(EquivalenceError::PortMismatch {
+                left: (self.inputs.len(), self.outputs.len()),
+                right: (other.inputs.len(), other.outputs.len()),
+            });
+        }
+
+        let counterexample = if self.inputs.len() <= MAX_EXHAUSTIVE_INPUTS {
+            let input_num = self.inputs.len();
+            (0..1_usize << input_num)
+                .map(|vector| {
+                    (0..input_num)
+                        .map(|pin| vector >> (input_num - 1 - pin) & 1 == 1)
+                        .collect::<Vec<_>>()
+                })
+                .find(|inputs| self.evaluate(inputs) != other.evaluate(inputs))
+        } else {
+            find_difference_with_bdd(self, other)?
+        };
+
+        Ok(match counterexample {
+            None => Equivalence::Equivalent,
+            Some(inputs) => Equivalence::Counterexample {
+                left: self
+                    .evaluate(&inputs)
+                    .into_iter()
+                    .map(to_bit_state)
+                    .collect(),
+                right: other
+                    .evaluate(&inputs)
+                    .into_iter()
+                    .map(to_bit_state)
+                    .collect(),
+                inputs: inputs.into_iter().map(to_bit_state).collect(),
+            },
+        })
+    }
+}
+
+fn find_difference_with_bdd(
+    left: &LogicNetwork,
+    right: &LogicNetwork,
+) -> Result<Option<Vec<bool>>, EquivalenceError> {
+    let mut bdd = Bdd::default();
+    let left_outputs = bdd.outputs(left)?;
+    let right_outputs = bdd.outputs(right)?;
+
+    // the miter: on whenever any pair of outputs differs
+    let mut miter = FALSE;
+    for (left, right) in left_outputs.into_iter().zip(right_outputs) {
+        let difference = bdd.xor(left, right)?;
+        miter = bdd.or(miter, difference)?;
+    }
+    Ok(bdd.satisfying_assignment(miter, left.inputs.len()))
+}
+
+type BddRef = usize;
+
+const FALSE: BddRef = 0;
+const TRUE: BddRef = 1;
+
+/// Reduced ordered binary decision diagram with the variables being the
+/// outer inputs in their natural order
+struct Bdd {
+    /// `(variable, low, high)`, the first two being the terminals
+    nodes: Vec<(usize, BddRef, BddRef)>,
+    unique: HashMap<(usize, BddRef, BddRef), BddRef>,
+    ite_cache: HashMap<(BddRef, BddRef, BddRef), BddRef>,
+}
+
+impl Default for Bdd {
+    fn default() -> Bdd {
+        Bdd {
+            nodes: vec![(usize::MAX, FALSE, FALSE), (usize::MAX, TRUE, TRUE)],
+            unique: HashMap::new(),
+            ite_cache: HashMap::new(),
+        }
+    }
+}
+
+impl Bdd {
+    fn node(
+        &mut self,
+        variable: usize,
+        low: BddRef,
+        high: BddRef,
+    ) -> Result<BddRef, EquivalenceError> {
+        if low == high {
+            return Ok(low);
+        }
+        if let Some(node) = self.unique.get(&(variable, low, high)) {
+            return Ok(*node);
+        }
+        if self.nodes.len() >= MAX_BDD_NODES {
+            return Err(EquivalenceError::TooComplex);
+        }
+        self.nodes.push((variable, low, high));
+        self.unique
+            .insert((variable, low, high), self.nodes.len() - 1);
+        Ok(self.nodes.len() - 1)
+    }
+
+    fn variable(&self, node: BddRef) -> usize {
+        self.nodes[node].0
+    }
+
+    /// Low and high cofactors of `node` with respect to `variable`
+    fn cofactors(&self, node: BddRef, variable: usize) -> (BddRef, BddRef) {
+        let (node_variable, low, high) = self.nodes[node];
+        if node_variable == variable {
+            (low, high)
+        } else {
+            (node, node)
+        }
+    }
+
+    /// if `f` then `g` else `h`
+    fn ite(&mut self, f: BddRef, g: BddRef, h: BddRef) -> Result<BddRef, EquivalenceError> {
+        match (f, g, h) {
+            (TRUE, _, _) => return Ok(g),
+            (FALSE, _, _) => return Ok(h),
+            (_, TRUE, FALSE) => return Ok(f),
+            _ if g == h => return Ok(g),
+            _ => {}
+        }
+        if let Some(node) = self.ite_cache.get(&(f, g, h)) {
+            return Ok(*node);
+        }
+        let variable = self.variable(f).min(self.variable(g)).min(self.variable(h));
+        let (f_low, f_high) = self.cofactors(f, variable);
+        let (g_low, g_high) = self.cofactors(g, variable);
+        let (h_low, h_high) = self.cofactors(h, variable);
+        let low = self.ite(f_low, g_low, h_low)?;
+        let high = self.ite(f_high, g_high, h_high)?;
+        let node = self.node(variable, low, high)?;
+        self.ite_cache.insert((f, g, h), node);
+        Ok(node)
+    }
+
+    fn or(&mut self, a: BddRef, b: BddRef) -> Result<BddRef, EquivalenceError> {
+        self.ite(a, TRUE, b)
+    }
+
+    fn xor(&mut self, a: BddRef, b: BddRef) -> Result<BddRef, EquivalenceError> {
+        let not_b = self.ite(b, FALSE, TRUE)?;
+        self.ite(a, not_b, b)
+    }
+
+    /// Diagrams of the outputs of every net, built gate by gate
+    fn outputs(&mut self, network: &LogicNetwork) -> Result<Vec<BddRef>, EquivalenceError> {
+        let mut nets = vec![FALSE; network.net_num];
+        for (variable, net) in network.inputs.iter().enumerate() {
+            let input = self.node(variable, FALSE, TRUE)?;
+            nets[*net] = self.or(nets[*net], input)?;
+        }
+        for gate in &network.gates {
+            let inputs = gate.inputs.iter().map(|net| nets[*net]).collect::<Vec<_>>();
+            for (pin, net) in gate.outputs.iter().enumerate() {
+                let column = gate.table.iter().map(|row| row[pin]).collect::<Vec<_>>();
+                let output = self.table(&column, &inputs)?;
+                nets[*net] = self.or(nets[*net], output)?;
+            }
+        }
+        Ok(network.outputs.iter().map(|net| nets[*net]).collect())
+    }
+
+    /// Shannon expansion of a truth table column over the first input
+    fn table(&mut self, column: &[bool], inputs: &[BddRef]) -> Result<BddRef, EquivalenceError> {
+        match inputs {
+            [] => Ok(if column[0] { TRUE } else { FALSE }),
+            [first, rest @ ..] => {
+                let (low, high) = column.split_at(column.len() / 2);
+                let low = self.table(low, rest)?;
+                let high = self.table(high, rest)?;
+                self.ite(*first, high, low)
+            }
+        }
+    }
+
+    fn satisfying_assignment(&self, mut node: BddRef, variable_num: usize) -> Option<Vec<bool>> {
+        if node == FALSE {
+            return None;
+        }
+        let mut assignment = vec![false; variable_num];
+        while node != TRUE {
+            let (variable, low, high) = self.nodes[node];
+            if low != FALSE {
+                node = low;
+            } else {
+                assignment[variable] = true;
+                node = high;
+            }
+        }
+        Some(assignment)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::logic_network::Gate;
+
+    /// `width` inputs reduced with `table` one gate at a time
+    fn chain(width: usize, table: &[bool; 4]) -> LogicNetwork {
+        let mut gates = vec![];
+        let mut previous = 0;
+        for input in 1..width {
+            gates.push(Gate {
+                instance: format!("#{input}"),
+                table: table.iter().map(|bit| vec![*bit]).collect(),
+                inputs: vec![previous, input],
+                outputs: vec![width + input],
+            });
+            previous = width + input;
+        }
+        LogicNetwork {
+            net_num: 2 * width,
+            inputs: (0..width).collect(),
+            outputs: vec![previous],
+            gates,
+        }
+    }
+
+    const AND: [bool; 4] = [false, false, false, true];
+    const OR: [bool; 4] = [false, true, true, true];
+
+    #[test]
+    fn checks_narrow_circuits_exhaustively() {
+        assert_eq!(
+            chain(3, &AND).check_equivalence(&chain(3, &AND)),
+            Ok(Equivalence::Equivalent)
+        );
+        assert_eq!(
+            chain(3, &AND).check_equivalence(&chain(3, &OR)),
+            Ok(Equivalence::Counterexample {
+                inputs: vec![BitState::Off, BitState::Off, BitState::On],
+                left: vec![BitState::Off],
+                right: vec![BitState::On],
+            })
+        );
+    }
+
+    #[test]
+    fn checks_wide_circuits_with_decision_diagrams() {
+        let mut reversed = chain(24, &AND);
+        reversed.inputs.reverse();
+
+        assert_eq!(
+            chain(24, &AND).check_equivalence(&reversed),
+            Ok(Equivalence::Equivalent)
+        );
+
+        let Ok(Equivalence::Counterexample { left, right, .. }) =
+            chain(24, &AND).check_equivalence(&chain(24, &OR))
+        else {
+            panic!("AND and OR must differ");
+        };
+        assert_ne!(left, right);
+    }
+
+    #[test]
+    fn rejects_mismatching_ports() {
+        assert_eq!(
+            chain(3, &AND).check_equivalence(&chain(4, &AND)),
+            Err(EquivalenceError::PortMismatch {
+                left: (3, 1),
+                right: (4, 1)
+            })
+        );
+    }
+}
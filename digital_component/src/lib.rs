@@ -2,7 +2,31 @@ mod digital_component;
 pub use digital_component::{ComponentLogic, ComponentLogicFactory, DigitalComponent};
 
 mod component_graph;
-pub use component_graph::{ComponentInput, ComponentOutput, Graph, GraphNodeRef, NodeKind};
+pub use component_graph::{ComponentInput, ComponentOutput, Graph, GraphNodeRef, NodeKind, Probe};
+
+mod netlist;
+pub use netlist::{Cell, CellRef, ComponentResolver, NetRef, Netlist};
+
+mod statistics;
+pub use statistics::Statistics;
+
+mod timing;
+pub use timing::{BrokenLoop, CriticalPath, DelayModel, TimingReport, TimingStep};
+
+mod logic_network;
+pub use logic_network::{Gate, GateTable, LogicNetwork, LogicNetworkError};
+
+mod equivalence;
+pub use equivalence::{check_equivalence, Equivalence, EquivalenceError};
+
+mod truth_table;
+pub use truth_table::{RowMismatch, TruthTable, TruthTableError, TruthTableRow};
+
+mod optimize;
+pub use optimize::OptimizationError;
+
+mod clock;
+pub use clock::{Clock, Scheduler};
 
 mod debug_logger;
 
@@ -0,0 +1,215 @@
+use crate::netlist::{NetRef, Netlist};
+use crate::{BitState, DigitalComponent};
+
+/// Primitives with more inputs than that are not tabulated
+pub const MAX_GATE_INPUTS: usize = 8;
+
+/// Outputs of a combinational primitive for every combination of its inputs.
+///
+/// Row `n` holds the outputs for the inputs spelling `n` in binary with
+/// input 0 being the most significant bit
+pub type GateTable = Vec<Vec<bool>>;
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Gate {
+    pub instance: String,
+    pub table: GateTable,
+    pub inputs: Vec<NetRef>,
+    pub outputs: Vec<NetRef>,
+}
+
+impl Gate {
+    pub fn evaluate(&self, inputs: impl Iterator<Item = bool>) -> &[bool] {
+        let row = inputs.fold(0, |row, bit| (row << 1) | bit as usize);
+        &self.table[row]
+    }
+}
+
+#[derive(Debug, PartialEq)]
+pub enum LogicNetworkError {
+    /// the primitives feed back into each other, so the outputs depend on more than the inputs
+    FeedbackLoop {
+        instances: Vec<String>,
+    },
+    TooManyInputs {
+        instance: String,
+    },
+    /// the primitive did not produce a defined output for defined inputs
+    UndefinedOutput {
+        instance: String,
+    },
+}
+
+/// Purely combinational model of a netlist with every primitive replaced by
+/// its truth table.
+///
+/// Unlike the netlist it is cheap to clone and evaluate, which is what the
+/// passes checking a circuit's behaviour need. Only `On` and `Off` are
+/// modelled: undriven nets read as `Off` and nets with several drivers are
+/// `On` if any of the drivers is
+#[derive(Clone, Debug, PartialEq)]
+pub struct LogicNetwork {
+    pub net_num: usize,
+    pub inputs: Vec<NetRef>,
+    pub outputs: Vec<NetRef>,
+    /// gates in an order in which every gate comes after the ones driving it
+    pub gates: Vec<Gate>,
+}
+
+impl LogicNetwork {
+    /// Tabulates every primitive of the netlist by trying all of its input combinations
+    pub fn new(netlist: &mut Netlist) -> Result<LogicNetwork, LogicNetworkError> {
+        if let Some(feedback_loop) = netlist.feedback_loops().first() {
+            return Err(LogicNetworkError::FeedbackLoop {
+                instances: feedback_loop
+                    .iter()
+                    .map(|cell| netlist.cells[*cell].instance.clone())
+                    .collect(),
+            });
+        }
+
+        let order = netlist.topological_order();
+        let mut gates = vec![];
+        for cell_idx in order {
+            let cell = &mut netlist.cells[cell_idx];
+            let table = gate_table(&mut cell.component).map_err(|error| match error {
+                GateTableError::TooManyInputs => LogicNetworkError::TooManyInputs {
+                    instance: cell.instance.clone(),
+                },
+                GateTableError::UndefinedOutput => LogicNetworkError::UndefinedOutput {
+                    instance: cell.instance.clone(),
+                },
+            })?;
+            gates.push(Gate {
+                instance: cell.instance.clone(),
+                table,
+                inputs: cell.inputs.clone(),
+                outputs: cell.outputs.clone(),
+            });
+        }
+
+        Ok(LogicNetwork {
+            net_num: netlist.net_num,
+            inputs: netlist.inputs.clone(),
+            outputs: netlist.outputs.clone(),
+            gates,
+        })
+    }
+
+    pub fn evaluate(&self, inputs: &[bool]) -> Vec<bool> {
+        let mut nets = vec![false; self.net_num];
+        for (net, value) in self.inputs.iter().zip(inputs) {
+            nets[*net] |= value;
+        }
+        for gate in &self.gates {
+            let outputs = gate.evaluate(gate.inputs.iter().map(|net| nets[*net]));
+            for (net, value) in gate.outputs.iter().zip(outputs) {
+                nets[*net] |= value;
+            }
+        }
+        self.outputs.iter().map(|net| nets[*net]).collect()
+    }
+}
+
+pub(crate) enum GateTableError {
+    TooManyInputs,
+    UndefinedOutput,
+}
+
+/// Truth table of a combinational component found by trying all of its input combinations
+pub(crate) fn gate_table(component: &mut DigitalComponent) -> Result<GateTable, GateTableError> {
+    let input_num = component.get_input_num();
+    if input_num > MAX_GATE_INPUTS {
+        return Err(GateTableError::TooManyInputs);
+    }
+    let mut outputs = vec![BitState::Undefined; component.get_output_num()];
+    (0..1_usize << input_num)
+        .map(|row| {
+            let inputs = (0..input_num)
+                .map(|pin| to_bit_state(row >> (input_num - 1 - pin) & 1 == 1))
+                .collect::<Vec<_>>();
+            component.get_func()(&inputs, &mut outputs);
+            outputs
+                .iter()
+                .map(|output| match output {
+                    BitState::On => Ok(true),
+                    BitState::Off => Ok(false),
+                    BitState::Undefined => Err(GateTableError::UndefinedOutput),
+                })
+                .collect()
+        })
+        .collect()
+}
+
+pub fn to_bit_state(bit: bool) -> BitState {
+    if bit {
+        BitState::On
+    } else {
+        BitState::Off
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::*;
+
+    fn nand(input: &[BitState], output: &mut [BitState]) {
+        output[0] = match (input[0], input[1]) {
+            (BitState::On, BitState::On) => BitState::Off,
+            (BitState::Undefined, _) | (_, BitState::Undefined) => BitState::Undefined,
+            _ => BitState::On,
+        }
+    }
+
+    /// AND made of two NANDs, the second one used as an inverter
+    fn and() -> Graph {
+        let mut graph = Graph::default();
+        let first = graph.add_component(DigitalComponent::new(2, 1, Box::new(nand)));
+        let second = graph.add_component(DigitalComponent::new(2, 1, Box::new(nand)));
+        let nodes = [
+            NodeKind::Input(0),
+            NodeKind::Input(1),
+            NodeKind::Output(0),
+            NodeKind::ComponentInput(ComponentInput::new(first, 0)),
+            NodeKind::ComponentInput(ComponentInput::new(first, 1)),
+            NodeKind::ComponentOutput(ComponentOutput::new(first, 0)),
+            NodeKind::ComponentInput(ComponentInput::new(second, 0)),
+            NodeKind::ComponentInput(ComponentInput::new(second, 1)),
+            NodeKind::ComponentOutput(ComponentOutput::new(second, 0)),
+        ]
+        .into_iter()
+        .map(|node| graph.add_node(node))
+        .collect::<Vec<_>>();
+        for (a, b) in [(0, 3), (1, 4), (5, 6), (5, 7), (8, 2)] {
+            graph.add_edge(&nodes[a], &nodes[b]);
+        }
+        graph
+    }
+
+    #[test]
+    fn tabulates_and_evaluates_gates() {
+        let network = LogicNetwork::new(&mut and().flatten(&|_| None)).unwrap();
+
+        assert_eq!(
+            network.gates[0].table,
+            vec![vec![true], vec![true], vec![true], vec![false]]
+        );
+        assert_eq!(network.evaluate(&[true, true]), vec![true]);
+        assert_eq!(network.evaluate(&[true, false]), vec![false]);
+    }
+
+    #[test]
+    fn rejects_feedback_loops() {
+        let mut graph = and();
+        // feed the output back into the first input
+        graph.add_edge(&3, &8);
+
+        assert_eq!(
+            LogicNetwork::new(&mut graph.flatten(&|_| None)),
+            Err(LogicNetworkError::FeedbackLoop {
+                instances: vec!["#0".to_string(), "#1".to_string()]
+            })
+        );
+    }
+}
@@ -0,0 +1,519 @@
+use crate::{BitState, ComponentInput, ComponentOutput, DigitalComponent, Graph, NodeKind};
+use std::collections::{HashMap, HashSet};
+
+pub type NetRef = usize;
+pub type CellRef = usize;
+
+/// Looks up the structure of a named component.
+///
+/// Returns [`None`] for primitives which can not be broken down any further
+pub type ComponentResolver<'a> = dyn Fn(&str) -> Option<Graph> + 'a;
+
+/// A primitive component of a flattened circuit
+pub struct Cell {
+    /// lowercased name of the primitive, e.g. `nand`
+    pub kind: String,
+    /// path of nested instances the primitive came from, e.g. `bit#0/nand#2`
+    pub instance: String,
+    pub component: DigitalComponent,
+    pub inputs: Vec<NetRef>,
+    pub outputs: Vec<NetRef>,
+}
+
+/// A circuit with all the nesting removed: primitive cells connected by nets
+#[derive(Default)]
+pub struct Netlist {
+    pub net_num: usize,
+    pub inputs: Vec<NetRef>,
+    pub outputs: Vec<NetRef>,
+    pub cells: Vec<Cell>,
+}
+
+impl Graph {
+    /// Recursively replaces every component that `resolve` knows the structure of
+    /// with the contents of that structure until only primitives are left
+    pub fn flatten(self, resolve: &ComponentResolver<'_>) -> Netlist {
+        let mut builder = NetlistBuilder::default();
+        let inputs = (0..self.input_num())
+            .map(|_| builder.add_net())
+            .collect::<Vec<_>>();
+        let outputs = (0..self.output_num())
+            .map(|_| builder.add_net())
+            .collect::<Vec<_>>();
+        builder.inline(self, resolve, &inputs, &outputs, "");
+        builder.build(inputs, outputs)
+    }
+}
+
+#[derive(Default)]
+struct NetlistBuilder {
+    net_parents: Vec<NetRef>,
+    cells: Vec<Cell>,
+    /// nets with a pull on them along with its value and instance path
+    pulls: Vec<(NetRef, BitState, String)>,
+}
+
+impl NetlistBuilder {
+    fn add_net(&mut self) -> NetRef {
+        self.net_parents.push(self.net_parents.len());
+        self.net_parents.len() - 1
+    }
+
+    fn find(&mut self, mut net: NetRef) -> NetRef {
+        while self.net_parents[net] != net {
+            self.net_parents[net] = self.net_parents[self.net_parents[net]];
+            net = self.net_parents[net];
+        }
+        net
+    }
+
+    fn union(&mut self, a: NetRef, b: NetRef) {
+        let (a, b) = (self.find(a), self.find(b));
+        self.net_parents[b] = a;
+    }
+
+    fn inline(
+        &mut self,
+        graph: Graph,
+        resolve: &ComponentResolver<'_>,
+        inputs: &[NetRef],
+        outputs: &[NetRef],
+        prefix: &str,
+    ) {
+        let node_sets = graph.find_disjointed_node_sets();
+        let Graph {
+            components, nodes, ..
+        } = graph;
+
+        let mut set_nets: HashMap<usize, NetRef> = HashMap::new();
+        let mut pin_nets = components
+            .iter()
+            .map(|component| {
+                (
+                    vec![None; component.get_input_num()],
+                    vec![None; component.get_output_num()],
+                )
+            })
+            .collect::<Vec<_>>();
+
+        for (node_idx, node) in nodes.iter().enumerate() {
+            let net = match set_nets.get(&node_sets[node_idx]) {
+                Some(net) => *net,
+                None => {
+                    let net = self.add_net();
+                    set_nets.insert(node_sets[node_idx], net);
+                    net
+                }
+            };
+            match node {
+                NodeKind::Input(idx) => self.union(inputs[*idx], net),
+                NodeKind::Output(idx) => self.union(outputs[*idx], net),
+                NodeKind::ComponentInput(pin) => pin_nets[pin.component].0[pin.pin] = Some(net),
+                NodeKind::ComponentOutput(pin) => pin_nets[pin.component].1[pin.pin] = Some(net),
+                NodeKind::Pull(value) => {
+                    self.pulls
+                        .push((net, *value, format!("{prefix}pull#{node_idx}")))
+                }
+                NodeKind::Joint => {}
+            }
+        }
+
+        for (idx, (component, (input_nets, output_nets))) in
+            components.into_iter().zip(pin_nets).enumerate()
+        {
+            let mut connect = |nets: Vec<Option<NetRef>>| {
+                nets.into_iter()
+                    .map(|net| net.unwrap_or_else(|| self.add_net()))
+                    .collect::<Vec<_>>()
+            };
+            let input_nets = connect(input_nets);
+            let output_nets = connect(output_nets);
+
+            let name = component.get_name().unwrap_or("").to_string();
+            let instance = format!("{prefix}{name}#{idx}");
+            match resolve(&name) {
+                Some(definition) => self.inline(
+                    definition,
+                    resolve,
+                    &input_nets,
+                    &output_nets,
+                    &format!("{instance}/"),
+                ),
+                None => self.cells.push(Cell {
+                    kind: name.to_lowercase(),
+                    instance,
+                    component,
+                    inputs: input_nets,
+                    outputs: output_nets,
+                }),
+            }
+        }
+    }
+
+    fn build(mut self, mut inputs: Vec<NetRef>, outputs: Vec<NetRef>) -> Netlist {
+        // a pull on a net nothing else drives is as good as a constant, while
+        // on a driven net it becomes a cell between the drivers and the rest
+        // of the net resolving what they leave undefined
+        let driving_nets = inputs
+            .iter()
+            .chain(self.cells.iter().flat_map(|cell| &cell.outputs))
+            .copied()
+            .collect::<Vec<_>>();
+        let driven = driving_nets
+            .into_iter()
+            .map(|net| self.find(net))
+            .collect::<HashSet<_>>();
+        let mut pulled = HashSet::new();
+        for (net, value, instance) in std::mem::take(&mut self.pulls) {
+            let net = self.find(net);
+            if !pulled.insert(net) {
+                continue;
+            }
+            if !driven.contains(&net) {
+                let component = DigitalComponent::constant(value);
+                self.cells.push(Cell {
+                    kind: component.get_name().unwrap_or_default().to_string(),
+                    instance,
+                    component,
+                    inputs: vec![],
+                    outputs: vec![net],
+                });
+                continue;
+            }
+
+            let drivers = self.add_net();
+            for input in inputs.iter_mut() {
+                if self.find(*input) == net {
+                    *input = drivers;
+                }
+            }
+            for cell_idx in 0..self.cells.len() {
+                for pin in 0..self.cells[cell_idx].outputs.len() {
+                    if self.find(self.cells[cell_idx].outputs[pin]) == net {
+                        self.cells[cell_idx].outputs[pin] = drivers;
+                    }
+                }
+            }
+            let component = DigitalComponent::pull(value);
+            self.cells.push(Cell {
+                kind: component.get_name().unwrap_or_default().to_string(),
+                instance,
+                component,
+                inputs: vec![drivers],
+                outputs: vec![net],
+            });
+        }
+
+        let mut compacted: HashMap<NetRef, NetRef> = HashMap::new();
+        let mut compact = |builder: &mut NetlistBuilder, net: NetRef| {
+            let root = builder.find(net);
+            let next = compacted.len();
+            *compacted.entry(root).or_insert(next)
+        };
+
+        let inputs = inputs
+            .into_iter()
+            .map(|net| compact(&mut self, net))
+            .collect();
+        let outputs = outputs
+            .into_iter()
+            .map(|net| compact(&mut self, net))
+            .collect();
+        let mut cells = std::mem::take(&mut self.cells);
+        for cell in cells.iter_mut() {
+            for net in cell.inputs.iter_mut().chain(cell.outputs.iter_mut()) {
+                *net = compact(&mut self, *net);
+            }
+        }
+
+        Netlist {
+            net_num: compacted.len(),
+            inputs,
+            outputs,
+            cells,
+        }
+    }
+}
+
+impl Netlist {
+    /// Turns the netlist back into a graph, e.g. to simulate it with [`Graph::finalize`]
+    pub fn into_graph(self) -> Graph {
+        let mut graph = Graph::default();
+        let nets = (0..self.net_num)
+            .map(|_| graph.add_node(NodeKind::Joint))
+            .collect::<Vec<_>>();
+        for (idx, net) in self.inputs.iter().enumerate() {
+            let input = graph.add_node(NodeKind::Input(idx));
+            graph.add_edge(&input, &nets[*net]);
+        }
+        for (idx, net) in self.outputs.iter().enumerate() {
+            let output = graph.add_node(NodeKind::Output(idx));
+            graph.add_edge(&output, &nets[*net]);
+        }
+        for cell in self.cells {
+            let component = graph.add_component(cell.component);
+            for (pin, net) in cell.inputs.iter().enumerate() {
+                let input = graph.add_node(NodeKind::ComponentInput(ComponentInput::new(
+                    component, pin,
+                )));
+                graph.add_edge(&input, &nets[*net]);
+            }
+            for (pin, net) in cell.outputs.iter().enumerate() {
+                let output = graph.add_node(NodeKind::ComponentOutput(ComponentOutput::new(
+                    component, pin,
+                )));
+                graph.add_edge(&output, &nets[*net]);
+            }
+        }
+        graph
+    }
+
+    /// For every net the cells and their input pins reading from it
+    pub fn readers(&self) -> Vec<Vec<(CellRef, usize)>> {
+        let mut readers = vec![vec![]; self.net_num];
+        for (cell_idx, cell) in self.cells.iter().enumerate() {
+            for (pin, net) in cell.inputs.iter().enumerate() {
+                readers[*net].push((cell_idx, pin));
+            }
+        }
+        readers
+    }
+
+    /// For every net the cells and their output pins driving it
+    pub fn drivers(&self) -> Vec<Vec<(CellRef, usize)>> {
+        let mut drivers = vec![vec![]; self.net_num];
+        for (cell_idx, cell) in self.cells.iter().enumerate() {
+            for (pin, net) in cell.outputs.iter().enumerate() {
+                drivers[*net].push((cell_idx, pin));
+            }
+        }
+        drivers
+    }
+
+    /// Cells directly depending on the outputs of each cell
+    pub fn successors(&self) -> Vec<Vec<CellRef>> {
+        let readers = self.readers();
+        self.cells
+            .iter()
+            .map(|cell| {
+                let mut successors = cell
+                    .outputs
+                    .iter()
+                    .flat_map(|net| readers[*net].iter().map(|(reader, _)| *reader))
+                    .collect::<Vec<_>>();
+                successors.sort();
+                successors.dedup();
+                successors
+            })
+            .collect()
+    }
+
+    /// Orders cells so that every cell comes after the cells driving its inputs.
+    ///
+    /// Feedback loops make that impossible, so an edge pointing backwards in
+    /// the returned order is a one that closes a loop
+    pub fn topological_order(&self) -> Vec<CellRef> {
+        let successors = self.successors();
+        let readers = self.readers();
+
+        // start from whatever the outer inputs feed so the loops get broken
+        // as close to their natural entry point as possible
+        let roots = self
+            .inputs
+            .iter()
+            .flat_map(|net| readers[*net].iter().map(|(cell, _)| *cell))
+            .chain(0..self.cells.len());
+
+        let mut visited = vec![false; self.cells.len()];
+        let mut post_order = vec![];
+        for root in roots {
+            if visited[root] {
+                continue;
+            }
+            visited[root] = true;
+            let mut stack = vec![(root, 0_usize)];
+            while let Some((cell, next_successor)) = stack.pop() {
+                if let Some(successor) = successors[cell].get(next_successor) {
+                    stack.push((cell, next_successor + 1));
+                    if !visited[*successor] {
+                        visited[*successor] = true;
+                        stack.push((*successor, 0));
+                    }
+                } else {
+                    post_order.push(cell);
+                }
+            }
+        }
+        post_order.reverse();
+        post_order
+    }
+
+    /// Groups of cells that feed back into each other, each group being a
+    /// strongly connected component of the cell graph
+    pub fn feedback_loops(&self) -> Vec<Vec<CellRef>> {
+        let successors = self.successors();
+        let mut predecessors = vec![vec![]; self.cells.len()];
+        for (cell, cell_successors) in successors.iter().enumerate() {
+            for successor in cell_successors {
+                predecessors[*successor].push(cell);
+            }
+        }
+
+        let mut assigned = vec![false; self.cells.len()];
+        let mut loops = vec![];
+        for root in self.topological_order() {
+            if assigned[root] {
+                continue;
+            }
+            assigned[root] = true;
+            let mut group = vec![];
+            let mut stack = vec![root];
+            while let Some(cell) = stack.pop() {
+                group.push(cell);
+                for predecessor in &predecessors[cell] {
+                    if !assigned[*predecessor] {
+                        assigned[*predecessor] = true;
+                        stack.push(*predecessor);
+                    }
+                }
+            }
+            if group.len() > 1 || successors[root].contains(&root) {
+                group.sort();
+                loops.push(group);
+            }
+        }
+        loops
+    }
+
+    /// Human readable name of a net: the outer input or the cell pin driving it
+    pub fn describe_net(&self, net: NetRef) -> String {
+        if let Some(input_idx) = self.inputs.iter().position(|input| *input == net) {
+            return format!("input({input_idx})");
+        }
+        self.drivers()[net]
+            .first()
+            .map(|(cell, pin)| format!("{} output({})", self.cells[*cell].instance, pin))
+            .unwrap_or_else(|| format!("net({net})"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::*;
+
+    fn inverter(input: &[BitState], output: &mut [BitState]) {
+        output[0] = match input[0] {
+            BitState::On => BitState::Off,
+            BitState::Off => BitState::On,
+            BitState::Undefined => BitState::Undefined,
+        }
+    }
+
+    /// Two inverters in a row, the first one being connected straight to the
+    /// outer input and the second one to the outer output
+    fn double_inverter() -> Graph {
+        let mut graph = Graph::default();
+        let first =
+            graph.add_component(DigitalComponent::new(1, 1, Box::new(inverter)).with_name("inv"));
+        let second =
+            graph.add_component(DigitalComponent::new(1, 1, Box::new(inverter)).with_name("inv"));
+        let input = graph.add_node(NodeKind::Input(0));
+        let output = graph.add_node(NodeKind::Output(0));
+        let first_in = graph.add_node(NodeKind::ComponentInput(ComponentInput::new(first, 0)));
+        let first_out = graph.add_node(NodeKind::ComponentOutput(ComponentOutput::new(first, 0)));
+        let second_in = graph.add_node(NodeKind::ComponentInput(ComponentInput::new(second, 0)));
+        let second_out = graph.add_node(NodeKind::ComponentOutput(ComponentOutput::new(second, 0)));
+        graph.add_edge(&input, &first_in);
+        graph.add_edge(&first_out, &second_in);
+        graph.add_edge(&second_out, &output);
+        graph
+    }
+
+    #[test]
+    fn flattens_nested_components() {
+        let mut graph = Graph::default();
+        let buffer = graph
+            .add_component(DigitalComponent::new(1, 1, Box::new(inverter)).with_name("buffer"));
+        let input = graph.add_node(NodeKind::Input(0));
+        let output = graph.add_node(NodeKind::Output(0));
+        let buffer_in = graph.add_node(NodeKind::ComponentInput(ComponentInput::new(buffer, 0)));
+        let buffer_out = graph.add_node(NodeKind::ComponentOutput(ComponentOutput::new(buffer, 0)));
+        graph.add_edge(&input, &buffer_in);
+        graph.add_edge(&buffer_out, &output);
+
+        let netlist = graph.flatten(&|name| Some(double_inverter()).filter(|_| name == "buffer"));
+
+        assert_eq!(
+            netlist
+                .cells
+                .iter()
+                .map(|cell| (cell.instance.as_str(), cell.kind.as_str()))
+                .collect::<Vec<_>>(),
+            vec![("buffer#0/inv#0", "inv"), ("buffer#0/inv#1", "inv")]
+        );
+        assert_eq!(netlist.cells[0].inputs, vec![netlist.inputs[0]]);
+        assert_eq!(netlist.cells[0].outputs, netlist.cells[1].inputs);
+        assert_eq!(netlist.cells[1].outputs, vec![netlist.outputs[0]]);
+        assert_eq!(netlist.net_num, 3);
+    }
+
+    #[test]
+    fn orders_cells_and_finds_loops() {
+        let mut graph = double_inverter();
+        // close the second inverter onto itself
+        let looped = graph.add_component(DigitalComponent::new(1, 1, Box::new(inverter)));
+        let looped_in = graph.add_node(NodeKind::ComponentInput(ComponentInput::new(looped, 0)));
+        let looped_out = graph.add_node(NodeKind::ComponentOutput(ComponentOutput::new(looped, 0)));
+        graph.add_edge(&looped_in, &looped_out);
+
+        let netlist = graph.flatten(&|_| None);
+
+        let order = netlist.topological_order();
+        let position = |cell| order.iter().position(|ordered| *ordered == cell);
+        assert!(position(0) < position(1));
+        assert_eq!(netlist.feedback_loops(), vec![vec![2]]);
+        assert_eq!(netlist.describe_net(netlist.inputs[0]), "input(0)");
+        assert_eq!(netlist.describe_net(netlist.outputs[0]), "inv#1 output(0)");
+    }
+
+    #[test]
+    fn turns_pulls_into_constants_or_resolving_cells() {
+        let mut graph = double_inverter();
+        let input_pull = graph.add_node(NodeKind::Pull(BitState::Off));
+        graph.add_edge(&input_pull, &0);
+        let unused = graph.add_component(DigitalComponent::new(1, 1, Box::new(inverter)));
+        let unused_in = graph.add_node(NodeKind::ComponentInput(ComponentInput::new(unused, 0)));
+        let pull = graph.add_node(NodeKind::Pull(BitState::On));
+        graph.add_edge(&pull, &unused_in);
+
+        let netlist = graph.flatten(&|_| None);
+
+        let constants = netlist
+            .cells
+            .iter()
+            .filter(|cell| cell.inputs.is_empty())
+            .map(|cell| (cell.instance.as_str(), cell.kind.as_str()))
+            .collect::<Vec<_>>();
+        assert_eq!(constants, vec![("pull#8", "vcc")]);
+        let vcc = netlist
+            .cells
+            .iter()
+            .find(|cell| cell.kind == "vcc")
+            .unwrap();
+        assert_eq!(vcc.outputs, netlist.cells[2].inputs);
+
+        // the outer input drives the pull, which drives what read the input
+        let pulldown = netlist
+            .cells
+            .iter()
+            .find(|cell| cell.kind == "pulldown")
+            .unwrap();
+        assert_eq!(pulldown.inputs, netlist.inputs);
+        assert_eq!(pulldown.outputs, netlist.cells[0].inputs);
+        let mut logic = netlist.into_graph().finalize();
+        let mut output = vec![BitState::Undefined];
+        logic(&[BitState::Undefined], &mut output);
+        assert_eq!(output, vec![BitState::Off]);
+    }
+}
@@ -0,0 +1,453 @@
+use crate::equivalence::{Equivalence, EquivalenceError};
+use crate::logic_network::{gate_table, to_bit_state, GateTable, LogicNetwork};
+use crate::netlist::{CellRef, NetRef, Netlist};
+use crate::{BitState, DigitalComponent};
+use std::collections::hash_map::Entry;
+use std::collections::HashMap;
+
+#[derive(Debug, PartialEq)]
+pub enum OptimizationError {
+    /// the optimized circuit produces different outputs for these inputs,
+    /// which means one of the passes is broken
+    NotEquivalent { inputs: Vec<BitState> },
+    /// the equivalence check can not be done, because the circuit is too large
+    /// or has feedback loops
+    Unverified(EquivalenceError),
+}
+
+impl Netlist {
+    /// Runs all the optimization passes until none of them finds anything to improve.
+    ///
+    /// Primitives are assumed to be combinational, their behaviour is found by
+    /// trying all of their input combinations. The result is checked to produce
+    /// the same outputs as the original one for every input, so circuits with
+    /// feedback loops, which the check can not handle, are not optimized
+    pub fn optimize(mut self) -> Result<Netlist, OptimizationError> {
+        let reference = LogicNetwork::new(&mut self).map_err(|error| {
+            OptimizationError::Unverified(EquivalenceError::NotCombinational(error))
+        })?;
+        loop {
+            let changes = self.propagate_constants()
+                + self.eliminate_double_inversions()
+                + self.merge_identical_cells()
+                + self.remove_dead_cells();
+            if changes == 0 {
+                break;
+            }
+        }
+
+        let optimized = LogicNetwork::new(&mut self)
+            .expect("optimizations can not introduce loops or new primitives");
+        match reference.check_equivalence(&optimized) {
+            Ok(Equivalence::Equivalent) => Ok(self),
+            Ok(Equivalence::Counterexample { inputs, .. }) => {
+                Err(OptimizationError::NotEquivalent { inputs })
+            }
+            Err(error) => Err(OptimizationError::Unverified(error)),
+        }
+    }
+
+    /// Replaces cells whose outputs do not depend on their inputs any more,
+    /// because some of them are driven by constants, with constants
+    pub fn propagate_constants(&mut self) -> usize {
+        let mut constants: Vec<Option<bool>> = vec![None; self.net_num];
+        let mut replaced = 0;
+        let mut new_cells = vec![];
+        for cell_idx in self.topological_order() {
+            let Some(table) = self.table(cell_idx) else {
+                continue;
+            };
+            let cell = &self.cells[cell_idx];
+            let known_inputs = cell
+                .inputs
+                .iter()
+                .map(|net| constants[*net])
+                .collect::<Vec<_>>();
+            let outputs = constant_outputs(&table, &known_inputs);
+            for (net, value) in cell.outputs.iter().zip(&outputs) {
+                if value.is_some() && !self.inputs.contains(net) {
+                    constants[*net] = *value;
+                }
+            }
+
+            let is_constant_already = cell.inputs.is_empty() && cell.outputs.len() == 1;
+            if is_constant_already || outputs.iter().any(Option::is_none) {
+                continue;
+            }
+            let cell = &mut self.cells[cell_idx];
+            let instance = cell.instance.clone();
+            let nets = std::mem::take(&mut cell.outputs);
+            cell.inputs.clear();
+            // the cell stays as a placeholder until it is removed as a dead one
+            for (net, value) in nets.into_iter().zip(outputs.into_iter().flatten()) {
+                new_cells.push(constant_cell(&instance, net, value));
+            }
+            replaced += 1;
+        }
+        self.cells.append(&mut new_cells);
+        replaced
+    }
+
+    /// Removes pairs of inverters following each other, connecting whatever
+    /// was reading the second one straight to the input of the first one
+    pub fn eliminate_double_inversions(&mut self) -> usize {
+        let drivers = self.drivers();
+        let inverters = (0..self.cells.len())
+            .map(|cell| self.is_inverter(cell))
+            .collect::<Vec<_>>();
+
+        let mut touched = vec![false; self.cells.len()];
+        let mut replacements = vec![];
+        for second in 0..self.cells.len() {
+            if !inverters[second] || touched[second] {
+                continue;
+            }
+            let middle = self.cells[second].inputs[0];
+            let output = self.cells[second].outputs[0];
+            let [(first, _)] = drivers[middle][..] else {
+                continue;
+            };
+            if !inverters[first] || touched[first] || first == second {
+                continue;
+            }
+            let input = self.cells[first].inputs[0];
+            // the input may be about to be replaced by an earlier pair
+            if drivers[input].iter().any(|(driver, _)| touched[*driver]) {
+                continue;
+            }
+            if input == output
+                || self.inputs.contains(&output)
+                || self.inputs.contains(&middle)
+                || drivers[output].len() != 1
+            {
+                continue;
+            }
+            touched[first] = true;
+            touched[second] = true;
+            replacements.push((second, output, input));
+        }
+
+        for (cell, from, to) in &replacements {
+            self.cells[*cell].inputs.clear();
+            self.cells[*cell].outputs.clear();
+            self.replace_net(*from, *to);
+        }
+        replacements.len()
+    }
+
+    /// Structural hashing: merges cells of the same kind reading the same nets
+    pub fn merge_identical_cells(&mut self) -> usize {
+        let drivers = self.drivers();
+        let mut symmetric: HashMap<String, bool> = HashMap::new();
+        let mut seen: HashMap<(String, Vec<NetRef>), CellRef> = HashMap::new();
+        let mut replacements = vec![];
+        for cell_idx in 0..self.cells.len() {
+            if self.cells[cell_idx].outputs.is_empty() {
+                continue;
+            }
+            let kind = self.cells[cell_idx].kind.clone();
+            if let Entry::Vacant(entry) = symmetric.entry(kind) {
+                entry.insert(
+                    self.table(cell_idx)
+                        .is_some_and(|table| is_symmetric(&table)),
+                );
+            }
+            let cell = &self.cells[cell_idx];
+            let mut inputs = cell.inputs.clone();
+            if symmetric[&cell.kind] {
+                inputs.sort();
+            }
+            let key = (cell.kind.clone(), inputs);
+            match seen.get(&key) {
+                Some(original) => {
+                    let original = &self.cells[*original];
+                    let mergeable = cell
+                        .outputs
+                        .iter()
+                        .zip(&original.outputs)
+                        .all(|(from, to)| {
+                            from == to || (drivers[*from].len() == 1 && !self.inputs.contains(from))
+                        });
+                    if mergeable {
+                        replacements.push((
+                            cell_idx,
+                            cell.outputs
+                                .iter()
+                                .copied()
+                                .zip(original.outputs.iter().copied())
+                                .collect::<Vec<_>>(),
+                        ));
+                    }
+                }
+                None => {
+                    seen.insert(key, cell_idx);
+                }
+            }
+        }
+
+        for (cell, nets) in &replacements {
+            self.cells[*cell].inputs.clear();
+            self.cells[*cell].outputs.clear();
+            for (from, to) in nets {
+                self.replace_net(*from, *to);
+            }
+        }
+        replacements.len()
+    }
+
+    /// Removes cells none of the outer outputs depend on
+    pub fn remove_dead_cells(&mut self) -> usize {
+        let drivers = self.drivers();
+        let mut live_nets = vec![false; self.net_num];
+        let mut live_cells = vec![false; self.cells.len()];
+        let mut to_visit = self.outputs.clone();
+        while let Some(net) = to_visit.pop() {
+            if live_nets[net] {
+                continue;
+            }
+            live_nets[net] = true;
+            for (cell, _) in &drivers[net] {
+                if !live_cells[*cell] {
+                    live_cells[*cell] = true;
+                    to_visit.extend(self.cells[*cell].inputs.iter().copied());
+                }
+            }
+        }
+
+        let cell_num = self.cells.len();
+        let mut live = live_cells.into_iter();
+        self.cells.retain(|_| live.next().unwrap_or(false));
+        cell_num - self.cells.len()
+    }
+
+    fn table(&mut self, cell: CellRef) -> Option<GateTable> {
+        gate_table(&mut self.cells[cell].component).ok()
+    }
+
+    fn is_inverter(&mut self, cell: CellRef) -> bool {
+        let (inputs, outputs) = (&self.cells[cell].inputs, &self.cells[cell].outputs);
+        if inputs.is_empty() || outputs.len() != 1 || inputs.iter().any(|net| *net != inputs[0]) {
+            return false;
+        }
+        self.table(cell)
+            .is_some_and(|table| table[0] == [true] && table[table.len() - 1] == [false])
+    }
+
+    /// Makes everything reading from `from` read from `to`
+    fn replace_net(&mut self, from: NetRef, to: NetRef) {
+        for net in self
+            .cells
+            .iter_mut()
+            .flat_map(|cell| cell.inputs.iter_mut())
+            .chain(self.outputs.iter_mut())
+        {
+            if *net == from {
+                *net = to;
+            }
+        }
+    }
+}
+
+/// Outputs which are the same for every value of the unknown inputs
+fn constant_outputs(table: &GateTable, known_inputs: &[Option<bool>]) -> Vec<Option<bool>> {
+    let input_num = known_inputs.len();
+    let mut outputs: Option<Vec<Option<bool>>> = None;
+    for (row, row_outputs) in table.iter().enumerate() {
+        let matches_known_inputs = known_inputs.iter().enumerate().all(|(pin, known)| {
+            known.is_none_or(|value| (row >> (input_num - 1 - pin) & 1 == 1) == value)
+        });
+        if !matches_known_inputs {
+            continue;
+        }
+        outputs = Some(match outputs {
+            None => row_outputs.iter().map(|value| Some(*value)).collect(),
+            Some(outputs) => outputs
+                .into_iter()
+                .zip(row_outputs)
+                .map(|(output, value)| output.filter(|output| output == value))
+                .collect(),
+        });
+    }
+    outputs.unwrap_or_default()
+}
+
+/// Whether swapping any two inputs keeps the outputs the same
+fn is_symmetric(table: &GateTable) -> bool {
+    let input_num = table.len().trailing_zeros() as usize;
+    (0..table.len()).all(|row| {
+        (0..input_num).all(|pin| {
+            (pin + 1..input_num).all(|other_pin| {
+                let (a, b) = (input_num - 1 - pin, input_num - 1 - other_pin);
+                let swapped = if (row >> a & 1) != (row >> b & 1) {
+                    row ^ (1 << a) ^ (1 << b)
+                } else {
+                    row
+                };
+                table[row] == table[swapped]
+            })
+        })
+    })
+}
+
+fn constant_cell(instance: &str, net: NetRef, value: bool) -> crate::Cell {
+    let component = DigitalComponent::constant(to_bit_state(value));
+    crate::Cell {
+        kind: component.get_name().unwrap_or_default().to_string(),
+        instance: format!("{instance}:constant"),
+        component,
+        inputs: vec![],
+        outputs: vec![net],
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::*;
+
+    fn nand(input: &[BitState], output: &mut [BitState]) {
+        output[0] = match (input[0], input[1]) {
+            (BitState::On, BitState::On) => BitState::Off,
+            (BitState::Undefined, BitState::Undefined) => BitState::Undefined,
+            _ => BitState::On,
+        }
+    }
+
+    /// Builds a graph of NANDs from `(input_a, input_b, output)` triples of
+    /// net names, names starting with `i` and `o` being outer inputs and outputs
+    fn nands(gates: &[(&str, &str, &str)]) -> Graph {
+        let mut graph = Graph::default();
+        let mut nets: HashMap<String, GraphNodeRef> = HashMap::new();
+        let mut net = |graph: &mut Graph, name: &str| {
+            *nets.entry(name.to_string()).or_insert_with(|| {
+                let joint = graph.add_node(NodeKind::Joint);
+                let index = name[1..].parse().unwrap_or_default();
+                let outer = match &name[..1] {
+                    "i" => Some(NodeKind::Input(index)),
+                    "o" => Some(NodeKind::Output(index)),
+                    _ => None,
+                };
+                if let Some(outer) = outer {
+                    let outer = graph.add_node(outer);
+                    graph.add_edge(&outer, &joint);
+                }
+                joint
+            })
+        };
+        for (a, b, output) in gates {
+            let gate =
+                graph.add_component(DigitalComponent::new(2, 1, Box::new(nand)).with_name("nand"));
+            for (kind, name) in [
+                (NodeKind::ComponentInput(ComponentInput::new(gate, 0)), a),
+                (NodeKind::ComponentInput(ComponentInput::new(gate, 1)), b),
+                (
+                    NodeKind::ComponentOutput(ComponentOutput::new(gate, 0)),
+                    output,
+                ),
+            ] {
+                let pin = graph.add_node(kind);
+                let net = net(&mut graph, name);
+                graph.add_edge(&pin, &net);
+            }
+        }
+        graph
+    }
+
+    fn kinds(netlist: &Netlist) -> Vec<&str> {
+        netlist
+            .cells
+            .iter()
+            .map(|cell| cell.kind.as_str())
+            .collect()
+    }
+
+    #[test]
+    fn eliminates_double_inversions() {
+        // i0 -> not -> not -> and with i1
+        let mut netlist = nands(&[
+            ("i0", "i0", "a"),
+            ("a", "a", "b"),
+            ("b", "i1", "c"),
+            ("c", "c", "o0"),
+        ])
+        .flatten(&|_| None);
+
+        assert_eq!(netlist.eliminate_double_inversions(), 1);
+        assert_eq!(netlist.remove_dead_cells(), 2);
+        assert_eq!(
+            netlist.cells[0].inputs,
+            vec![netlist.inputs[0], netlist.inputs[1]]
+        );
+    }
+
+    #[test]
+    fn connects_inputs_straight_to_outputs() {
+        let netlist = nands(&[("i0", "i0", "a"), ("a", "a", "o0")])
+            .flatten(&|_| None)
+            .optimize()
+            .unwrap();
+
+        assert!(netlist.cells.is_empty());
+        let mut logic = netlist.into_graph().finalize();
+        let mut output = vec![BitState::Undefined];
+        logic(&[BitState::On], &mut output);
+        assert_eq!(output, vec![BitState::On]);
+    }
+
+    #[test]
+    fn merges_identical_cells() {
+        // the same NAND twice, once with the inputs swapped
+        let mut netlist =
+            nands(&[("i0", "i1", "a"), ("i1", "i0", "b"), ("a", "b", "o0")]).flatten(&|_| None);
+
+        assert_eq!(netlist.merge_identical_cells(), 1);
+        assert_eq!(netlist.remove_dead_cells(), 1);
+        assert_eq!(netlist.cells[1].inputs[0], netlist.cells[1].inputs[1]);
+    }
+
+    #[test]
+    fn propagates_constants() {
+        let mut graph = nands(&[("i0", "zero", "a"), ("a", "i1", "o0")]);
+        let zero = graph.add_component(DigitalComponent::constant(BitState::Off));
+        let zero_output = graph.add_node(NodeKind::ComponentOutput(ComponentOutput::new(zero, 0)));
+        // node 4 is the joint of the `zero` net
+        graph.add_edge(&zero_output, &4);
+
+        let netlist = graph.flatten(&|_| None).optimize().unwrap();
+
+        // NAND with Off is always On, making the second one an inverter of i1
+        assert_eq!(kinds(&netlist), vec!["nand", "vcc"]);
+        assert_eq!(netlist.cells[0].inputs[1], netlist.inputs[1]);
+    }
+
+    #[test]
+    fn leaves_feedback_loops_alone() {
+        // SR latch of two cross-coupled NANDs
+        let netlist = nands(&[("i0", "o1", "o0"), ("i1", "o0", "o1")]).flatten(&|_| None);
+
+        assert!(matches!(
+            netlist.optimize(),
+            Err(OptimizationError::Unverified(
+                EquivalenceError::NotCombinational(LogicNetworkError::FeedbackLoop { .. })
+            ))
+        ));
+    }
+
+    #[test]
+    fn finds_differences() {
+        let mut and = nands(&[("i0", "i1", "a"), ("a", "a", "o0")]).flatten(&|_| None);
+        let mut or =
+            nands(&[("i0", "i0", "a"), ("i1", "i1", "b"), ("a", "b", "o0")]).flatten(&|_| None);
+
+        assert_eq!(
+            LogicNetwork::new(&mut and)
+                .unwrap()
+                .check_equivalence(&LogicNetwork::new(&mut or).unwrap()),
+            Ok(Equivalence::Counterexample {
+                inputs: vec![BitState::Off, BitState::On],
+                left: vec![BitState::Off],
+                right: vec![BitState::On],
+            })
+        );
+    }
+}
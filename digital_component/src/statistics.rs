@@ -0,0 +1,256 @@
+use crate::netlist::Netlist;
+use std::collections::BTreeMap;
+use std::fmt::{Display, Error, Formatter};
+
+/// Size and shape of a flattened circuit
+#[derive(Debug, PartialEq)]
+pub struct Statistics {
+    /// number of primitives of each kind
+    pub primitives: BTreeMap<String, usize>,
+    /// number of primitives of each kind per top level instance, in the
+    /// order the instances were added to the graph
+    pub instances: Vec<(String, BTreeMap<String, usize>)>,
+    /// longest chain of primitives between an outer input and an outer output
+    pub max_depth: usize,
+    /// largest number of primitive inputs and outer outputs connected to one net
+    pub max_fan_out: usize,
+    /// the net with the largest fan out
+    pub max_fan_out_net: Option<String>,
+    /// instances of the primitives forming each feedback loop
+    pub feedback_loops: Vec<Vec<String>>,
+}
+
+impl Statistics {
+    pub fn new(netlist: &Netlist) -> Statistics {
+        let mut primitives = BTreeMap::new();
+        let mut instances: Vec<(String, BTreeMap<String, usize>)> = vec![];
+        for cell in &netlist.cells {
+            *primitives.entry(cell.kind.clone()).or_insert(0) += 1;
+
+            let top_level_instance = cell.instance.split('/').next().unwrap_or_default();
+            if instances.last().map(|(instance, _)| instance.as_str()) != Some(top_level_instance) {
+                instances.push((top_level_instance.to_string(), BTreeMap::new()));
+            }
+            if let Some((_, counts)) = instances.last_mut() {
+                *counts.entry(cell.kind.clone()).or_insert(0) += 1;
+            }
+        }
+
+        let (max_fan_out, max_fan_out_net) = fan_out(netlist);
+
+        Statistics {
+            primitives,
+            instances,
+            max_depth: max_depth(netlist),
+            max_fan_out,
+            max_fan_out_net,
+            feedback_loops: netlist
+                .feedback_loops()
+                .into_iter()
+                .map(|cells| {
+                    cells
+                        .into_iter()
+                        .map(|cell| netlist.cells[cell].instance.clone())
+                        .collect()
+                })
+                .collect(),
+        }
+    }
+}
+
+fn max_depth(netlist: &Netlist) -> usize {
+    let drivers = netlist.drivers();
+    let order = netlist.topological_order();
+    let mut position = vec![0; netlist.cells.len()];
+    for (idx, cell) in order.iter().enumerate() {
+        position[*cell] = idx;
+    }
+
+    // edges pointing backwards in the topological order close feedback loops and are ignored
+    let mut depth = vec![0; netlist.cells.len()];
+    for cell in order {
+        depth[cell] = 1 + netlist.cells[cell]
+            .inputs
+            .iter()
+            .flat_map(|net| drivers[*net].iter())
+            .filter(|(driver, _)| position[*driver] < position[cell])
+            .map(|(driver, _)| depth[*driver])
+            .max()
+            .unwrap_or(0);
+    }
+
+    netlist
+        .outputs
+        .iter()
+        .flat_map(|net| drivers[*net].iter())
+        .map(|(driver, _)| depth[*driver])
+        .max()
+        .unwrap_or(0)
+}
+
+fn fan_out(netlist: &Netlist) -> (usize, Option<String>) {
+    let mut fan_out = netlist
+        .readers()
+        .iter()
+        .map(|readers| readers.len())
+        .collect::<Vec<_>>();
+    for output in &netlist.outputs {
+        fan_out[*output] += 1;
+    }
+    fan_out
+        .iter()
+        .enumerate()
+        // first of the nets with the largest fan out
+        .rev()
+        .max_by_key(|(_, fan_out)| **fan_out)
+        .map(|(net, fan_out)| (*fan_out, Some(netlist.describe_net(net))))
+        .unwrap_or((0, None))
+}
+
+impl Display for Statistics {
+    fn fmt(&self, f: &mut Formatter) -> Result<(), Error> {
+        let breakdown = |counts: &BTreeMap<String, usize>| {
+            counts
+                .iter()
+                .map(|(kind, count)| format!("{kind}: {count}"))
+                .collect::<Vec<_>>()
+                .join(", ")
+        };
+
+        let instance_width = self
+            .instances
+            .iter()
+            .map(|(instance, _)| instance.chars().count())
+            .chain(self.primitives.keys().map(|kind| kind.chars().count()))
+            .chain(["primitive".len(), "instance".len()])
+            .max()
+            .unwrap_or_default();
+
+        writeln!(f, "{:instance_width$} | count", "primitive")?;
+        writeln!(f, "{:-<instance_width$}-+------", "")?;
+        for (kind, count) in &self.primitives {
+            writeln!(f, "{kind:instance_width$} | {count}")?;
+        }
+        writeln!(
+            f,
+            "{:instance_width$} | {}",
+            "total",
+            self.primitives.values().sum::<usize>()
+        )?;
+        writeln!(f)?;
+
+        writeln!(f, "{:instance_width$} | primitives", "instance")?;
+        writeln!(f, "{:-<instance_width$}-+-----------", "")?;
+        for (instance, counts) in &self.instances {
+            writeln!(f, "{instance:instance_width$} | {}", breakdown(counts))?;
+        }
+        writeln!(f)?;
+
+        writeln!(f, "max logic depth: {}", self.max_depth)?;
+        match &self.max_fan_out_net {
+            Some(net) => writeln!(f, "max fan-out: {} ({})", self.max_fan_out, net)?,
+            None => writeln!(f, "max fan-out: {}", self.max_fan_out)?,
+        }
+        writeln!(f, "feedback loops: {}", self.feedback_loops.len())?;
+        for feedback_loop in &self.feedback_loops {
+            writeln!(f, "  {}", feedback_loop.join(" "))?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::*;
+
+    fn nand(input: &[BitState], output: &mut [BitState]) {
+        output[0] = match (input[0], input[1]) {
+            (BitState::On, BitState::On) => BitState::Off,
+            _ => BitState::On,
+        }
+    }
+
+    /// SR latch made of two cross coupled NANDs with a third one combining their outputs
+    fn latch() -> Graph {
+        let mut graph = Graph::default();
+        let gates = (0..3)
+            .map(|_| {
+                graph.add_component(DigitalComponent::new(2, 1, Box::new(nand)).with_name("NAND"))
+            })
+            .collect::<Vec<_>>();
+        let pins = gates
+            .iter()
+            .map(|gate| {
+                (
+                    graph.add_node(NodeKind::ComponentInput(ComponentInput::new(*gate, 0))),
+                    graph.add_node(NodeKind::ComponentInput(ComponentInput::new(*gate, 1))),
+                    graph.add_node(NodeKind::ComponentOutput(ComponentOutput::new(*gate, 0))),
+                )
+            })
+            .collect::<Vec<_>>();
+        let set = graph.add_node(NodeKind::Input(0));
+        let reset = graph.add_node(NodeKind::Input(1));
+        let output = graph.add_node(NodeKind::Output(0));
+
+        graph.add_edge(&set, &pins[0].0);
+        graph.add_edge(&reset, &pins[1].1);
+        graph.add_edge(&pins[0].2, &pins[1].0);
+        graph.add_edge(&pins[1].2, &pins[0].1);
+        graph.add_edge(&pins[0].2, &pins[2].0);
+        graph.add_edge(&pins[1].2, &pins[2].1);
+        graph.add_edge(&pins[2].2, &output);
+        graph
+    }
+
+    #[test]
+    fn collects_statistics() {
+        let statistics = Statistics::new(&latch().flatten(&|_| None));
+
+        assert_eq!(
+            statistics,
+            Statistics {
+                primitives: [("nand".to_string(), 3)].into_iter().collect(),
+                instances: ["NAND#0", "NAND#1", "NAND#2"]
+                    .iter()
+                    .map(|instance| {
+                        (
+                            instance.to_string(),
+                            [("nand".to_string(), 1)].into_iter().collect(),
+                        )
+                    })
+                    .collect(),
+                max_depth: 3,
+                max_fan_out: 2,
+                max_fan_out_net: Some("NAND#1 output(0)".to_string()),
+                feedback_loops: vec![vec!["NAND#0".to_string(), "NAND#1".to_string()]],
+            }
+        );
+    }
+
+    #[test]
+    fn prints_statistics_table() {
+        let statistics = Statistics::new(&latch().flatten(&|_| None));
+
+        assert_eq!(
+            statistics.to_string(),
+            "\
+            primitive | count\n\
+            ----------+------\n\
+            nand      | 3\n\
+            total     | 3\n\
+            \n\
+            instance  | primitives\n\
+            ----------+-----------\n\
+            NAND#0    | nand: 1\n\
+            NAND#1    | nand: 1\n\
+            NAND#2    | nand: 1\n\
+            \n\
+            max logic depth: 3\n\
+            max fan-out: 2 (NAND#1 output(0))\n\
+            feedback loops: 1\n  \
+            NAND#0 NAND#1\n\
+            "
+        );
+    }
+}
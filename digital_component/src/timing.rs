@@ -0,0 +1,392 @@
+use crate::netlist::{CellRef, NetRef, Netlist};
+use std::collections::HashMap;
+use std::fmt::{Display, Error, Formatter};
+
+/// Propagation delay of every kind of primitive, in arbitrary time units
+pub struct DelayModel {
+    default: u32,
+    delays: HashMap<String, u32>,
+}
+
+impl DelayModel {
+    pub fn new(default: u32) -> DelayModel {
+        DelayModel {
+            default,
+            delays: HashMap::new(),
+        }
+    }
+
+    pub fn with_delay(mut self, kind: &str, delay: u32) -> DelayModel {
+        self.delays.insert(kind.to_lowercase(), delay);
+        self
+    }
+
+    pub fn delay(&self, kind: &str) -> u32 {
+        *self.delays.get(kind).unwrap_or(&self.default)
+    }
+}
+
+impl Default for DelayModel {
+    fn default() -> DelayModel {
+        DelayModel::new(1)
+    }
+}
+
+/// One primitive on a timing path, entered through `input_pin` and left through `output_pin`
+#[derive(Debug, PartialEq)]
+pub struct TimingStep {
+    pub instance: String,
+    pub input_pin: usize,
+    pub output_pin: usize,
+    /// time the signal arrives at the output pin
+    pub arrival: u32,
+}
+
+#[derive(Debug, PartialEq)]
+pub struct CriticalPath {
+    pub input: usize,
+    pub output: usize,
+    pub delay: u32,
+    pub steps: Vec<TimingStep>,
+}
+
+/// A connection that had to be ignored because it closes a combinational loop
+#[derive(Debug, PartialEq)]
+pub struct BrokenLoop {
+    pub from_instance: String,
+    pub from_pin: usize,
+    pub to_instance: String,
+    pub to_pin: usize,
+}
+
+#[derive(Debug, PartialEq)]
+pub struct TimingReport {
+    /// `arrivals[input][output]` is the longest delay from an outer input to
+    /// an outer output, [`None`] if the output does not depend on the input
+    pub arrivals: Vec<Vec<Option<u32>>>,
+    pub critical_path: Option<CriticalPath>,
+    pub broken_loops: Vec<BrokenLoop>,
+}
+
+impl TimingReport {
+    pub fn new(netlist: &Netlist, delays: &DelayModel) -> TimingReport {
+        let analysis = TimingAnalysis::new(netlist, delays);
+
+        let arrivals = netlist
+            .inputs
+            .iter()
+            .map(|input| {
+                let (net_arrivals, _) = analysis.propagate(&[*input]);
+                netlist
+                    .outputs
+                    .iter()
+                    .map(|output| net_arrivals[*output])
+                    .collect()
+            })
+            .collect::<Vec<Vec<_>>>();
+
+        let critical_path = arrivals
+            .iter()
+            .enumerate()
+            .flat_map(|(input, outputs)| {
+                outputs
+                    .iter()
+                    .enumerate()
+                    .filter_map(move |(output, delay)| delay.map(|delay| (input, output, delay)))
+            })
+            // the first of the longest paths
+            .rev()
+            .max_by_key(|(_, _, delay)| *delay)
+            .map(|(input, output, delay)| CriticalPath {
+                input,
+                output,
+                delay,
+                steps: analysis.trace(netlist.inputs[input], netlist.outputs[output]),
+            });
+
+        TimingReport {
+            arrivals,
+            critical_path,
+            broken_loops: analysis.broken_loops(),
+        }
+    }
+
+    /// Shortest clock period at which every output settles within one cycle
+    pub fn min_clock_period(&self) -> u32 {
+        self.critical_path
+            .as_ref()
+            .map(|path| path.delay)
+            .unwrap_or_default()
+    }
+}
+
+struct TimingAnalysis<'a> {
+    netlist: &'a Netlist,
+    delays: Vec<u32>,
+    order: Vec<CellRef>,
+    position: Vec<usize>,
+    drivers: Vec<Vec<(CellRef, usize)>>,
+}
+
+impl<'a> TimingAnalysis<'a> {
+    fn new(netlist: &'a Netlist, delays: &DelayModel) -> TimingAnalysis<'a> {
+        let order = netlist.topological_order();
+        let mut position = vec![0; netlist.cells.len()];
+        for (idx, cell) in order.iter().enumerate() {
+            position[*cell] = idx;
+        }
+        TimingAnalysis {
+            netlist,
+            delays: netlist
+                .cells
+                .iter()
+                .map(|cell| delays.delay(&cell.kind))
+                .collect(),
+            order,
+            position,
+            drivers: netlist.drivers(),
+        }
+    }
+
+    /// Arrival time of every net when all the `sources` switch at time 0.
+    ///
+    /// Also returns for every cell the input pin the latest signal came through
+    fn propagate(&self, sources: &[NetRef]) -> (Vec<Option<u32>>, Vec<Option<usize>>) {
+        let mut net_arrivals = vec![None; self.netlist.net_num];
+        for source in sources {
+            net_arrivals[*source] = Some(0);
+        }
+        let mut latest_inputs = vec![None; self.netlist.cells.len()];
+
+        // nets are only read after all the drivers preceding the reader in the
+        // topological order were processed, which ignores the loop closing ones
+        for cell in &self.order {
+            let latest_input = self.netlist.cells[*cell]
+                .inputs
+                .iter()
+                .enumerate()
+                .filter_map(|(pin, net)| net_arrivals[*net].map(|arrival| (pin, arrival)))
+                .rev()
+                .max_by_key(|(_, arrival)| *arrival);
+            if let Some((pin, arrival)) = latest_input {
+                latest_inputs[*cell] = Some(pin);
+                for output in &self.netlist.cells[*cell].outputs {
+                    let output_arrival = arrival + self.delays[*cell];
+                    if net_arrivals[*output].is_none_or(|current| current < output_arrival) {
+                        net_arrivals[*output] = Some(output_arrival);
+                    }
+                }
+            }
+        }
+        (net_arrivals, latest_inputs)
+    }
+
+    /// Walks back from `output` to `input` along the latest arriving signals
+    fn trace(&self, input: NetRef, output: NetRef) -> Vec<TimingStep> {
+        let (net_arrivals, latest_inputs) = self.propagate(&[input]);
+        let cell_arrival = |cell: CellRef| {
+            latest_inputs[cell].and_then(|pin| {
+                net_arrivals[self.netlist.cells[cell].inputs[pin]]
+                    .map(|arrival| arrival + self.delays[cell])
+            })
+        };
+
+        let mut steps = vec![];
+        let mut net = output;
+        // only ever step back in the topological order so loops can not be followed
+        let mut bound = self.order.len();
+        while net != input {
+            let Some((cell, output_pin)) = self.drivers[net]
+                .iter()
+                .filter(|(cell, _)| self.position[*cell] < bound)
+                .filter(|(cell, _)| cell_arrival(*cell).is_some())
+                .max_by_key(|(cell, _)| cell_arrival(*cell))
+                .copied()
+            else {
+                break;
+            };
+            let input_pin = latest_inputs[cell].unwrap_or_default();
+            steps.push(TimingStep {
+                instance: self.netlist.cells[cell].instance.clone(),
+                input_pin,
+                output_pin,
+                arrival: cell_arrival(cell).unwrap_or_default(),
+            });
+            net = self.netlist.cells[cell].inputs[input_pin];
+            bound = self.position[cell];
+        }
+        steps.reverse();
+        steps
+    }
+
+    fn broken_loops(&self) -> Vec<BrokenLoop> {
+        let mut broken_loops = vec![];
+        for cell in &self.order {
+            for (to_pin, net) in self.netlist.cells[*cell].inputs.iter().enumerate() {
+                for (driver, from_pin) in &self.drivers[*net] {
+                    if self.position[*driver] >= self.position[*cell] {
+                        broken_loops.push(BrokenLoop {
+                            from_instance: self.netlist.cells[*driver].instance.clone(),
+                            from_pin: *from_pin,
+                            to_instance: self.netlist.cells[*cell].instance.clone(),
+                            to_pin,
+                        });
+                    }
+                }
+            }
+        }
+        broken_loops
+    }
+}
+
+impl Display for TimingReport {
+    fn fmt(&self, f: &mut Formatter) -> Result<(), Error> {
+        writeln!(f, "arrival times (input -> output):")?;
+        for (input, outputs) in self.arrivals.iter().enumerate() {
+            let outputs = outputs
+                .iter()
+                .map(|arrival| match arrival {
+                    Some(arrival) => format!("{arrival:>3}"),
+                    None => "  -".to_string(),
+                })
+                .collect::<Vec<_>>()
+                .join(" ");
+            writeln!(f, "  input({input}): {outputs}")?;
+        }
+
+        match &self.critical_path {
+            Some(path) => {
+                writeln!(
+                    f,
+                    "critical path: input({}) -> output({}), delay {}",
+                    path.input, path.output, path.delay
+                )?;
+                for step in &path.steps {
+                    writeln!(
+                        f,
+                        "  {:>4}  {} input({}) -> output({})",
+                        step.arrival, step.instance, step.input_pin, step.output_pin
+                    )?;
+                }
+            }
+            None => writeln!(f, "critical path: none")?,
+        }
+
+        writeln!(f, "broken loops: {}", self.broken_loops.len())?;
+        for broken_loop in &self.broken_loops {
+            writeln!(
+                f,
+                "  {} output({}) -> {} input({})",
+                broken_loop.from_instance,
+                broken_loop.from_pin,
+                broken_loop.to_instance,
+                broken_loop.to_pin
+            )?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::*;
+
+    fn gate(input: &[BitState], output: &mut [BitState]) {
+        output[0] = input[0];
+    }
+
+    /// `slow` sits on input 0 only, `fast` joins it with input 1 and drives the
+    /// only output, while `looped` feeds back into itself from `fast`
+    fn circuit() -> Netlist {
+        let mut graph = Graph::default();
+        let add_gate = |graph: &mut Graph, name: &str| {
+            let gate =
+                graph.add_component(DigitalComponent::new(2, 1, Box::new(gate)).with_name(name));
+            (
+                graph.add_node(NodeKind::ComponentInput(ComponentInput::new(gate, 0))),
+                graph.add_node(NodeKind::ComponentInput(ComponentInput::new(gate, 1))),
+                graph.add_node(NodeKind::ComponentOutput(ComponentOutput::new(gate, 0))),
+            )
+        };
+        let slow = add_gate(&mut graph, "slow");
+        let fast = add_gate(&mut graph, "fast");
+        let looped = add_gate(&mut graph, "looped");
+        let input_0 = graph.add_node(NodeKind::Input(0));
+        let input_1 = graph.add_node(NodeKind::Input(1));
+        let output = graph.add_node(NodeKind::Output(0));
+
+        graph.add_edge(&input_0, &slow.0);
+        graph.add_edge(&input_0, &slow.1);
+        graph.add_edge(&slow.2, &fast.0);
+        graph.add_edge(&input_1, &fast.1);
+        graph.add_edge(&fast.2, &output);
+        graph.add_edge(&fast.2, &looped.0);
+        graph.add_edge(&looped.2, &looped.1);
+        graph.flatten(&|_| None)
+    }
+
+    #[test]
+    fn finds_critical_path() {
+        let report = TimingReport::new(&circuit(), &DelayModel::new(1).with_delay("slow", 5));
+
+        assert_eq!(report.arrivals, vec![vec![Some(6)], vec![Some(1)]]);
+        assert_eq!(
+            report.critical_path,
+            Some(CriticalPath {
+                input: 0,
+                output: 0,
+                delay: 6,
+                steps: vec![
+                    TimingStep {
+                        instance: "slow#0".to_string(),
+                        input_pin: 0,
+                        output_pin: 0,
+                        arrival: 5,
+                    },
+                    TimingStep {
+                        instance: "fast#1".to_string(),
+                        input_pin: 0,
+                        output_pin: 0,
+                        arrival: 6,
+                    },
+                ],
+            })
+        );
+        assert_eq!(report.min_clock_period(), 6);
+    }
+
+    #[test]
+    fn breaks_loops() {
+        let report = TimingReport::new(&circuit(), &DelayModel::default());
+
+        assert_eq!(
+            report.broken_loops,
+            vec![BrokenLoop {
+                from_instance: "looped#2".to_string(),
+                from_pin: 0,
+                to_instance: "looped#2".to_string(),
+                to_pin: 1,
+            }]
+        );
+    }
+
+    #[test]
+    fn prints_report() {
+        let report = TimingReport::new(&circuit(), &DelayModel::new(1).with_delay("slow", 5));
+
+        assert_eq!(
+            report.to_string(),
+            "\
+            arrival times (input -> output):\n  \
+              input(0):   6\n  \
+              input(1):   1\n\
+            critical path: input(0) -> output(0), delay 6\n     \
+                 5  slow#0 input(0) -> output(0)\n     \
+                 6  fast#1 input(0) -> output(0)\n\
+            broken loops: 1\n  \
+              looped#2 output(0) -> looped#2 input(1)\n\
+            "
+        );
+    }
+}
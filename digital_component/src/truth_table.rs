@@ -0,0 +1,380 @@
+use crate::{BitState, ComponentLogic};
+use std::collections::HashMap;
+use std::fmt::{Display, Error, Formatter};
+use std::fs;
+use std::io::ErrorKind;
+use std::path::Path;
+
+/// Components with more inputs than that are not tabulated
+pub const MAX_TRUTH_TABLE_INPUTS: usize = 16;
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct TruthTableRow {
+    pub inputs: Vec<BitState>,
+    pub outputs: Vec<BitState>,
+}
+
+/// Outputs of a component for combinations of its inputs, printed as `1`
+/// for `On`, `0` for `Off` and `x` for `Undefined`
+#[derive(Clone, Debug, PartialEq)]
+pub struct TruthTable {
+    pub inputs: Vec<String>,
+    pub outputs: Vec<String>,
+    pub rows: Vec<TruthTableRow>,
+}
+
+/// A row that is missing from one of the tables or has different outputs in them
+#[derive(Debug, PartialEq)]
+pub struct RowMismatch {
+    pub inputs: Vec<BitState>,
+    pub expected: Option<Vec<BitState>>,
+    pub actual: Option<Vec<BitState>>,
+}
+
+#[derive(Debug, PartialEq)]
+pub enum TruthTableError {
+    TooManyInputs {
+        input_num: usize,
+    },
+    Io(ErrorKind),
+    /// `line` is counted from 1
+    Parse {
+        line: usize,
+        message: String,
+    },
+}
+
+impl TruthTable {
+    /// Feeds the logic every combination of its inputs, counting up in binary
+    /// with input 0 being the most significant bit.
+    ///
+    /// The rows are evaluated one after another on the same logic, so the
+    /// outputs of a component with memory depend on the rows before them
+    pub fn generate(
+        logic: &mut ComponentLogic,
+        input_num: usize,
+        output_num: usize,
+    ) -> Result<TruthTable, TruthTableError> {
+        if input_num > MAX_TRUTH_TABLE_INPUTS {
+            return Err(TruthTableError::TooManyInputs { input_num });
+        }
+        let mut outputs = vec![BitState::Undefined; output_num];
+        let rows = (0..1_usize << input_num)
+            .map(|row| {
+                let inputs = (0..input_num)
+                    .map(|pin| {
+                        if row >> (input_num - 1 - pin) & 1 == 1 {
+                            BitState::On
+                        } else {
+                            BitState::Off
+                        }
+                    })
+                    .collect::<Vec<_>>();
+                logic(&inputs, &mut outputs);
+                TruthTableRow {
+                    inputs,
+                    outputs: outputs.clone(),
+                }
+            })
+            .collect();
+        Ok(TruthTable {
+            inputs: (0..input_num).map(|pin| format!("in{pin}")).collect(),
+            outputs: (0..output_num).map(|pin| format!("out{pin}")).collect(),
+            rows,
+        })
+    }
+
+    /// Reads a table printed by [`TruthTable::to_markdown`] or [`TruthTable::to_csv`],
+    /// the first `input_num` columns being the inputs
+    pub fn parse(text: &str, input_num: usize) -> Result<TruthTable, TruthTableError> {
+        let mut lines = text
+            .lines()
+            .enumerate()
+            .map(|(idx, line)| (idx + 1, line.trim()))
+            .filter(|(_, line)| !line.is_empty())
+            // the line under a markdown header
+            .filter(|(_, line)| !line.chars().all(|c| matches!(c, '|' | '-' | ':' | ' ')));
+
+        let Some((line, header)) = lines.next() else {
+            return Err(TruthTableError::Parse {
+                line: 1,
+                message: "missing header".to_string(),
+            });
+        };
+        let header = split_columns(header);
+        if header.len() < input_num {
+            return Err(TruthTableError::Parse {
+                line,
+                message: format!("expected at least {input_num} columns"),
+            });
+        }
+
+        let rows = lines
+            .map(|(line, text)| {
+                let columns = split_columns(text);
+                if columns.len() != header.len() {
+                    return Err(TruthTableError::Parse {
+                        line,
+                        message: format!("expected {} columns", header.len()),
+                    });
+                }
+                let states = columns
+                    .iter()
+                    .map(|column| match *column {
+                        "1" => Ok(BitState::On),
+                        "0" => Ok(BitState::Off),
+                        "x" | "X" => Ok(BitState::Undefined),
+                        _ => Err(TruthTableError::Parse {
+                            line,
+                            message: format!("`{column}` is not one of 1, 0 or x"),
+                        }),
+                    })
+                    .collect::<Result<Vec<_>, _>>()?;
+                Ok(TruthTableRow {
+                    inputs: states[..input_num].to_vec(),
+                    outputs: states[input_num..].to_vec(),
+                })
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+
+        Ok(TruthTable {
+            inputs: header[..input_num]
+                .iter()
+                .map(|name| name.to_string())
+                .collect(),
+            outputs: header[input_num..]
+                .iter()
+                .map(|name| name.to_string())
+                .collect(),
+            rows,
+        })
+    }
+
+    /// Feeds the logic the inputs of the rows in their order and returns the
+    /// outputs it gives. With a table listing a sequence of inputs and the
+    /// outputs expected after each of them, this checks components with memory
+    pub fn replay(&self, logic: &mut ComponentLogic) -> TruthTable {
+        let mut outputs = vec![BitState::Undefined; self.outputs.len()];
+        let rows = self
+            .rows
+            .iter()
+            .map(|row| {
+                logic(&row.inputs, &mut outputs);
+                TruthTableRow {
+                    inputs: row.inputs.clone(),
+                    outputs: outputs.clone(),
+                }
+            })
+            .collect();
+        TruthTable {
+            inputs: self.inputs.clone(),
+            outputs: self.outputs.clone(),
+            rows,
+        }
+    }
+
+    pub fn load(path: impl AsRef<Path>, input_num: usize) -> Result<TruthTable, TruthTableError> {
+        let text = fs::read_to_string(path).map_err(|error| TruthTableError::Io(error.kind()))?;
+        TruthTable::parse(&text, input_num)
+    }
+
+    /// Rows whose outputs differ from the expected table, matched by their
+    /// inputs so the order of the rows does not matter
+    pub fn mismatches(&self, expected: &TruthTable) -> Vec<RowMismatch> {
+        let expected_outputs = expected
+            .rows
+            .iter()
+            .map(|row| (&row.inputs, &row.outputs))
+            .collect::<HashMap<_, _>>();
+        let actual_outputs = self
+            .rows
+            .iter()
+            .map(|row| (&row.inputs, &row.outputs))
+            .collect::<HashMap<_, _>>();
+
+        let mut mismatches = self
+            .rows
+            .iter()
+            .filter(|row| expected_outputs.get(&row.inputs) != Some(&&row.outputs))
+            .map(|row| RowMismatch {
+                inputs: row.inputs.clone(),
+                expected: expected_outputs
+                    .get(&row.inputs)
+                    .map(|outputs| outputs.to_vec()),
+                actual: Some(row.outputs.clone()),
+            })
+            .collect::<Vec<_>>();
+        mismatches.extend(
+            expected
+                .rows
+                .iter()
+                .filter(|row| !actual_outputs.contains_key(&row.inputs))
+                .map(|row| RowMismatch {
+                    inputs: row.inputs.clone(),
+                    expected: Some(row.outputs.clone()),
+                    actual: None,
+                }),
+        );
+        mismatches
+    }
+
+    pub fn to_markdown(&self) -> String {
+        let header = self.columns().collect::<Vec<_>>();
+        let mut markdown = format!("| {} |\n", header.join(" | "));
+        let separator = header
+            .iter()
+            .map(|name| "-".repeat(name.len()))
+            .collect::<Vec<_>>();
+        markdown += &format!("|-{}-|\n", separator.join("-|-"));
+        for row in &self.rows {
+            let cells = row
+                .inputs
+                .iter()
+                .chain(&row.outputs)
+                .zip(&header)
+                .map(|(state, name)| format!("{:width$}", symbol(state), width = name.len()))
+                .collect::<Vec<_>>();
+            markdown += &format!("| {} |\n", cells.join(" | "));
+        }
+        markdown
+    }
+
+    pub fn to_csv(&self) -> String {
+        let mut csv = self.columns().collect::<Vec<_>>().join(",") + "\n";
+        for row in &self.rows {
+            let cells = row
+                .inputs
+                .iter()
+                .chain(&row.outputs)
+                .map(|state| symbol(state).to_string())
+                .collect::<Vec<_>>();
+            csv += &(cells.join(",") + "\n");
+        }
+        csv
+    }
+
+    fn columns(&self) -> impl Iterator<Item = &str> {
+        self.inputs.iter().chain(&self.outputs).map(String::as_str)
+    }
+}
+
+impl Display for TruthTable {
+    fn fmt(&self, f: &mut Formatter) -> Result<(), Error> {
+        write!(f, "{}", self.to_markdown())
+    }
+}
+
+fn split_columns(line: &str) -> Vec<&str> {
+    if line.starts_with('|') {
+        line.trim_matches('|').split('|').map(str::trim).collect()
+    } else {
+        line.split(',').map(str::trim).collect()
+    }
+}
+
+fn symbol(state: &BitState) -> char {
+    match state {
+        BitState::On => '1',
+        BitState::Off => '0',
+        BitState::Undefined => 'x',
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::BitState::*;
+
+    fn nand(input: &[BitState], output: &mut [BitState]) {
+        output[0] = match (input[0], input[1]) {
+            (On, On) => Off,
+            (Undefined, Undefined) => Undefined,
+            _ => On,
+        }
+    }
+
+    fn nand_table() -> TruthTable {
+        TruthTable::generate(&mut nand, 2, 1).unwrap()
+    }
+
+    #[test]
+    fn prints_markdown_and_csv() {
+        assert_eq!(
+            nand_table().to_markdown(),
+            "\
+            | in0 | in1 | out0 |\n\
+            |-----|-----|------|\n\
+            | 0   | 0   | 1    |\n\
+            | 0   | 1   | 1    |\n\
+            | 1   | 0   | 1    |\n\
+            | 1   | 1   | 0    |\n\
+            "
+        );
+        assert_eq!(
+            nand_table().to_csv(),
+            "in0,in1,out0\n0,0,1\n0,1,1\n1,0,1\n1,1,0\n"
+        );
+    }
+
+    #[test]
+    fn parses_printed_tables() {
+        assert_eq!(
+            TruthTable::parse(&nand_table().to_markdown(), 2),
+            Ok(nand_table())
+        );
+        assert_eq!(
+            TruthTable::parse(&nand_table().to_csv(), 2),
+            Ok(nand_table())
+        );
+        assert_eq!(
+            TruthTable::parse("a,b,y\n0,1,1\n1,2,0\n", 2),
+            Err(TruthTableError::Parse {
+                line: 3,
+                message: "`2` is not one of 1, 0 or x".to_string()
+            })
+        );
+    }
+
+    #[test]
+    fn replays_rows_in_order() {
+        // a latch that keeps its output while input 1 is `Off`
+        let mut stored = Undefined;
+        let mut latch = move |input: &[BitState], output: &mut [BitState]| {
+            if input[1] == On {
+                stored = input[0];
+            }
+            output[0] = stored;
+        };
+        let table = TruthTable::parse("d,e,q\n0,0,x\n1,1,1\n0,0,1\n0,1,0\n", 2).unwrap();
+
+        assert_eq!(table.replay(&mut latch), table);
+    }
+
+    #[test]
+    fn finds_mismatching_rows() {
+        // AND instead of NAND, with the last row missing
+        let expected = TruthTable::parse("a,b,y\n1,0,0\n0,0,0\n0,1,1\n", 2).unwrap();
+
+        assert_eq!(
+            nand_table().mismatches(&expected),
+            vec![
+                RowMismatch {
+                    inputs: vec![Off, Off],
+                    expected: Some(vec![Off]),
+                    actual: Some(vec![On]),
+                },
+                RowMismatch {
+                    inputs: vec![On, Off],
+                    expected: Some(vec![Off]),
+                    actual: Some(vec![On]),
+                },
+                RowMismatch {
+                    inputs: vec![On, On],
+                    expected: None,
+                    actual: Some(vec![Off]),
+                },
+            ]
+        );
+        assert!(nand_table().mismatches(&nand_table()).is_empty());
+    }
+}
@@ -50,9 +50,9 @@ pub fn scan_box(
         });
     }
 
-    let next_char = input[next_position.line]
-        .chars()
-        .nth(next_position.column)
+    let next_char = input
+        .get(next_position.line)
+        .and_then(|line| line.chars().nth(next_position.column))
         .ok_or(ParseError::EndOfInput)?;
     let mut parse_later = vec![];
     match next_char {
@@ -0,0 +1,79 @@
+use crate::{Position, WireMap};
+use digital_component::{BitState, GraphNodeRef};
+
+const ON: &str = "\x1b[1;32m";
+const OFF: &str = "\x1b[34m";
+const UNDEFINED: &str = "\x1b[33m";
+const RESET: &str = "\x1b[0m";
+
+/// Draws the diagram with ANSI colours on its wires and box pins, bright
+/// green for `On`, blue for `Off` and yellow for `Undefined`, taking the
+/// states from `state` of the graph nodes the cells are connected to
+pub fn colorize(source: &str, wires: &WireMap, state: impl Fn(GraphNodeRef) -> BitState) -> String {
+    let mut colorized = String::new();
+    for (line_num, line) in source.lines().enumerate() {
+        let mut current_color = None;
+        for (column, c) in line.chars().enumerate() {
+            let color =
+                wires
+                    .get(&Position::new(line_num, column))
+                    .map(|node| match state(*node) {
+                        BitState::On => ON,
+                        BitState::Off => OFF,
+                        BitState::Undefined => UNDEFINED,
+                    });
+            if color != current_color {
+                colorized += color.unwrap_or(RESET);
+                current_color = color;
+            }
+            colorized.push(c);
+        }
+        if current_color.is_some() {
+            colorized += RESET;
+        }
+        colorized.push('\n');
+    }
+    colorized
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parse_with_wires;
+    use digital_component::ComponentLogicFactory;
+    use std::collections::HashMap;
+
+    fn not(input: &[BitState], output: &mut [BitState]) {
+        output[0] = match input[0] {
+            BitState::On => BitState::Off,
+            BitState::Off => BitState::On,
+            BitState::Undefined => BitState::Undefined,
+        }
+    }
+
+    #[test]
+    fn colours_wires_by_state() {
+        let diagram = "
+           ┏━━━┓
+        ─┬─┨not┠──
+         │ ┗━━━┛
+         └──
+        ";
+        let mut functions: HashMap<&str, Box<ComponentLogicFactory>> = HashMap::new();
+        functions.insert("not", Box::new(|| Box::new(not)));
+        let (graph, wires) = parse_with_wires(diagram, &functions).unwrap();
+        let (mut logic, probe) = graph.finalize_probed();
+        let mut outputs = vec![BitState::Undefined; 2];
+        logic(&[BitState::On], &mut outputs);
+
+        assert_eq!(
+            colorize(diagram, &wires, |node| probe.state(node)),
+            format!(
+                "\n           ┏━━━┓\n        \
+                {ON}─┬─┨{RESET}not{OFF}┠──{RESET}\n         \
+                {ON}│{RESET} ┗━━━┛\n         \
+                {ON}└──{RESET}\n        \n"
+            )
+        );
+    }
+}
@@ -0,0 +1,149 @@
+use crate::render::render_labelled;
+use crate::types::Node;
+use crate::types::ParseError;
+use crate::{build_node_graph, clock_inputs, names, scan};
+use digital_component::{BitState, ComponentLogicFactory};
+use std::collections::HashMap;
+
+/// Redraws a diagram the way [`render`](crate::render()) lays components out: boxes sized
+/// to their names, the boxes of every column ordered to keep the crossings
+/// down, straight wires between pins facing each other and tracks under the
+/// boxes for the other wires.
+///
+/// Only the topology is kept, along with the clocks and the tunnels the
+/// wires are labelled with, so anything not connected to the outer inputs and the other text
+/// outside the boxes is dropped. Formatting a formatted diagram
+/// gives it back unchanged
+pub fn format(source: &str) -> Result<String, ParseError> {
+    let names = names(source);
+    // only the structure is drawn, so the components need no logic
+    let mut comp_funcs: HashMap<&str, Box<ComponentLogicFactory>> = HashMap::new();
+    for name in &names {
+        comp_funcs.insert(
+            name,
+            Box::new(|| Box::new(|_: &[BitState], _: &mut [BitState]| {})),
+        );
+    }
+    let nodes = scan(source)?;
+    let clocks = clock_inputs(&nodes);
+    let tunnels = nodes
+        .iter()
+        .filter_map(|node| match node {
+            Node::Tunnel { position, name } => Some((name.clone(), position.clone())),
+            _ => None,
+        })
+        .collect::<Vec<_>>();
+    let (graph, position_to_node) = build_node_graph(nodes, &comp_funcs)?;
+    let tunnels = tunnels
+        .into_iter()
+        .map(|(name, position)| (name, position_to_node[&position]))
+        .collect::<Vec<_>>();
+    Ok(render_labelled(graph, &clocks, &tunnels))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parse;
+
+    fn nand(input: &[BitState], output: &mut [BitState]) {
+        output[0] = match (input[0], input[1]) {
+            (BitState::On, BitState::On) => BitState::Off,
+            (BitState::Undefined, BitState::Undefined) => BitState::Undefined,
+            _ => BitState::On,
+        }
+    }
+
+    /// SR latch with the wires taking the long way round
+    const LATCH: &str = "
+             ┏━━━━━━━━━━━━━━┓
+      ───────┨              ┃
+             ┃     NAND     ┠──┬──────────────
+           ┌─┨              ┃  │
+           │ ┗━━━━━━━━━━━━━━┛  │
+           │   ┌───────────────┘
+           │   │
+           │   │  ┏━━━━━━┓
+           │   └──┨      ┠──┐
+           │      ┃ NAND ┃  │
+      ─────┼──────┨      ┃  │
+           │      ┗━━━━━━┛  │
+           └────────────────┘
+        ";
+
+    /// The same SR latch with its feedback wires labelled
+    const TUNNELLED_LATCH: &str = "
+             ┏━━━━━━┓
+  ───────────┨ NAND ┠──┬──▶q
+        nq▶──┨      ┃  └──────
+             ┗━━━━━━┛
+             ┏━━━━━━┓
+         q▶──┨ NAND ┠──┬──▶nq
+  ───────────┨      ┃  └──────
+             ┗━━━━━━┛
+        ";
+
+    /// Checks the diagrams behave the same while the latch is set and reset
+    fn assert_same_behaviour(source: &str, formatted: &str) {
+        let mut functions: HashMap<&str, Box<ComponentLogicFactory>> = HashMap::new();
+        functions.insert("NAND", Box::new(|| Box::new(nand)));
+        let original = parse(source, &functions).unwrap();
+        let mut expected = vec![BitState::Undefined; original.output_num()];
+        let mut actual = expected.clone();
+        let mut original = original.finalize();
+        let mut reformatted = parse(formatted, &functions).unwrap().finalize();
+
+        for inputs in [[0, 1], [1, 1], [1, 0], [1, 1], [0, 1], [1, 1]] {
+            let inputs = inputs.map(|bit| {
+                if bit == 1 {
+                    BitState::On
+                } else {
+                    BitState::Off
+                }
+            });
+            original(&inputs, &mut expected);
+            reformatted(&inputs, &mut actual);
+            assert_eq!(expected, actual, "{formatted}");
+        }
+    }
+
+    #[test]
+    fn keeps_the_topology() {
+        assert_same_behaviour(LATCH, &format(LATCH).unwrap());
+    }
+
+    #[test]
+    fn keeps_tunnels() {
+        let formatted = format(TUNNELLED_LATCH).unwrap();
+
+        assert_same_behaviour(TUNNELLED_LATCH, &formatted);
+        for label in ["──▶q", "q▶──", "──▶nq", "nq▶──"] {
+            assert!(formatted.contains(label), "{formatted}");
+        }
+        assert_eq!(format(&formatted), Ok(formatted.clone()), "{formatted}");
+    }
+
+    #[test]
+    fn keeps_clocks() {
+        let clocked = "
+          ┏━━━━━━┓
+ ~clk─────┨ NAND ┠──
+ ─────────┨      ┃
+          ┗━━━━━━┛
+        ";
+        let formatted = format(clocked).unwrap();
+
+        assert_same_behaviour(clocked, &formatted);
+        assert!(formatted.contains("~clk──"), "{formatted}");
+        assert_eq!(crate::clocks(&formatted), crate::clocks(clocked));
+        assert_eq!(format(&formatted), Ok(formatted.clone()), "{formatted}");
+    }
+
+    #[test]
+    fn is_idempotent() {
+        let formatted = format(LATCH).unwrap();
+
+        assert_eq!(format(&formatted), Ok(formatted.clone()), "{formatted}");
+        assert!(!formatted.contains("━━━━━━━━━━"));
+    }
+}
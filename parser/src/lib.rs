@@ -1,19 +1,32 @@
 use std::collections::{HashMap, VecDeque};
 
 mod types;
-use types::{Direction, Node, ParseError, ParsingMode, Symbol};
+pub use types::{DiagramBox, ParseError, Position};
+use types::{Direction, Node, ParsingMode, Symbol};
 
 mod wires;
 
 mod r#box;
 
 mod structural_scan;
-use structural_scan::{find_dangling_wires, structural_scan};
+use structural_scan::{find_dangling_wires, find_drivers, find_tunnels, structural_scan};
 
 mod node_graph;
-use node_graph::build_node_graph;
+use node_graph::{build_node_graph, clock_inputs, find_boxes};
 
-use digital_component::{ComponentLogicFactory, Graph};
+mod render;
+pub use render::render;
+
+mod format;
+pub use format::format;
+
+mod colorize;
+pub use colorize::colorize;
+
+mod svg;
+pub use svg::svg;
+
+use digital_component::{ComponentLogicFactory, Graph, GraphNodeRef};
 
 pub fn parse(
     source: &str,
@@ -23,27 +36,90 @@ pub fn parse(
     let result = scan(source)?;
 
     // no build an graph where wires from previous stage are edges and the rest is nodes
-    build_node_graph(result, comp_funcs)
+    Ok(build_node_graph(result, comp_funcs)?.0)
+}
+
+/// Graph node every wire cell and box pin of a diagram is connected to
+pub type WireMap = HashMap<Position, GraphNodeRef>;
+
+/// Like [`parse`], also telling which net every wire cell of the diagram is on
+/// so the diagram can be drawn with the signals of a running simulation
+pub fn parse_with_wires(
+    source: &str,
+    comp_funcs: &HashMap<&str, Box<ComponentLogicFactory>>,
+) -> Result<(Graph, WireMap), ParseError> {
+    let (nodes, wire_cells) = scan_with_wire_cells(source)?;
+    let (graph, position_to_node) = build_node_graph(nodes, comp_funcs)?;
+    let wires = wire_cells
+        .into_iter()
+        .filter_map(|(cell, wire_start)| {
+            position_to_node.get(&wire_start).map(|node| (cell, *node))
+        })
+        .chain(position_to_node.clone())
+        .collect();
+    Ok((graph, wires))
+}
+
+/// Names written in the diagram, the labels of its boxes among them, to
+/// look up the component functions [`parse`] needs
+pub fn names(source: &str) -> Vec<String> {
+    let lines: Vec<_> = source.lines().collect();
+    scan_for_text_tokens(&lines)
+        .into_iter()
+        .filter_map(|node| match node {
+            Node::Text { value, .. } => Some(value),
+            _ => None,
+        })
+        .collect()
+}
+
+/// Boxes of the diagram in the order of the components of the graph [`parse`]
+/// builds from it
+pub fn boxes(source: &str) -> Result<Vec<DiagramBox>, ParseError> {
+    Ok(find_boxes(scan(source)?))
+}
+
+/// Clocks the diagram refers to by name like `~clk──`, each along with the
+/// index of the input of the graph [`parse`] builds that it drives
+pub fn clocks(source: &str) -> Result<Vec<(String, usize)>, ParseError> {
+    Ok(clock_inputs(&scan(source)?))
 }
 
 fn scan(source: &str) -> Result<Vec<Node>, ParseError> {
+    Ok(scan_with_wire_cells(source)?.0)
+}
+
+fn scan_with_wire_cells(
+    source: &str,
+) -> Result<(Vec<Node>, HashMap<Position, Position>), ParseError> {
     // convert string to alighned 2d array
     let lines: Vec<_> = source.lines().collect();
     // find inputs as dangling -.*
     let (dangling_inputs, dangling_outputs) = find_dangling_wires(&lines);
+    let (mut tunnels, tunnel_wire_starts) = find_tunnels(&lines);
+    let (mut drivers, driver_wire_starts) = find_drivers(&lines);
 
     // put them in a stack or a queue and start untangling according to rules
     let mut symbols: VecDeque<Symbol> = VecDeque::new();
-    dangling_inputs.iter().for_each(|input_position| {
-        symbols.push_back(Symbol::new(
-            input_position.clone(),
-            '─',
-            &Direction::Right,
-            ParsingMode::Wire,
-        ))
-    });
+    // the wires leaving labels like `clk▶──` or `VCC──` are followed the same
+    // as the inputs
+    dangling_inputs
+        .iter()
+        .chain(&tunnel_wire_starts)
+        .chain(&driver_wire_starts)
+        .for_each(|wire_start| {
+            symbols.push_back(Symbol::new(
+                wire_start.clone(),
+                '─',
+                &Direction::Right,
+                ParsingMode::Wire,
+            ))
+        });
     let mut nodes = scan_for_text_tokens(&lines);
-    nodes.append(&mut structural_scan(&lines, symbols)?);
+    let (mut structure, wire_cells) = structural_scan(&lines, symbols)?;
+    nodes.append(&mut structure);
+    nodes.append(&mut tunnels);
+    nodes.append(&mut drivers);
     nodes.append(
         &mut dangling_inputs
             .into_iter()
@@ -56,14 +132,17 @@ fn scan(source: &str) -> Result<Vec<Node>, ParseError> {
             .map(|position| Node::Output { position })
             .collect(),
     );
-    Ok(nodes)
+    Ok((nodes, wire_cells))
 }
 
+/// Text tokens are names made of letters, digits and `_` starting with a letter,
+/// optionally followed by arguments like `decoder(3)` or `mux(2,8)`
 fn scan_for_text_tokens(input: &[&str]) -> Vec<Node> {
     let mut nodes = vec![];
     let mut current_state = TextTokenFSMState::Junk;
     let mut current_token = String::new();
     let mut token_start = 0;
+    let mut name_end = 0;
     for (line_num, line) in input.iter().enumerate() {
         for (char_num, c) in line.chars().chain([' ']).enumerate() {
             match (c, &current_state) {
@@ -75,6 +154,24 @@ fn scan_for_text_tokens(input: &[&str]) -> Vec<Node> {
                 ('a'..='z' | 'A'..='Z' | '0'..='9' | '_', TextTokenFSMState::Text) => {
                     current_token.push(c);
                 }
+                ('(', TextTokenFSMState::Text) => {
+                    current_state = TextTokenFSMState::Arguments;
+                    name_end = char_num;
+                    current_token.push(c);
+                }
+                ('0'..='9' | ',', TextTokenFSMState::Arguments) => {
+                    current_token.push(c);
+                }
+                (')', TextTokenFSMState::Arguments) => {
+                    current_token.push(c);
+                    nodes.push(Node::Text {
+                        line: line_num,
+                        position: token_start..char_num + 1,
+                        value: current_token,
+                    });
+                    current_state = TextTokenFSMState::Junk;
+                    current_token = String::new();
+                }
                 (_, TextTokenFSMState::Text) => {
                     nodes.push(Node::Text {
                         line: line_num,
@@ -84,6 +181,17 @@ fn scan_for_text_tokens(input: &[&str]) -> Vec<Node> {
                     current_state = TextTokenFSMState::Junk;
                     current_token = String::new();
                 }
+                // not an argument list after all, only the name is the token
+                (_, TextTokenFSMState::Arguments) => {
+                    current_token.truncate(name_end - token_start);
+                    nodes.push(Node::Text {
+                        line: line_num,
+                        position: token_start..name_end,
+                        value: current_token,
+                    });
+                    current_state = TextTokenFSMState::Junk;
+                    current_token = String::new();
+                }
                 _ => {}
             }
         }
@@ -94,6 +202,7 @@ fn scan_for_text_tokens(input: &[&str]) -> Vec<Node> {
 enum TextTokenFSMState {
     Junk,
     Text,
+    Arguments,
 }
 
 #[cfg(test)]
@@ -132,6 +241,34 @@ mod tests {
         ]);
     }
 
+    #[test]
+    fn finds_boxes_in_component_order() {
+        let test_circuit = "
+                      ┏━━━━━┓
+              ───┬────┨ not ┠──
+                 │    ┗━━━━━┛
+                 │  ┏━━━━━━━━━━━━┓
+                 └──┨ decoder(1) ┠──
+                    ┃            ┠──
+                    ┗━━━━━━━━━━━━┛
+    ";
+        assert_eq!(
+            boxes(test_circuit).unwrap(),
+            vec![
+                DiagramBox {
+                    name: "not".to_string(),
+                    top_left: Position::new(1, 22),
+                    bottom_right: Position::new(3, 28),
+                },
+                DiagramBox {
+                    name: "decoder(1)".to_string(),
+                    top_left: Position::new(4, 20),
+                    bottom_right: Position::new(7, 33),
+                },
+            ]
+        );
+    }
+
     #[test]
     fn finds_text_tokens() {
         let test_circuit = "
@@ -140,7 +277,7 @@ mod tests {
                  ┃   token2┃
                  ┗━━━━━━━━━┛
                tok_en3;4token4
-token5              %$#
+token5              %$#  decoder(3) mux(2,8) not(x)
     ";
         let nodes = scan(test_circuit).unwrap();
         assert_that!(nodes).contains(&Node::Box {
@@ -174,5 +311,20 @@ token5              %$#
             position: 0..6,
             value: String::from("token5"),
         });
+        assert_that!(nodes).contains(&Node::Text {
+            line: 6,
+            position: 25..35,
+            value: String::from("decoder(3)"),
+        });
+        assert_that!(nodes).contains(&Node::Text {
+            line: 6,
+            position: 36..44,
+            value: String::from("mux(2,8)"),
+        });
+        assert_that!(nodes).contains(&Node::Text {
+            line: 6,
+            position: 45..48,
+            value: String::from("not"),
+        });
     }
 }
@@ -1,4 +1,4 @@
-use crate::types::{Node, ParseError, Position};
+use crate::types::{DiagramBox, Node, ParseError, Position};
 use core::ops::Range;
 use digital_component::{
     ComponentInput, ComponentLogicFactory, ComponentOutput, DigitalComponent, Graph, GraphNodeRef,
@@ -7,32 +7,57 @@ use digital_component::{
 use std::collections::HashMap;
 
 fn create_component_from_text_nodes(
+    top_left: &Position,
     text_nodes: Vec<&Node>,
+    input_num: usize,
+    output_num: usize,
     comp_funcs: &HashMap<&str, Box<ComponentLogicFactory>>,
-) -> DigitalComponent {
-    if let Node::Text { value, .. } = text_nodes[0] {
-        let comp_logic = (*comp_funcs.get(&value[..]).unwrap())();
-        DigitalComponent::new(2, 1, Box::new(comp_logic))
+) -> Result<DigitalComponent, ParseError> {
+    if let Some(Node::Text {
+        value,
+        line,
+        position,
+    }) = text_nodes.first()
+    {
+        let comp_func = comp_funcs
+            .get(&value[..])
+            .ok_or_else(|| ParseError::UnknownComponent {
+                name: value.clone(),
+                position: Position::new(*line, position.start),
+            })?;
+        Ok(DigitalComponent::new(input_num, output_num, Box::new(comp_func())).with_name(value))
     } else {
-        panic!("Expected function name got {:?}", text_nodes[0]);
+        Err(ParseError::UnnamedBox {
+            position: top_left.clone(),
+        })
     }
 }
 
+/// Builds the graph along with the graph node at the position of every
+/// box pin, outer input and output and wire joint
 pub fn build_node_graph(
     mut nodes: Vec<Node>,
     comp_funcs: &HashMap<&str, Box<ComponentLogicFactory>>,
-) -> Result<Graph, ParseError> {
+) -> Result<(Graph, HashMap<Position, GraphNodeRef>), ParseError> {
     let mut graph = Graph::default();
     let mut position_to_node: HashMap<&Position, GraphNodeRef> = HashMap::default();
     nodes.sort_by_key(|node| node.sort_key());
 
     for (box_node, text_nodes) in correlate_boxes_and_text(&nodes) {
         if let Node::Box {
-            inputs, outputs, ..
+            top_left,
+            inputs,
+            outputs,
+            ..
         } = box_node
         {
-            let component =
-                graph.add_component(create_component_from_text_nodes(text_nodes, comp_funcs));
+            let component = graph.add_component(create_component_from_text_nodes(
+                top_left,
+                text_nodes,
+                inputs.len(),
+                outputs.len(),
+                comp_funcs,
+            )?);
 
             for (idx, input_position) in inputs.iter().enumerate() {
                 let node_ref = graph.add_node(NodeKind::ComponentInput(ComponentInput::new(
@@ -54,11 +79,45 @@ pub fn build_node_graph(
 
     insert_inputs_outputs_into_graph(&mut graph, &nodes, &mut position_to_node);
 
+    insert_tunnels_into_graph(&mut graph, &nodes, &mut position_to_node)?;
+
+    insert_drivers_into_graph(&mut graph, &nodes, &mut position_to_node);
+
     insert_joints_into_graph(&mut graph, &nodes, &mut position_to_node)?;
 
     add_edges(&mut graph, &nodes, &mut position_to_node);
 
-    Ok(graph)
+    let position_to_node = position_to_node
+        .into_iter()
+        .map(|(position, node)| (position.clone(), node))
+        .collect();
+    Ok((graph, position_to_node))
+}
+
+/// The labelled boxes in the order [`build_node_graph`] adds them to the graph
+/// as components
+pub fn find_boxes(mut nodes: Vec<Node>) -> Vec<DiagramBox> {
+    nodes.sort_by_key(|node| node.sort_key());
+    correlate_boxes_and_text(&nodes)
+        .into_iter()
+        .filter_map(
+            |(box_node, text_nodes)| match (box_node, text_nodes.first()) {
+                (
+                    Node::Box {
+                        top_left,
+                        bottom_right,
+                        ..
+                    },
+                    Some(Node::Text { value, .. }),
+                ) => Some(DiagramBox {
+                    name: value.clone(),
+                    top_left: top_left.clone(),
+                    bottom_right: bottom_right.clone(),
+                }),
+                _ => None,
+            },
+        )
+        .collect()
 }
 
 fn correlate_boxes_and_text(nodes: &Vec<Node>) -> Vec<(&Node, Vec<&Node>)> {
@@ -115,6 +174,88 @@ fn insert_inputs_outputs_into_graph<'a>(
             _ => {}
         };
     }
+    let clocks = clock_inputs(nodes);
+    for node in nodes {
+        if let Node::Clock { position, name } = node {
+            let (_, input_idx) = clocks.iter().find(|(clock, _)| clock == name).unwrap();
+            position_to_node.insert(position, graph.add_node(NodeKind::Input(*input_idx)));
+        }
+    }
+}
+
+/// Names of the clocks of the diagram along with the outer inputs they
+/// drive, the ones after the dangling inputs in the order the clocks first
+/// appear in
+pub fn clock_inputs(nodes: &[Node]) -> Vec<(String, usize)> {
+    let mut nodes = nodes.iter().collect::<Vec<_>>();
+    nodes.sort_by_key(|node| node.sort_key());
+    let input_num = nodes
+        .iter()
+        .filter(|node| matches!(node, Node::Input { .. }))
+        .count();
+    let mut clocks: Vec<(String, usize)> = vec![];
+    for node in nodes {
+        if let Node::Clock { name, .. } = node {
+            if !clocks.iter().any(|(clock, _)| clock == name) {
+                clocks.push((name.clone(), input_num + clocks.len()));
+            }
+        }
+    }
+    clocks
+}
+
+/// Merges the wires with the same label into one net by giving all of their
+/// labelled ends the same joint
+fn insert_tunnels_into_graph<'a>(
+    graph: &mut Graph,
+    nodes: &'a [Node],
+    position_to_node: &mut HashMap<&'a Position, GraphNodeRef>,
+) -> Result<(), ParseError> {
+    let mut nets: HashMap<&str, (GraphNodeRef, usize)> = HashMap::default();
+    for node in nodes {
+        if let Node::Tunnel { position, name } = node {
+            let (joint, count) = nets
+                .entry(name)
+                .or_insert_with(|| (graph.add_node(NodeKind::Joint), 0));
+            *count += 1;
+            position_to_node.insert(position, *joint);
+        }
+    }
+    // the first wire with a label that is used only once is reported
+    for node in nodes {
+        if let Node::Tunnel { position, name } = node {
+            if nets[name.as_str()].1 == 1 {
+                return Err(ParseError::UnmatchedTunnel {
+                    name: name.clone(),
+                    position: position.clone(),
+                });
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Constants become components after the ones of the boxes, so the boxes
+/// keep the component indices [`find_boxes`] tells, pulls become pull nodes
+fn insert_drivers_into_graph<'a>(
+    graph: &mut Graph,
+    nodes: &'a [Node],
+    position_to_node: &mut HashMap<&'a Position, GraphNodeRef>,
+) {
+    for node in nodes {
+        match node {
+            Node::Constant { position, value } => {
+                let constant = graph.add_component(DigitalComponent::constant(*value));
+                let node_ref =
+                    graph.add_node(NodeKind::ComponentOutput(ComponentOutput::new(constant, 0)));
+                position_to_node.insert(position, node_ref);
+            }
+            Node::Pull { position, value } => {
+                position_to_node.insert(position, graph.add_node(NodeKind::Pull(*value)));
+            }
+            _ => {}
+        }
+    }
 }
 
 fn insert_joints_into_graph<'a>(
@@ -277,6 +418,163 @@ mod tests {
         );
     }
 
+    fn not() -> Box<ComponentLogic> {
+        Box::new(|input: &[BitState], output: &mut [BitState]| {
+            output[0] = match input[0] {
+                BitState::On => BitState::Off,
+                BitState::Off => BitState::On,
+                BitState::Undefined => BitState::Undefined,
+            }
+        })
+    }
+
+    fn and() -> Box<ComponentLogic> {
+        Box::new(|input: &[BitState], output: &mut [BitState]| {
+            output[0] = match (input[0], input[1]) {
+                (BitState::On, BitState::On) => BitState::On,
+                (BitState::Off, _) | (_, BitState::Off) => BitState::Off,
+                _ => BitState::Undefined,
+            }
+        })
+    }
+
+    #[test]
+    fn connects_wires_with_the_same_label() {
+        let test_circuit = "
+               ┏━━━┓
+             ──┨not┠──▶a
+               ┗━━━┛
+                  ┏━━━┓
+             a▶───┨not┠──
+                  ┗━━━┛
+             a▶──
+    ";
+        let mut comps: HashMap<&str, Box<ComponentLogicFactory>> = HashMap::new();
+        comps.insert("not", Box::new(not));
+        let graph = parse(test_circuit, &comps).unwrap();
+        assert_eq!((graph.input_num(), graph.output_num()), (1, 2));
+
+        let mut logic = graph.finalize();
+        let mut outputs = vec![BitState::Undefined; 2];
+        logic(&[BitState::On], &mut outputs);
+        assert_eq!(outputs, vec![BitState::On, BitState::Off]);
+        logic(&[BitState::Off], &mut outputs);
+        assert_eq!(outputs, vec![BitState::Off, BitState::On]);
+    }
+
+    #[test]
+    fn ties_wires_to_constants_and_pulls() {
+        let test_circuit = "
+                   ┏━━━━━┓
+             1─────┨ and ┠──
+             ──┬───┨     ┃
+               │   ┗━━━━━┛
+               │   ┏━━━━━┓
+               └───┨ and ┠──
+             GND───┨     ┃
+                   ┗━━━━━┛
+             PULLUP─────────
+             PULLDOWN───┬───
+             ───────────┘
+    ";
+        let mut comps: HashMap<&str, Box<ComponentLogicFactory>> = HashMap::new();
+        comps.insert("and", Box::new(and));
+        let graph = parse(test_circuit, &comps).unwrap();
+        assert_eq!((graph.input_num(), graph.output_num()), (2, 4));
+
+        let mut logic = graph.finalize();
+        let mut outputs = vec![BitState::Undefined; 4];
+        logic(&[BitState::On, BitState::Undefined], &mut outputs);
+        use BitState::*;
+        assert_eq!(outputs, vec![On, Off, On, Off]);
+        logic(&[BitState::Off, BitState::On], &mut outputs);
+        assert_eq!(outputs, vec![Off, Off, On, On]);
+    }
+
+    #[test]
+    fn drives_inputs_with_named_clocks() {
+        let test_circuit = "
+                    ┏━━━━━┓
+             ~clk───┨ and ┠──
+             ───────┨     ┃
+                    ┗━━━━━┛
+             ~reset─────────
+             ~clk───────────
+    ";
+        let mut comps: HashMap<&str, Box<ComponentLogicFactory>> = HashMap::new();
+        comps.insert("and", Box::new(and));
+        assert_eq!(
+            crate::clocks(test_circuit).unwrap(),
+            vec![(String::from("clk"), 1), (String::from("reset"), 2)]
+        );
+        let graph = parse(test_circuit, &comps).unwrap();
+        assert_eq!((graph.input_num(), graph.output_num()), (3, 3));
+
+        let mut logic = graph.finalize();
+        let mut outputs = vec![BitState::Undefined; 3];
+        logic(&[BitState::On, BitState::On, BitState::Off], &mut outputs);
+        assert_eq!(outputs, vec![BitState::On, BitState::Off, BitState::On]);
+    }
+
+    #[test]
+    fn detects_unmatched_labels() {
+        let test_circuit = "
+             ──┬──▶a
+               └──▶b
+             a▶──
+    ";
+        let error = parse(test_circuit, &HashMap::new()).unwrap_err();
+        assert_eq!(
+            error,
+            ParseError::UnmatchedTunnel {
+                name: String::from("b"),
+                position: Position::new(2, 17),
+            },
+        );
+    }
+
+    #[test]
+    fn detects_unnamed_boxes() {
+        let test_circuit = "
+               ┏━━━━┓
+             ──┨    ┠──
+               ┗━━━━┛
+    ";
+        let error = parse(test_circuit, &HashMap::new()).unwrap_err();
+        assert_eq!(
+            error,
+            ParseError::UnnamedBox {
+                position: Position::new(1, 15),
+            },
+        );
+    }
+
+    #[test]
+    fn detects_drawings_running_off_the_diagram() {
+        // a box and a wire turning up from the first line
+        let box_circuit = "   ┏━━━━━━┛
+ ──┨ nand ┃
+   ┗━━━━━━┛
+";
+        let wire_circuit = "──┘";
+        let mut comps: HashMap<&str, Box<ComponentLogicFactory>> = HashMap::new();
+        comps.insert(
+            "nand",
+            Box::new(|| Box::new(|_: &[BitState], _: &mut [BitState]| {})),
+        );
+
+        assert_eq!(
+            parse(box_circuit, &comps).unwrap_err(),
+            ParseError::EndOfInput
+        );
+        assert_eq!(
+            parse(wire_circuit, &comps).unwrap_err(),
+            ParseError::LooseWire {
+                position: Position::new(0, 2),
+            },
+        );
+    }
+
     #[test]
     fn detects_loose_wiring() {
         let test_circuit = "
@@ -0,0 +1,772 @@
+use digital_component::{
+    BitState, ComponentInput, DigitalComponent, Graph, GraphNodeRef, NetRef, Netlist, NodeKind,
+};
+use std::collections::HashMap;
+
+/// Draws the graph in the notation [`crate::parse`] reads, so parsing the
+/// result with the same component functions gives back an equivalent graph.
+///
+/// Components are boxes placed in columns by their distance from the inputs.
+/// A net joining just two pins facing each other across a channel is drawn as
+/// a straight wire, the boxes being moved down to line their pins up where
+/// that is possible, and every other net is routed down from its pins to a
+/// horizontal track of its own under the boxes. Only the components connected
+/// to the outer inputs through wires are found by the parser again
+pub fn render(graph: Graph) -> String {
+    render_labelled(graph, &[], &[])
+}
+
+/// Like [`render`], drawing the outer inputs driven by clocks after their
+/// names like `~clk──`, and every net one of the graph nodes is on as wires
+/// labelled with the name given with the node, `──▶name` after the pins
+/// driving it and `name▶──` before the others, instead of routing it
+pub(crate) fn render_labelled(
+    mut graph: Graph,
+    clocks: &[(String, usize)],
+    tunnels: &[(String, GraphNodeRef)],
+) -> String {
+    // a pin on the net of each node, of a component the layout leaves out
+    for (name, node) in tunnels {
+        let marker = graph.add_component(
+            DigitalComponent::new(1, 0, Box::new(|_: &[BitState], _: &mut [BitState]| {}))
+                .with_name(&format!("{TUNNEL}{name}")),
+        );
+        let pin = graph.add_node(NodeKind::ComponentInput(ComponentInput::new(marker, 0)));
+        graph.add_edge(node, &pin);
+    }
+    let mut netlist = graph.flatten(&|_| None);
+    pulls_as_labels(&mut netlist);
+
+    let mut tunnels = HashMap::new();
+    netlist.cells.retain(|cell| {
+        match cell
+            .component
+            .get_name()
+            .and_then(|name| name.strip_prefix(TUNNEL))
+        {
+            Some(name) => {
+                tunnels.insert(cell.inputs[0], name.to_string());
+                false
+            }
+            None => true,
+        }
+    });
+    let drawn = Layout::new(&netlist, &tunnels).draw();
+
+    // the outer inputs start on the rows of their indices, so the clock
+    // names go in a margin left of them
+    let margin = clocks
+        .iter()
+        .map(|(name, _)| name.chars().count() + 1)
+        .max()
+        .unwrap_or(0);
+    drawn
+        .lines()
+        .enumerate()
+        .map(|(row, line)| {
+            let clock = clocks
+                .iter()
+                .find(|(_, input)| *input == row)
+                .map_or(String::new(), |(name, _)| format!("~{name}"));
+            format!("{clock:>margin$}{line}").trim_end().to_string() + "\n"
+        })
+        .collect()
+}
+
+const TUNNEL: char = '▶';
+
+/// Puts the pulls the netlist keeps between the drivers of a net and the
+/// rest of it back on the net, to be drawn as labels like the constants
+fn pulls_as_labels(netlist: &mut Netlist) {
+    for cell_idx in 0..netlist.cells.len() {
+        let cell = &netlist.cells[cell_idx];
+        if !["pullup", "pulldown"].contains(&cell.kind.as_str()) || cell.inputs.len() != 1 {
+            continue;
+        }
+        let (drivers, net) = (cell.inputs[0], cell.outputs[0]);
+        netlist.cells[cell_idx].inputs.clear();
+        let cell_nets = netlist
+            .cells
+            .iter_mut()
+            .flat_map(|cell| cell.inputs.iter_mut().chain(cell.outputs.iter_mut()));
+        for other in netlist
+            .inputs
+            .iter_mut()
+            .chain(netlist.outputs.iter_mut())
+            .chain(cell_nets)
+        {
+            if *other == drivers {
+                *other = net;
+            }
+        }
+    }
+}
+
+/// Which way a pin's wire leaves it to reach the routing channel next to it
+#[derive(Clone, Copy, PartialEq)]
+enum Side {
+    /// box outputs and outer inputs
+    Right,
+    /// box inputs and outer outputs
+    Left,
+}
+
+/// A pin connected to other pins, with the wire running from it along
+/// `row` to `drop_column` and down to the net's track from there
+struct Terminal {
+    net: NetRef,
+    row: usize,
+    side: Side,
+    /// the column next to the pin for the box pins, the pin's own for the outer ones
+    pin_column: usize,
+    drop_column: usize,
+}
+
+/// Text drawn from `column` on along `row`
+struct Label {
+    row: usize,
+    column: usize,
+    text: String,
+}
+
+/// A net of two pins on the same row, drawn as a wire from one to the other
+struct Straight {
+    row: usize,
+    first_column: usize,
+    last_column: usize,
+}
+
+struct PlacedBox {
+    name: String,
+    top: usize,
+    left: usize,
+    width: usize,
+    input_num: usize,
+    output_num: usize,
+}
+
+struct Layout {
+    boxes: Vec<PlacedBox>,
+    terminals: Vec<Terminal>,
+    straights: Vec<Straight>,
+    /// the wires of the nets drawn as tunnels
+    labels: Vec<Label>,
+    /// pins that are the only ones on their net, drawn as stubs going nowhere
+    loose_inputs: Vec<usize>,
+    loose_outputs: Vec<usize>,
+    track_rows: HashMap<NetRef, usize>,
+    output_column: usize,
+    width: usize,
+    height: usize,
+}
+
+impl Layout {
+    fn new(netlist: &Netlist, tunnels: &HashMap<NetRef, String>) -> Layout {
+        let levels = levels(netlist);
+        let level_num = levels.iter().max().copied().unwrap_or(0);
+        let output_region = level_num + 1;
+
+        let mut pin_counts = vec![0; netlist.net_num];
+        for net in netlist.inputs.iter().chain(&netlist.outputs).chain(
+            netlist
+                .cells
+                .iter()
+                .flat_map(|cell| cell.inputs.iter().chain(&cell.outputs)),
+        ) {
+            pin_counts[*net] += 1;
+        }
+
+        // (region, row, column, side, net) of every pin, the column being the
+        // one right of the box relative to the region for the box outputs
+        let mut pins = vec![];
+        // region and row of the first pin driving each net
+        let mut net_rows: HashMap<NetRef, (usize, usize)> = HashMap::new();
+        for (input, net) in netlist.inputs.iter().enumerate() {
+            pins.push((0, input, 0, Side::Right, *net));
+            net_rows.entry(*net).or_insert((0, input));
+        }
+
+        let mut region_widths = vec![1; output_region + 1];
+        let mut content_height = netlist.inputs.len().max(netlist.outputs.len());
+        let mut boxes = vec![];
+        for (level, region_width) in region_widths
+            .iter_mut()
+            .enumerate()
+            .take(output_region)
+            .skip(1)
+        {
+            let mut cells = (0..netlist.cells.len())
+                .filter(|cell| levels[*cell] == level)
+                .collect::<Vec<_>>();
+            // next to the pins driving them to keep the wires short
+            cells.sort_by_key(|cell| {
+                let rows = netlist.cells[*cell]
+                    .inputs
+                    .iter()
+                    .filter_map(|net| net_rows.get(net))
+                    .map(|(_, row)| *row)
+                    .collect::<Vec<_>>();
+                match rows.len() {
+                    0 => usize::MAX,
+                    len => rows.into_iter().sum::<usize>() * 16 / len,
+                }
+            });
+
+            let mut top = 0;
+            for cell_idx in cells {
+                let cell = &netlist.cells[cell_idx];
+                let name = cell.component.get_name().unwrap_or("unnamed").to_string();
+                let width = name.chars().count() + 4;
+                // low enough for the first input that can have a straight
+                // wire from the region before to line up with its driver
+                if let Some(row) =
+                    cell.inputs
+                        .iter()
+                        .enumerate()
+                        .find_map(|(pin, net)| match net_rows.get(net) {
+                            Some((region, row))
+                                if pin_counts[*net] == 2
+                                    && !tunnels.contains_key(net)
+                                    && region + 1 == level
+                                    && *row > pin =>
+                            {
+                                Some(row - 1 - pin)
+                            }
+                            _ => None,
+                        })
+                {
+                    top = top.max(row);
+                }
+                for (pin, net) in cell.inputs.iter().enumerate() {
+                    pins.push((level, top + 1 + pin, 0, Side::Left, *net));
+                }
+                for (pin, net) in cell.outputs.iter().enumerate() {
+                    pins.push((level, top + 1 + pin, width, Side::Right, *net));
+                    net_rows.entry(*net).or_insert((level, top + 1 + pin));
+                }
+                let height = cell.inputs.len().max(cell.outputs.len()).max(1) + 2;
+                *region_width = (*region_width).max(width);
+                boxes.push((
+                    level,
+                    top,
+                    name,
+                    width,
+                    cell.inputs.len(),
+                    cell.outputs.len(),
+                ));
+                top += height + 1;
+            }
+            // without the gap under the last box
+            content_height = content_height.max(top.saturating_sub(1));
+        }
+        for (output, net) in netlist.outputs.iter().enumerate() {
+            pins.push((output_region, output, 0, Side::Left, *net));
+        }
+
+        // channel `k` lies between region `k` and `k + 1`, every wire turning
+        // down in it gets its own column with a space in between
+        let mut region_columns = vec![0; output_region + 1];
+        let mut terminals = vec![];
+        let mut straights = vec![];
+        let mut labels = vec![];
+        for channel in 0..output_region {
+            let labelled = |side: Side, region: usize| {
+                pins.iter()
+                    .filter(|(pin_region, _, _, pin_side, net)| {
+                        *pin_region == region && *pin_side == side && tunnels.contains_key(net)
+                    })
+                    .map(|(_, row, column, _, net)| (*row, *column, &tunnels[net]))
+                    .collect::<Vec<_>>()
+            };
+            let right_labels = labelled(Side::Right, channel);
+            let left_labels = labelled(Side::Left, channel + 1);
+            let label_width = |labels: &[(usize, usize, &String)]| {
+                labels
+                    .iter()
+                    .map(|(.., name)| name.chars().count() + 3)
+                    .max()
+                    .unwrap_or(0)
+            };
+
+            // the labels after the pins on the left of the channel come
+            // first, then the wires turning down and the labels before the
+            // pins on the right
+            let start =
+                region_columns[channel] + region_widths[channel] + label_width(&right_labels);
+            let straight_pins = pins
+                .iter()
+                .filter(|(region, _, _, side, net)| {
+                    *region == channel
+                        && *side == Side::Right
+                        && pin_counts[*net] == 2
+                        && !tunnels.contains_key(net)
+                })
+                .filter_map(|right @ (_, row, _, _, net)| {
+                    pins.iter()
+                        .find(|(region, other_row, _, side, other_net)| {
+                            *region == channel + 1
+                                && other_row == row
+                                && *side == Side::Left
+                                && other_net == net
+                        })
+                        .map(|_| (right, *row))
+                })
+                .collect::<Vec<_>>();
+            let is_straight = |net: &NetRef| {
+                straight_pins
+                    .iter()
+                    .any(|((.., straight), _)| straight == net)
+            };
+            let routed = |side: Side, region: usize| {
+                let mut routed = pins
+                    .iter()
+                    .filter(|(pin_region, _, _, pin_side, net)| {
+                        *pin_region == region
+                            && *pin_side == side
+                            && pin_counts[*net] > 1
+                            && !is_straight(net)
+                            && !tunnels.contains_key(net)
+                    })
+                    .collect::<Vec<_>>();
+                routed.sort_by_key(|(_, row, ..)| *row);
+                routed
+            };
+            let right = routed(Side::Right, channel);
+            let left = routed(Side::Left, channel + 1);
+
+            // the wires of the upper pins turn further from the pins so they
+            // do not cut through the wires of the lower ones
+            for (idx, (_, row, column, side, net)) in right.iter().enumerate() {
+                terminals.push(Terminal {
+                    net: *net,
+                    row: *row,
+                    side: *side,
+                    pin_column: region_columns[channel] + column,
+                    drop_column: start + 1 + 2 * (right.len() - 1 - idx),
+                });
+            }
+            let next_region =
+                start + 2 * (right.len() + left.len()) + 1 + label_width(&left_labels);
+            for (idx, (region, row, _, side, net)) in left.iter().enumerate() {
+                terminals.push(Terminal {
+                    net: *net,
+                    row: *row,
+                    side: *side,
+                    pin_column: if *region == output_region {
+                        next_region
+                    } else {
+                        next_region - 1
+                    },
+                    drop_column: start + 1 + 2 * right.len() + 2 * idx,
+                });
+            }
+            for ((_, _, column, ..), row) in &straight_pins {
+                straights.push(Straight {
+                    row: *row,
+                    first_column: region_columns[channel] + column,
+                    last_column: if channel + 1 == output_region {
+                        next_region
+                    } else {
+                        next_region - 1
+                    },
+                });
+            }
+            for (row, column, name) in right_labels {
+                labels.push(Label {
+                    row,
+                    column: region_columns[channel] + column,
+                    text: format!("──{TUNNEL}{name}"),
+                });
+            }
+            for (row, _, name) in left_labels {
+                let text = format!("{name}{TUNNEL}──");
+                let last_column = if channel + 1 == output_region {
+                    next_region
+                } else {
+                    next_region - 1
+                };
+                labels.push(Label {
+                    row,
+                    column: last_column + 1 - text.chars().count(),
+                    text,
+                });
+            }
+            region_columns[channel + 1] = next_region;
+        }
+
+        // nets whose tracks do not overlap share a row
+        let mut spans: HashMap<NetRef, (usize, usize)> = HashMap::new();
+        for terminal in &terminals {
+            let span = spans
+                .entry(terminal.net)
+                .or_insert((terminal.drop_column, terminal.drop_column));
+            span.0 = span.0.min(terminal.drop_column);
+            span.1 = span.1.max(terminal.drop_column);
+        }
+        let mut spans = spans.into_iter().collect::<Vec<_>>();
+        spans.sort_by_key(|(net, (first, last))| (*first, *last, *net));
+        let mut track_ends: Vec<usize> = vec![];
+        let mut track_rows = HashMap::new();
+        for (net, (first, last)) in spans {
+            let track = match track_ends.iter().position(|end| end + 1 < first) {
+                Some(track) => track,
+                None => {
+                    track_ends.push(0);
+                    track_ends.len() - 1
+                }
+            };
+            track_ends[track] = last;
+            track_rows.insert(net, content_height + 1 + track);
+        }
+
+        let loose_inputs = netlist
+            .inputs
+            .iter()
+            .enumerate()
+            .filter(|(_, net)| pin_counts[**net] == 1)
+            .map(|(input, _)| input)
+            .collect();
+        let loose_outputs = netlist
+            .outputs
+            .iter()
+            .enumerate()
+            .filter(|(_, net)| pin_counts[**net] == 1)
+            .map(|(output, _)| output)
+            .collect();
+
+        Layout {
+            boxes: boxes
+                .into_iter()
+                .map(
+                    |(level, top, name, width, input_num, output_num)| PlacedBox {
+                        name,
+                        top,
+                        left: region_columns[level],
+                        width,
+                        input_num,
+                        output_num,
+                    },
+                )
+                .collect(),
+            terminals,
+            straights,
+            labels,
+            loose_inputs,
+            loose_outputs,
+            width: region_columns[output_region] + 1,
+            height: content_height + 1 + track_ends.len(),
+            track_rows,
+            output_column: region_columns[output_region],
+        }
+    }
+
+    fn draw(&self) -> String {
+        let mut grid = vec![vec![' '; self.width]; self.height];
+
+        for placed in &self.boxes {
+            draw_box(&mut grid, placed);
+        }
+        for input in &self.loose_inputs {
+            grid[*input][0] = '─';
+            grid[*input][1] = '┤';
+        }
+        for output in &self.loose_outputs {
+            grid[*output][self.output_column - 1] = '├';
+            grid[*output][self.output_column] = '─';
+        }
+
+        for straight in &self.straights {
+            grid[straight.row][straight.first_column..=straight.last_column].fill('─');
+        }
+        for label in &self.labels {
+            for (idx, c) in label.text.chars().enumerate() {
+                grid[label.row][label.column + idx] = c;
+            }
+        }
+
+        for terminal in &self.terminals {
+            let row = &mut grid[terminal.row];
+            match terminal.side {
+                Side::Right => {
+                    row[terminal.pin_column..terminal.drop_column].fill('─');
+                    row[terminal.drop_column] = '┐';
+                }
+                Side::Left => {
+                    row[terminal.drop_column] = '┌';
+                    row[terminal.drop_column + 1..=terminal.pin_column].fill('─');
+                }
+            }
+            let track_row = self.track_rows[&terminal.net];
+            for line in &mut grid[terminal.row + 1..track_row] {
+                // crossing the straight wires of the channel
+                line[terminal.drop_column] = match line[terminal.drop_column] {
+                    '─' => '┼',
+                    _ => '│',
+                };
+            }
+        }
+
+        for (net, track_row) in &self.track_rows {
+            let drops = self
+                .terminals
+                .iter()
+                .filter(|terminal| terminal.net == *net)
+                .map(|terminal| terminal.drop_column)
+                .collect::<Vec<_>>();
+            let first = *drops.iter().min().unwrap();
+            let last = *drops.iter().max().unwrap();
+            for (column, cell) in grid[*track_row]
+                .iter_mut()
+                .enumerate()
+                .take(last + 1)
+                .skip(first)
+            {
+                *cell = if drops.contains(&column) {
+                    match (column > first, column < last) {
+                        (true, true) => '┴',
+                        (false, true) => '└',
+                        (true, false) => '┘',
+                        (false, false) => '│',
+                    }
+                } else if *cell == '│' {
+                    '┼'
+                } else {
+                    '─'
+                };
+            }
+        }
+
+        grid.into_iter()
+            .map(|line| line.into_iter().collect::<String>().trim_end().to_string() + "\n")
+            .collect()
+    }
+}
+
+fn draw_box(grid: &mut [Vec<char>], placed: &PlacedBox) {
+    let right = placed.left + placed.width - 1;
+    // constants and pulls are labels the wire of their output starts right after
+    if placed.input_num == 0 && ["vcc", "gnd", "pullup", "pulldown"].contains(&placed.name.as_str())
+    {
+        let label = placed.name.to_uppercase();
+        let start = right + 1 - label.len();
+        for (idx, c) in label.chars().enumerate() {
+            grid[placed.top + 1][start + idx] = c;
+        }
+        return;
+    }
+
+    let height = placed.input_num.max(placed.output_num).max(1) + 2;
+    let bottom = placed.top + height - 1;
+
+    grid[placed.top][placed.left] = '┏';
+    grid[placed.top][placed.left + 1..right].fill('━');
+    grid[placed.top][right] = '┓';
+    for (pin, line) in grid[placed.top + 1..bottom].iter_mut().enumerate() {
+        line[placed.left] = if pin < placed.input_num { '┨' } else { '┃' };
+        line[right] = if pin < placed.output_num {
+            '┠'
+        } else {
+            '┃'
+        };
+    }
+    grid[bottom][placed.left] = '┗';
+    grid[bottom][placed.left + 1..right].fill('━');
+    grid[bottom][right] = '┛';
+
+    for (idx, c) in placed.name.chars().enumerate() {
+        grid[placed.top + 1][placed.left + 2 + idx] = c;
+    }
+}
+
+/// Column of every cell, one more than the furthest of the cells driving it
+/// with the connections closing loops ignored
+fn levels(netlist: &Netlist) -> Vec<usize> {
+    let order = netlist.topological_order();
+    let drivers = netlist.drivers();
+    let mut position = vec![0; netlist.cells.len()];
+    for (idx, cell) in order.iter().enumerate() {
+        position[*cell] = idx;
+    }
+
+    let mut levels = vec![1; netlist.cells.len()];
+    for cell in &order {
+        levels[*cell] = netlist.cells[*cell]
+            .inputs
+            .iter()
+            .flat_map(|net| &drivers[*net])
+            .filter(|(driver, _)| position[*driver] < position[*cell])
+            .map(|(driver, _)| levels[*driver] + 1)
+            .max()
+            .unwrap_or(1);
+    }
+    levels
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parse;
+    use digital_component::*;
+
+    fn nand(input: &[BitState], output: &mut [BitState]) {
+        output[0] = match (input[0], input[1]) {
+            (BitState::On, BitState::On) => BitState::Off,
+            (BitState::Undefined, BitState::Undefined) => BitState::Undefined,
+            _ => BitState::On,
+        }
+    }
+
+    fn functions() -> HashMap<&'static str, Box<ComponentLogicFactory>> {
+        let mut functions: HashMap<&str, Box<ComponentLogicFactory>> = HashMap::new();
+        functions.insert("nand", Box::new(|| Box::new(nand)));
+        functions
+    }
+
+    #[test]
+    fn renders_components_in_columns() {
+        let graph = parse(
+            "
+           ┏━━━━┓
+        ─┬─┨nand┠──
+         └─┨    ┃
+           ┗━━━━┛
+        ",
+            &functions(),
+        )
+        .unwrap();
+
+        assert_eq!(
+            render(graph),
+            "\
+            ──┐     ┏━━━━━━┓   ┌──\n  \
+              │ ┌───┨ nand ┠─┐ │\n  \
+              │ │ ┌─┨      ┃ │ │\n  \
+              │ │ │ ┗━━━━━━┛ │ │\n  \
+              │ │ │          │ │\n  \
+              └─┴─┘          └─┘\n\
+            "
+        );
+    }
+
+    /// XOR made of four NANDs
+    fn xor() -> Graph {
+        let mut graph = Graph::default();
+        let add_nand = |graph: &mut Graph| {
+            let gate =
+                graph.add_component(DigitalComponent::new(2, 1, Box::new(nand)).with_name("nand"));
+            [
+                graph.add_node(NodeKind::ComponentInput(ComponentInput::new(gate, 0))),
+                graph.add_node(NodeKind::ComponentInput(ComponentInput::new(gate, 1))),
+                graph.add_node(NodeKind::ComponentOutput(ComponentOutput::new(gate, 0))),
+            ]
+        };
+        let gates = [(); 4].map(|_| add_nand(&mut graph));
+        let a = graph.add_node(NodeKind::Input(0));
+        let b = graph.add_node(NodeKind::Input(1));
+        let output = graph.add_node(NodeKind::Output(0));
+        for (from, to) in [
+            (a, gates[0][0]),
+            (b, gates[0][1]),
+            (a, gates[1][0]),
+            (gates[0][2], gates[1][1]),
+            (gates[0][2], gates[2][0]),
+            (b, gates[2][1]),
+            (gates[1][2], gates[3][0]),
+            (gates[2][2], gates[3][1]),
+            (gates[3][2], output),
+        ] {
+            graph.add_edge(&from, &to);
+        }
+        graph
+    }
+
+    #[test]
+    fn draws_constants_as_labels() {
+        let mut graph = Graph::default();
+        let gate =
+            graph.add_component(DigitalComponent::new(2, 1, Box::new(nand)).with_name("nand"));
+        let one = graph.add_component(DigitalComponent::constant(BitState::On));
+        let gate_inputs = [0, 1]
+            .map(|pin| graph.add_node(NodeKind::ComponentInput(ComponentInput::new(gate, pin))));
+        let gate_output = graph.add_node(NodeKind::ComponentOutput(ComponentOutput::new(gate, 0)));
+        let one_output = graph.add_node(NodeKind::ComponentOutput(ComponentOutput::new(one, 0)));
+        let input = graph.add_node(NodeKind::Input(0));
+        let output = graph.add_node(NodeKind::Output(0));
+        graph.add_edge(&input, &gate_inputs[0]);
+        graph.add_edge(&one_output, &gate_inputs[1]);
+        graph.add_edge(&gate_output, &output);
+
+        let rendered = render(graph);
+        assert!(rendered.contains("VCC─"), "{rendered}");
+        let parsed = parse(&rendered, &functions()).unwrap();
+
+        let mut logic = parsed.finalize();
+        let mut outputs = vec![BitState::Undefined];
+        logic(&[BitState::On], &mut outputs);
+        assert_eq!(outputs, vec![BitState::Off], "{rendered}");
+        logic(&[BitState::Off], &mut outputs);
+        assert_eq!(outputs, vec![BitState::On], "{rendered}");
+    }
+
+    #[test]
+    fn keeps_pulls_on_driven_nets() {
+        let graph = parse(
+            "
+             ──┐                    ┏━━━━━━┓   ┌──
+               │     PULLDOWN─┐ ┌───┨ nand ┠─┐ │
+               │              │ │ ┌─┨      ┃ │ │
+               │              │ │ │ ┗━━━━━━┛ │ │
+               │              │ │ │          │ │
+               └──────────────┴─┴─┘          └─┘
+    ",
+            &functions(),
+        )
+        .unwrap();
+
+        let rendered = render(graph);
+        assert!(rendered.contains("PULLDOWN─"), "{rendered}");
+        let mut logic = parse(&rendered, &functions()).unwrap().finalize();
+        let mut outputs = vec![BitState::Undefined];
+        logic(&[BitState::Undefined], &mut outputs);
+        assert_eq!(outputs, vec![BitState::On], "{rendered}");
+        logic(&[BitState::On], &mut outputs);
+        assert_eq!(outputs, vec![BitState::Off], "{rendered}");
+    }
+
+    #[test]
+    fn draws_straight_wires_between_facing_pins() {
+        let source = "
+        ──────────────┐
+           ┏━━━━━━┓   │ ┏━━━━━━┓
+        ───┨ nand ┠───┼─┨ nand ┠───
+        ───┨      ┃   └─┨      ┃
+           ┗━━━━━━┛     ┗━━━━━━┛
+        ";
+
+        let rendered = render(parse(source, &functions()).unwrap());
+        assert!(rendered.contains("┨ nand ┠───┨ nand ┠"), "{rendered}");
+        // the wire of the first input crosses the straight ones
+        assert!(rendered.contains('┼'), "{rendered}");
+        assert_eq!(
+            check_equivalence(
+                parse(source, &functions()).unwrap(),
+                parse(&rendered, &functions()).unwrap(),
+                &|_| None
+            ),
+            Ok(Equivalence::Equivalent),
+            "{rendered}"
+        );
+    }
+
+    #[test]
+    fn round_trips_through_parser() {
+        let rendered = render(xor());
+        let parsed = parse(&rendered, &functions()).unwrap();
+
+        assert_eq!(
+            check_equivalence(xor(), parsed, &|_| None),
+            Ok(Equivalence::Equivalent),
+            "{rendered}"
+        );
+    }
+}
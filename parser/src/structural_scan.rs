@@ -1,16 +1,23 @@
-use std::collections::{HashSet, VecDeque};
+use digital_component::BitState;
+use std::collections::{HashMap, HashSet, VecDeque};
 
 use crate::types::{Node, ParseError, ParsingMode, Position, Symbol};
 
 use crate::r#box::{scan_box, BoxParsingContext};
 use crate::wires::{scan_for_wire_end, WIRE_SYMBOLS};
 
+/// Finds the wires and boxes reachable from the symbols, along with the
+/// start of the wire every wire cell belongs to
 pub fn structural_scan(
     input: &[&str],
     mut to_look_at: VecDeque<Symbol>,
-) -> Result<Vec<Node>, ParseError> {
+) -> Result<(Vec<Node>, HashMap<Position, Position>), ParseError> {
     let mut components = vec![];
+    let mut wire_cells = HashMap::new();
+    let mut current_wire_cells = vec![];
     let mut debug_num = 0;
+    // every symbol is looked at a few times at most, unless the scan is going in circles
+    let symbol_limit = 10000.max(4 * input.iter().map(|line| line.chars().count()).sum::<usize>());
     let mut new_component = true;
     let mut wire_start = Position::new(0, 0);
     let mut box_parsing_context = BoxParsingContext::new(&wire_start);
@@ -20,6 +27,7 @@ pub fn structural_scan(
             match symbol.mode {
                 ParsingMode::Wire => {
                     wire_start = symbol.position.clone();
+                    current_wire_cells.clear();
                 }
                 ParsingMode::Box => {
                     box_parsing_context = BoxParsingContext::new(&symbol.position);
@@ -28,12 +36,15 @@ pub fn structural_scan(
         }
 
         debug_num += 1;
-        if debug_num > 10000 {
+        if debug_num > symbol_limit {
             return Err(ParseError::Looping);
         }
 
         new_component = false;
 
+        if let ParsingMode::Wire = symbol.mode {
+            current_wire_cells.push(symbol.position.clone());
+        }
         let scanner_result = match symbol.mode {
             ParsingMode::Wire => scan_for_wire_end(input, symbol, &wire_start, &mut visited),
             ParsingMode::Box => scan_box(input, symbol, &mut box_parsing_context, &mut visited),
@@ -43,6 +54,11 @@ pub fn structural_scan(
         }
 
         if let Some(node) = scanner_result.node {
+            if let Node::Wire { start, .. } = &node {
+                for cell in current_wire_cells.drain(..) {
+                    wire_cells.insert(cell, start.clone());
+                }
+            }
             components.push(node);
             new_component = true;
         }
@@ -55,21 +71,30 @@ pub fn structural_scan(
             to_look_at.push_back(to_back);
         }
     }
-    Ok(components)
+    Ok((components, wire_cells))
 }
 
 pub fn find_dangling_wires(input: &[&str]) -> (Vec<Position>, Vec<Position>) {
     let mut dangling_inputs = vec![];
     let mut dangling_outputs = vec![];
-    let struct_symbol_set: HashSet<_> = WIRE_SYMBOLS.chars().chain(BOX_SYMBOLS.chars()).collect();
+    let struct_symbol_set: HashSet<_> = WIRE_SYMBOLS
+        .chars()
+        .chain(BOX_SYMBOLS.chars())
+        .chain([TUNNEL])
+        .collect();
     for (line_num, line) in input.iter().enumerate() {
+        let chars = line.chars().collect::<Vec<_>>();
         let mut prev_symbol: Option<char> = None;
-        for (col_num, symbol) in line.chars().chain([' ']).enumerate() {
+        for (col_num, symbol) in chars.iter().copied().chain([' ']).enumerate() {
             match (prev_symbol, symbol) {
                 (Some('─'), junk) if !struct_symbol_set.contains(&junk) => {
                     dangling_outputs.push(Position::new(line_num, col_num - 1));
                 }
-                (Some(junk), '─') if !struct_symbol_set.contains(&junk) => {
+                (Some(junk), '─')
+                    if !struct_symbol_set.contains(&junk)
+                        && driver_before(&chars[..col_num], Position::new(line_num, col_num))
+                            .is_none() =>
+                {
                     dangling_inputs.push(Position::new(line_num, col_num));
                 }
                 (None, '─') => {
@@ -83,6 +108,112 @@ pub fn find_dangling_wires(input: &[&str]) -> (Vec<Position>, Vec<Position>) {
     (dangling_inputs, dangling_outputs)
 }
 
+/// Finds the wires ending in a label like `──▶clk` and the wires starting
+/// from one like `clk▶──`, the latter also returned separately as wires to
+/// scan from
+pub fn find_tunnels(input: &[&str]) -> (Vec<Node>, Vec<Position>) {
+    let mut tunnels = vec![];
+    let mut wire_starts = vec![];
+    let is_name = |c: &char| c.is_ascii_alphanumeric() || *c == '_';
+    for (line_num, line) in input.iter().enumerate() {
+        let chars = line.chars().collect::<Vec<_>>();
+        for (col_num, _) in chars.iter().enumerate().filter(|(_, c)| **c == TUNNEL) {
+            if col_num > 0 && chars[col_num - 1] == '─' {
+                let name = chars[col_num + 1..]
+                    .iter()
+                    .take_while(|c| is_name(c))
+                    .collect::<String>();
+                if !name.is_empty() {
+                    tunnels.push(Node::Tunnel {
+                        position: Position::new(line_num, col_num - 1),
+                        name,
+                    });
+                }
+            }
+            if chars.get(col_num + 1) == Some(&'─') {
+                let mut name = chars[..col_num]
+                    .iter()
+                    .rev()
+                    .take_while(|c| is_name(c))
+                    .collect::<Vec<_>>();
+                name.reverse();
+                if !name.is_empty() {
+                    let position = Position::new(line_num, col_num + 1);
+                    wire_starts.push(position.clone());
+                    tunnels.push(Node::Tunnel {
+                        position,
+                        name: name.into_iter().collect(),
+                    });
+                }
+            }
+        }
+    }
+    (tunnels, wire_starts)
+}
+
+/// Finds the wires starting from a constant like `VCC──` or `0──`, from a
+/// pull like `PULLUP──` or from a clock like `~clk──`, returned separately
+/// as wires to scan from
+pub fn find_drivers(input: &[&str]) -> (Vec<Node>, Vec<Position>) {
+    let mut drivers = vec![];
+    let mut wire_starts = vec![];
+    for (line_num, line) in input.iter().enumerate() {
+        let chars = line.chars().collect::<Vec<_>>();
+        for (col_num, _) in chars.iter().enumerate().filter(|(_, c)| **c == '─') {
+            let position = Position::new(line_num, col_num);
+            if let Some(driver) = driver_before(&chars[..col_num], position.clone()) {
+                wire_starts.push(position);
+                drivers.push(driver);
+            }
+        }
+    }
+    (drivers, wire_starts)
+}
+
+/// Labels of the values wires can be tied to, the weak ones only deciding
+/// nets nothing else drives
+const DRIVERS: [(&str, BitState, bool); 6] = [
+    ("1", BitState::On, false),
+    ("0", BitState::Off, false),
+    ("VCC", BitState::On, false),
+    ("GND", BitState::Off, false),
+    ("PULLUP", BitState::On, true),
+    ("PULLDOWN", BitState::Off, true),
+];
+
+/// The driver of the wire starting at `position` if the characters before
+/// it end in a driver label, the label having to be a word of its own
+fn driver_before(chars: &[char], position: Position) -> Option<Node> {
+    let word_start = chars
+        .iter()
+        .rposition(|c| !c.is_ascii_alphanumeric() && *c != '_')
+        .map_or(0, |idx| idx + 1);
+    let word = chars[word_start..].iter().collect::<String>();
+    if word_start > 0 && chars[word_start - 1] == CLOCK && !word.is_empty() {
+        return Some(Node::Clock {
+            position,
+            name: word,
+        });
+    }
+    DRIVERS
+        .iter()
+        .find(|(label, ..)| *label == word)
+        .map(|(_, value, weak)| match weak {
+            true => Node::Pull {
+                position,
+                value: *value,
+            },
+            false => Node::Constant {
+                position,
+                value: *value,
+            },
+        })
+}
+
+pub const CLOCK: char = '~';
+
+pub const TUNNEL: char = '▶';
+
 const BOX_SYMBOLS: &str = "━┃┓┏┗┛┠┨";
 
 #[derive(Default)]
@@ -126,4 +257,32 @@ mod tests {
             outputs: vec![Position::new(2, 21), Position::new(3, 21)],
         }]);
     }
+
+    #[test]
+    fn finds_tunnels() {
+        let test_circuit = "
+              ───▶clk
+           clk▶──┐  ──▶ ▶──
+                 └──▶q_1
+    ";
+        assert_that!(scan(test_circuit)
+            .unwrap()
+            .into_iter()
+            .filter(|node| matches!(node, Node::Tunnel { .. }))
+            .collect::<Vec<_>>())
+        .contains_exactly(vec![
+            Node::Tunnel {
+                position: Position::new(1, 16),
+                name: String::from("clk"),
+            },
+            Node::Tunnel {
+                position: Position::new(2, 15),
+                name: String::from("clk"),
+            },
+            Node::Tunnel {
+                position: Position::new(3, 19),
+                name: String::from("q_1"),
+            },
+        ]);
+    }
 }
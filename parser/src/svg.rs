@@ -0,0 +1,282 @@
+use crate::structural_scan::CLOCK;
+use crate::types::{Direction, Node, ParseError, Position};
+use crate::{scan_with_wire_cells, WireMap};
+use digital_component::{BitState, GraphNodeRef};
+use std::fmt::Write;
+
+const CELL_WIDTH: usize = 10;
+const CELL_HEIGHT: usize = 20;
+
+/// Draws the diagram as an SVG image with the boxes, pins, labels and wires
+/// where the parser finds them. With `signals` the wires are coloured by the
+/// states of the graph nodes they are connected to, green for `On`, blue for
+/// `Off` and orange for `Undefined`
+pub fn svg(
+    source: &str,
+    signals: Option<(&WireMap, &dyn Fn(GraphNodeRef) -> BitState)>,
+) -> Result<String, ParseError> {
+    let (nodes, wire_cells) = scan_with_wire_cells(source)?;
+    let lines = source
+        .lines()
+        .map(|line| line.chars().collect::<Vec<_>>())
+        .collect::<Vec<_>>();
+    let width = lines.iter().map(Vec::len).max().unwrap_or(0) * CELL_WIDTH;
+    let height = lines.len() * CELL_HEIGHT;
+    let glyph = |position: &Position| {
+        lines
+            .get(position.line)
+            .and_then(|line| line.get(position.column))
+            .copied()
+            .unwrap_or(' ')
+    };
+    let color = |position: &Position| match signals {
+        Some((wires, state)) => match wires.get(position).map(|node| state(*node)) {
+            Some(BitState::On) => "#2a2",
+            Some(BitState::Off) => "#24c",
+            Some(BitState::Undefined) => "#d80",
+            None => "black",
+        },
+        None => "black",
+    };
+
+    let mut image = format!(
+        "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{width}\" height=\"{height}\" \
+        viewBox=\"0 0 {width} {height}\" font-family=\"monospace\" font-size=\"14\">\n"
+    );
+    image += &format!("<rect width=\"{width}\" height=\"{height}\" fill=\"white\"/>\n");
+
+    let mut cells = wire_cells.keys().collect::<Vec<_>>();
+    cells.sort();
+    for cell in cells {
+        let (x, y) = center(cell);
+        for direction in arms(glyph(cell)) {
+            // the two wires of a crossing take the colour of the cells next to it
+            let mut neighbour = cell.clone();
+            while glyph(&neighbour) == '┼' && !at_edge(&neighbour, direction) {
+                let next = direction.move_cursor(neighbour.clone());
+                if !wire_cells.contains_key(&next) {
+                    break;
+                }
+                neighbour = next;
+            }
+            let (dx, dy) = match direction {
+                Direction::Up => (0, -1),
+                Direction::Down => (0, 1),
+                Direction::Left => (-1, 0),
+                Direction::Right => (1, 0),
+            };
+            let _ = writeln!(
+                image,
+                "<line x1=\"{x}\" y1=\"{y}\" x2=\"{}\" y2=\"{}\" stroke=\"{}\" \
+                stroke-width=\"2\" stroke-linecap=\"square\"/>",
+                x as isize + dx * (CELL_WIDTH / 2) as isize,
+                y as isize + dy * (CELL_HEIGHT / 2) as isize,
+                color(&neighbour)
+            );
+        }
+    }
+
+    // the labels go on top of the boxes
+    let mut nodes = nodes;
+    nodes.sort_by_key(|node| !matches!(node, Node::Box { .. }));
+    for node in &nodes {
+        match node {
+            Node::Box {
+                top_left,
+                bottom_right,
+                inputs,
+                outputs,
+            } => {
+                let (left, top) = center(top_left);
+                let (right, bottom) = center(bottom_right);
+                let _ = writeln!(
+                    image,
+                    "<rect x=\"{left}\" y=\"{top}\" width=\"{}\" height=\"{}\" \
+                    fill=\"#f4f4f4\" stroke=\"black\" stroke-width=\"3\"/>",
+                    right - left,
+                    bottom - top
+                );
+                for pin in inputs.iter().chain(outputs) {
+                    let (x, y) = center(pin);
+                    let _ = writeln!(
+                        image,
+                        "<circle cx=\"{x}\" cy=\"{y}\" r=\"3\" fill=\"{}\"/>",
+                        color(pin)
+                    );
+                }
+            }
+            Node::Text {
+                line,
+                position,
+                value,
+            } => {
+                let _ = writeln!(
+                    image,
+                    "<text x=\"{}\" y=\"{}\" dominant-baseline=\"central\">{}</text>",
+                    position.start * CELL_WIDTH,
+                    line * CELL_HEIGHT + CELL_HEIGHT / 2,
+                    escape(value)
+                );
+            }
+            Node::Input { position } | Node::Output { position } => {
+                let (x, y) = center(position);
+                let x = match node {
+                    Node::Input { .. } => x - CELL_WIDTH / 2,
+                    _ => x + CELL_WIDTH / 2,
+                };
+                let _ = writeln!(
+                    image,
+                    "<circle cx=\"{x}\" cy=\"{y}\" r=\"3\" fill=\"white\" stroke=\"{}\" \
+                    stroke-width=\"2\"/>",
+                    color(position)
+                );
+            }
+            Node::Tunnel { position, .. } => {
+                let arrow = if glyph(&Direction::Right.move_cursor(position.clone())) == '▶' {
+                    position.column + 1
+                } else {
+                    position.column - 1
+                };
+                let (left, top) = (arrow * CELL_WIDTH, position.line * CELL_HEIGHT);
+                let _ = writeln!(
+                    image,
+                    "<polygon points=\"{},{} {},{} {},{}\" fill=\"{}\"/>",
+                    left + 1,
+                    top + 4,
+                    left + CELL_WIDTH - 1,
+                    top + CELL_HEIGHT / 2,
+                    left + 1,
+                    top + CELL_HEIGHT - 4,
+                    color(position)
+                );
+            }
+            // the named ones are text tokens drawn like the other labels
+            Node::Constant { position, .. }
+                if glyph(&Direction::Left.move_cursor(position.clone())).is_ascii_digit() =>
+            {
+                let _ = writeln!(
+                    image,
+                    "<text x=\"{}\" y=\"{}\" dominant-baseline=\"central\">{}</text>",
+                    (position.column - 1) * CELL_WIDTH,
+                    position.line * CELL_HEIGHT + CELL_HEIGHT / 2,
+                    glyph(&Direction::Left.move_cursor(position.clone()))
+                );
+            }
+            Node::Clock { position, name } => {
+                let _ = writeln!(
+                    image,
+                    "<text x=\"{}\" y=\"{}\" dominant-baseline=\"central\">{}</text>",
+                    (position.column - name.chars().count() - 1) * CELL_WIDTH,
+                    position.line * CELL_HEIGHT + CELL_HEIGHT / 2,
+                    CLOCK
+                );
+            }
+            Node::Wire { .. } | Node::Constant { .. } | Node::Pull { .. } => {}
+        }
+    }
+    image += "</svg>\n";
+    Ok(image)
+}
+
+fn center(position: &Position) -> (usize, usize) {
+    (
+        position.column * CELL_WIDTH + CELL_WIDTH / 2,
+        position.line * CELL_HEIGHT + CELL_HEIGHT / 2,
+    )
+}
+
+/// Directions the wire leaves the cell of the glyph in
+fn arms(glyph: char) -> &'static [Direction] {
+    use Direction::*;
+    match glyph {
+        '─' => &[Left, Right],
+        '│' => &[Up, Down],
+        '┌' => &[Right, Down],
+        '┐' => &[Left, Down],
+        '└' => &[Up, Right],
+        '┘' => &[Up, Left],
+        '├' => &[Up, Down, Right],
+        '┤' => &[Up, Down, Left],
+        '┬' => &[Left, Right, Down],
+        '┴' => &[Left, Right, Up],
+        '┼' => &[Left, Right, Up, Down],
+        // the pins only get the half outside the box
+        '┨' => &[Left],
+        '┠' => &[Right],
+        _ => &[],
+    }
+}
+
+fn at_edge(position: &Position, direction: &Direction) -> bool {
+    match direction {
+        Direction::Up => position.line == 0,
+        Direction::Left => position.column == 0,
+        _ => false,
+    }
+}
+
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parse_with_wires;
+    use digital_component::ComponentLogicFactory;
+    use std::collections::HashMap;
+
+    fn not(input: &[BitState], output: &mut [BitState]) {
+        output[0] = match input[0] {
+            BitState::On => BitState::Off,
+            BitState::Off => BitState::On,
+            BitState::Undefined => BitState::Undefined,
+        }
+    }
+
+    const DIAGRAM: &str = "
+   ┏━━━┓
+ ──┨not┠──┐
+   ┗━━━┛  │
+ ─────────┼──
+          └──
+";
+
+    #[test]
+    fn draws_boxes_labels_and_wires() {
+        let image = svg(DIAGRAM, None).unwrap();
+
+        assert!(image.starts_with("<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"130\""));
+        assert!(image.contains("<rect x=\"35\" y=\"30\" width=\"40\" height=\"40\""));
+        assert!(image.contains(">not</text>"));
+        // the crossing is drawn as four arms
+        assert_eq!(image.matches("<line x1=\"105\" y1=\"90\"").count(), 4);
+        assert!(!image.contains("#2a2"));
+    }
+
+    #[test]
+    fn colours_nets_by_state() {
+        let mut functions: HashMap<&str, Box<ComponentLogicFactory>> = HashMap::new();
+        functions.insert("not", Box::new(|| Box::new(not)));
+        let (graph, wires) = parse_with_wires(DIAGRAM, &functions).unwrap();
+        let (mut logic, probe) = graph.finalize_probed();
+        let mut outputs = vec![BitState::Undefined; 2];
+        logic(&[BitState::On, BitState::On], &mut outputs);
+
+        let image = svg(DIAGRAM, Some((&wires, &|node| probe.state(node)))).unwrap();
+
+        // horizontally the crossing belongs to the second input, vertically
+        // to the output of the inverter
+        let arm = |x2: usize, y2: usize| {
+            let line = format!("<line x1=\"105\" y1=\"90\" x2=\"{x2}\" y2=\"{y2}\"");
+            let start = image.find(&line).unwrap();
+            image[start..].lines().next().unwrap().to_string()
+        };
+        assert!(arm(100, 90).contains("#2a2"));
+        assert!(arm(110, 90).contains("#2a2"));
+        assert!(arm(105, 80).contains("#24c"));
+        assert!(arm(105, 100).contains("#24c"));
+    }
+}
@@ -1,3 +1,4 @@
+use digital_component::BitState;
 use std::cmp::Ordering;
 use std::fmt::{Display, Error, Formatter};
 use std::ops::Range;
@@ -15,6 +16,28 @@ pub enum ParseError {
     },
     EndOfInput,
     InternalStateError,
+    /// a box labelled with a name there is no function for
+    UnknownComponent {
+        name: String,
+        position: Position,
+    },
+    /// a wire label no other wire is labelled with
+    UnmatchedTunnel {
+        name: String,
+        position: Position,
+    },
+    /// a box with no name in it to tell what component it is
+    UnnamedBox {
+        position: Position,
+    },
+}
+
+/// A box of a diagram with the name it is labelled with
+#[derive(Debug, PartialEq, Clone)]
+pub struct DiagramBox {
+    pub name: String,
+    pub top_left: Position,
+    pub bottom_right: Position,
 }
 
 #[derive(Debug)]
@@ -46,6 +69,28 @@ pub enum Node {
     Output {
         position: Position,
     },
+    /// The wire cell next to the `▶` of a wire label like `──▶clk` or
+    /// `clk▶──`, on the same net as every other wire with that label
+    Tunnel {
+        position: Position,
+        name: String,
+    },
+    /// The start of a wire driven by a constant like `1──` or `VCC──`
+    Constant {
+        position: Position,
+        value: BitState,
+    },
+    /// The start of a wire pulling its net like `PULLUP──` or `PULLDOWN──`
+    Pull {
+        position: Position,
+        value: BitState,
+    },
+    /// The start of a wire driven by the clock named like `~clk──`, an outer
+    /// input after the dangling ones shared by all wires of the clock
+    Clock {
+        position: Position,
+        name: String,
+    },
 }
 
 impl Node {
@@ -54,7 +99,12 @@ impl Node {
             Node::Wire { start, .. } => start.clone(),
             Node::Box { top_left, .. } => top_left.clone(),
             Node::Text { line, position, .. } => Position::new(*line, position.start),
-            Node::Input { position } | Node::Output { position } => position.clone(),
+            Node::Input { position }
+            | Node::Output { position }
+            | Node::Tunnel { position, .. }
+            | Node::Constant { position, .. }
+            | Node::Pull { position, .. }
+            | Node::Clock { position, .. } => position.clone(),
         }
     }
 }
@@ -69,12 +119,14 @@ pub enum Direction {
 }
 
 impl Direction {
+    /// Moving up from the first line or left from the first column gives a
+    /// position far past the end of the diagram, where no character is found
     pub fn move_cursor(&self, Position { line, column }: Position) -> Position {
         match self {
-            Direction::Up => Position::new(line - 1, column),
+            Direction::Up => Position::new(line.wrapping_sub(1), column),
             Direction::Down => Position::new(line + 1, column),
             Direction::Right => Position::new(line, column + 1),
-            Direction::Left => Position::new(line, column - 1),
+            Direction::Left => Position::new(line, column.wrapping_sub(1)),
         }
     }
 
@@ -124,6 +176,50 @@ impl Position {
     }
 }
 
+impl Display for ParseError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result<(), Error> {
+        match self {
+            ParseError::UnexpectedSymbol(position) => {
+                write!(f, "unexpected symbol at {position}")
+            }
+            ParseError::Looping => write!(f, "the wires go round in circles"),
+            ParseError::UnexpectedState { position, message } => {
+                write!(f, "{message} at {position}")
+            }
+            ParseError::LooseWire { position } => {
+                write!(f, "wire at {position} is not connected to anything")
+            }
+            ParseError::EndOfInput => write!(f, "unexpected end of the diagram"),
+            ParseError::InternalStateError => write!(f, "internal parser error"),
+            ParseError::UnknownComponent { name, position } => {
+                write!(f, "unknown component `{name}` at {position}")
+            }
+            ParseError::UnmatchedTunnel { name, position } => {
+                write!(
+                    f,
+                    "no other wire is labelled `{name}` like the one at {position}"
+                )
+            }
+            ParseError::UnnamedBox { position } => write!(f, "box at {position} has no name"),
+        }
+    }
+}
+
+impl ParseError {
+    /// Where in the diagram the error is, if anywhere in particular
+    pub fn position(&self) -> Option<&Position> {
+        match self {
+            ParseError::UnexpectedSymbol(position)
+            | ParseError::UnexpectedState { position, .. }
+            | ParseError::LooseWire { position }
+            | ParseError::UnknownComponent { position, .. }
+            | ParseError::UnmatchedTunnel { position, .. }
+            | ParseError::UnnamedBox { position } => Some(position),
+            ParseError::Looping | ParseError::EndOfInput | ParseError::InternalStateError => None,
+        }
+    }
+}
+
 impl Display for Position {
     fn fmt(&self, f: &mut Formatter<'_>) -> Result<(), Error> {
         write!(f, "{}:{}", self.line, self.column)
@@ -54,9 +54,9 @@ pub fn scan_for_wire_end(
             parse_later: vec![],
         },
         _ => {
-            if let Some(underlying_char) = input[symbol.position.line]
-                .chars()
-                .nth(symbol.position.column)
+            if let Some(underlying_char) = input
+                .get(symbol.position.line)
+                .and_then(|line| line.chars().nth(symbol.position.column))
             {
                 if !WIRE_JOINT.contains(underlying_char) && '┼' != underlying_char {
                     if visited.contains(&symbol.position) {
@@ -67,7 +67,7 @@ pub fn scan_for_wire_end(
                 }
                 follow_wire(&symbol, input, wire_start)?
             } else {
-                panic!("Missing structural symbol at {:?}", symbol.position);
+                return Err(ParseError::EndOfInput);
             }
         }
     })
@@ -98,7 +98,9 @@ fn follow_wire(
     };
 
     let next_position = next_direction.move_cursor(symbol.position.clone());
-    let next_char = input[next_position.line].chars().nth(next_position.column);
+    let next_char = input
+        .get(next_position.line)
+        .and_then(|line| line.chars().nth(next_position.column));
     Ok(match next_char {
         Some(character) if WIRE_SYMBOLS.contains(character) || "┨┠".contains(character) => {
             ScannerResult {
@@ -0,0 +1,366 @@
+use crate::nand::nand;
+use crate::not::not;
+use crate::synthesis::NandBuilder;
+use digital_component::{
+    ComponentInput, ComponentLogic, ComponentLogicFactory, ComponentOutput, DigitalComponent,
+    Graph, GraphNodeRef, NodeKind,
+};
+use parser::parse;
+use std::collections::HashMap;
+
+pub fn half_adder() -> Box<ComponentLogic> {
+    half_adder_graph().finalize()
+}
+
+/// Adds two bits: a and b inputs, sum and carry outputs.
+///
+/// The sum is the XOR of the inputs made of four NAND gates, the carry their
+/// AND, sharing the first of the NAND gates
+pub fn half_adder_graph() -> Graph {
+    let mut functions: HashMap<&str, Box<ComponentLogicFactory>> = HashMap::new();
+    functions.insert("nand", Box::new(|| Box::new(nand)));
+    functions.insert("not", Box::new(not));
+    parse(
+        "
+                           ┏━━━━━━┓
+         ──┬───────────────┨      ┃
+           │  ┏━━━━━━┓     ┃ nand ┠──┐
+           └──┨      ┃   ┌─┨      ┃  │ ┏━━━━━━┓
+              ┃ nand ┠─┬─┘ ┗━━━━━━┛  └─┨      ┃
+         ──┬──┨      ┃ │               ┃ nand ┠───
+           │  ┗━━━━━━┛ │ ┏━━━━━━┓    ┌─┨      ┃
+           └───────────┼─┨      ┃    │ ┗━━━━━━┛
+                       │ ┃ nand ┠────┘
+                       ├─┨      ┃
+                       │ ┗━━━━━━┛
+                       │ ┏━━━━━┓
+                       └─┨ not ┠─────────────
+                         ┗━━━━━┛
+    ",
+        &functions,
+    )
+    .unwrap()
+}
+
+pub fn full_adder() -> Box<ComponentLogic> {
+    full_adder_graph().finalize()
+}
+
+/// Adds three bits: a, b and carry in inputs, sum and carry out outputs.
+///
+/// The carry in is added to the sum of a and b, either of the two additions
+/// carrying out
+pub fn full_adder_graph() -> Graph {
+    let mut functions: HashMap<&str, Box<ComponentLogicFactory>> = HashMap::new();
+    functions.insert("nand", Box::new(|| Box::new(nand)));
+    functions.insert("not", Box::new(not));
+    functions.insert("half_adder", Box::new(half_adder));
+    parse(
+        "
+             ┏━━━━━━━━━━━━━━┓
+         ────┨              ┠─────┐   ┏━━━━━━━━━━━━━━┓
+             ┃  half_adder  ┃     └───┨              ┠───────────────────────────
+         ────┨              ┠──┐      ┃  half_adder  ┃
+             ┗━━━━━━━━━━━━━━┛  │   ┌──┨              ┠───┐
+                               │   │  ┗━━━━━━━━━━━━━━┛   │
+         ──────────────────────┼───┘                     │  ┏━━━━━┓
+                               │                         └──┨ not ┠─┐ ┏━━━━━━┓
+                               │                            ┗━━━━━┛ └─┨      ┃
+                               │                            ┏━━━━━┓   ┃ nand ┠───
+                               └────────────────────────────┨ not ┠───┨      ┃
+                                                            ┗━━━━━┛   ┗━━━━━━┛
+    ",
+        &functions,
+    )
+    .unwrap()
+}
+
+pub fn adder(n: usize) -> Box<ComponentLogicFactory> {
+    Box::new(move || -> Box<ComponentLogic> { adder_graph(n).finalize() })
+}
+
+/// Ripple carry adder of n full adders: n bits of a, n bits of b and carry in
+/// inputs, n sum bits and carry out outputs, the numbers being most
+/// significant bit first.
+/// The carry out of every full adder is the carry in of the next, more
+/// significant one, so for n = 2 an equivalent to the following circuit will
+/// be generated:
+///
+///              ┏━━━━━━━━━━━━┓
+///  a1 ─────────┨            ┠───── s1
+///  b1 ─────────┨ full_adder ┃
+///           ┌──┨            ┠───── carry out
+///           │  ┗━━━━━━━━━━━━┛
+///           │  ┏━━━━━━━━━━━━┓
+///  a0 ──────┼──┨            ┠───── s0
+///  b0 ──────┼──┨ full_adder ┃
+///  carry in ┼──┨            ┠──┐
+///           │  ┗━━━━━━━━━━━━┛  │
+///           └──────────────────┘
+///
+pub fn adder_graph(n: usize) -> Graph {
+    let mut graph = Graph::default();
+    // from the least significant bit
+    let full_adders = (0..n)
+        .map(|_| {
+            graph.add_component(DigitalComponent::new(3, 2, full_adder()).with_name("full_adder"))
+        })
+        .collect::<Vec<_>>();
+
+    for (bit, full_adder) in full_adders.iter().enumerate() {
+        let pin = n - 1 - bit;
+        for (outer_input, input) in [(pin, 0), (n + pin, 1)] {
+            let outer_input = graph.add_node(NodeKind::Input(outer_input));
+            let input = graph.add_node(NodeKind::ComponentInput(ComponentInput::new(
+                *full_adder,
+                input,
+            )));
+            graph.add_edge(&outer_input, &input);
+        }
+        let outer_output = graph.add_node(NodeKind::Output(pin));
+        let sum = graph.add_node(NodeKind::ComponentOutput(ComponentOutput::new(
+            *full_adder,
+            0,
+        )));
+        graph.add_edge(&outer_output, &sum);
+    }
+
+    let carry_ins = full_adders
+        .iter()
+        .map(|full_adder| {
+            graph.add_node(NodeKind::ComponentInput(ComponentInput::new(
+                *full_adder,
+                2,
+            )))
+        })
+        .collect::<Vec<_>>();
+    let carry_outs = full_adders
+        .iter()
+        .map(|full_adder| {
+            graph.add_node(NodeKind::ComponentOutput(ComponentOutput::new(
+                *full_adder,
+                1,
+            )))
+        })
+        .collect::<Vec<_>>();
+
+    let carry_in = graph.add_node(NodeKind::Input(2 * n));
+    graph.add_edge(&carry_in, &carry_ins[0]);
+    for (carry_out, next_carry_in) in carry_outs.iter().zip(carry_ins.iter().skip(1)) {
+        graph.add_edge(carry_out, next_carry_in);
+    }
+    let carry_out = graph.add_node(NodeKind::Output(n));
+    graph.add_edge(&carry_out, carry_outs.iter().last().unwrap());
+
+    graph
+}
+
+pub fn adder_cla(n: usize) -> Box<ComponentLogicFactory> {
+    Box::new(move || -> Box<ComponentLogic> { adder_cla_graph(n).finalize() })
+}
+
+/// Carry lookahead adder of NAND gates with the same inputs and outputs as
+/// [`adder_graph`].
+///
+/// A bit generates a carry when both a and b are on and propagates the one
+/// coming in when exactly one of them is. A Kogge–Stone prefix network merges
+/// these into the generate and propagate signals of groups of bits twice as
+/// wide at every level, so all the carries are known after log2(n) levels
+/// instead of rippling through every bit below them
+pub fn adder_cla_graph(n: usize) -> Graph {
+    let mut builder = NandBuilder::new(2 * n + 1);
+    let carry_in = builder.input(2 * n);
+
+    // from the least significant bit
+    let mut propagate = vec![];
+    let mut groups = vec![];
+    for bit in 0..n {
+        let a = builder.input(n - 1 - bit);
+        let b = builder.input(2 * n - 1 - bit);
+        let not_generate = builder.nand(a, b);
+        let a_only = builder.nand(a, not_generate);
+        let b_only = builder.nand(b, not_generate);
+        propagate.push(builder.nand(a_only, b_only));
+        groups.push(Generate {
+            on: None,
+            off: Some(not_generate),
+        });
+    }
+    // the group of the least significant bit takes in the carry in, so the
+    // generate signal of every group reaching down to it is a carry
+    let carried = builder.nand(propagate[0], carry_in);
+    let not_generate = groups[0].off(&mut builder);
+    groups[0] = Generate {
+        on: Some(builder.nand(not_generate, carried)),
+        off: None,
+    };
+    // none for the groups reaching the least significant bit
+    let mut group_propagate = propagate
+        .iter()
+        .enumerate()
+        .map(|(bit, propagate)| (bit > 0).then_some(*propagate))
+        .collect::<Vec<_>>();
+
+    let mut distance = 1;
+    while distance < n {
+        // from the most significant bit, so the groups below are still the
+        // ones of the previous level
+        for bit in (distance..n).rev() {
+            let high_propagate = group_propagate[bit].unwrap();
+            let low_generate = groups[bit - distance].on(&mut builder);
+            let carried = builder.nand(high_propagate, low_generate);
+            let not_generate = groups[bit].off(&mut builder);
+            groups[bit] = Generate {
+                on: Some(builder.nand(not_generate, carried)),
+                off: None,
+            };
+            group_propagate[bit] = group_propagate[bit - distance].map(|low_propagate| {
+                let both = builder.nand(high_propagate, low_propagate);
+                builder.not(both)
+            });
+        }
+        distance *= 2;
+    }
+
+    let mut carry = carry_in;
+    for bit in 0..n {
+        let sum = builder.xor(propagate[bit], carry);
+        builder.output(n - 1 - bit, sum);
+        carry = groups[bit].on(&mut builder);
+    }
+    builder.output(n, carry);
+    builder.into_graph()
+}
+
+/// Generate signal of a group of bits, inverted only when the other
+/// polarity is needed
+struct Generate {
+    on: Option<GraphNodeRef>,
+    off: Option<GraphNodeRef>,
+}
+
+impl Generate {
+    fn on(&mut self, builder: &mut NandBuilder) -> GraphNodeRef {
+        let off = self.off;
+        *self.on.get_or_insert_with(|| builder.not(off.unwrap()))
+    }
+
+    fn off(&mut self, builder: &mut NandBuilder) -> GraphNodeRef {
+        let on = self.on;
+        *self.off.get_or_insert_with(|| builder.not(on.unwrap()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::registry::components;
+    use crate::testing::{bits, evaluate, logic_network};
+    use digital_component::{check_equivalence, BitState::*, Equivalence, Statistics, TruthTable};
+
+    #[test]
+    fn half_adder_adds_two_bits() {
+        let table = TruthTable::generate(&mut *half_adder(), 2, 2).unwrap();
+        let expected = TruthTable::parse(
+            "
+            | a | b | sum | carry |
+            |---|---|-----|-------|
+            | 0 | 0 | 0   | 0     |
+            | 0 | 1 | 1   | 0     |
+            | 1 | 0 | 1   | 0     |
+            | 1 | 1 | 0   | 1     |
+            ",
+            2,
+        )
+        .unwrap();
+
+        assert_eq!(table.mismatches(&expected), vec![]);
+    }
+
+    #[test]
+    fn full_adder_matches_truth_table() {
+        let expected = TruthTable::load(
+            concat!(env!("CARGO_MANIFEST_DIR"), "/truth_tables/full_adder.md"),
+            3,
+        )
+        .unwrap();
+
+        let table = TruthTable::generate(&mut *full_adder(), 3, 2).unwrap();
+
+        assert_eq!(table.mismatches(&expected), vec![]);
+    }
+
+    #[test]
+    fn adds_every_pair_of_numbers() {
+        for n in 1..=8 {
+            let network = logic_network(adder_graph(n));
+            // the simulated adder is only checked in the fewer bits
+            let mut simulated = (n <= 3).then(|| adder(n)());
+            let mut output = vec![Undefined; n + 1];
+            for a in 0..1 << n {
+                for b in 0..1 << n {
+                    for carry in 0..2 {
+                        let inputs = [bits(a, n), bits(b, n), bits(carry, 1)].concat();
+                        let sum = a + b + carry;
+                        let expected = [bits(sum, n), bits(sum >> n, 1)].concat();
+                        assert_eq!(
+                            evaluate(&network, &inputs),
+                            expected,
+                            "{a} + {b} + {carry} in {n} bits"
+                        );
+                        if let Some(adder) = &mut simulated {
+                            adder(&inputs, &mut output);
+                            assert_eq!(output, expected, "simulated {a} + {b} + {carry}");
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn simulates_additions() {
+        let mut adder = adder(4)();
+        let mut output = vec![Undefined; 5];
+        for a in 0..16 {
+            for b in 0..16 {
+                for carry in 0..2 {
+                    adder(
+                        &[bits(a, 4), bits(b, 4), bits(carry, 1)].concat(),
+                        &mut output,
+                    );
+                    let sum = a + b + carry;
+                    assert_eq!(output, [bits(sum, 4), bits(sum >> 4, 1)].concat());
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn lookahead_adder_matches_ripple_adder() {
+        let registry = components();
+        for n in [1, 2, 3, 8, 12] {
+            assert_eq!(
+                check_equivalence(adder_graph(n), adder_cla_graph(n), &|name| registry
+                    .graph(name)),
+                Ok(Equivalence::Equivalent),
+                "{n} bits"
+            );
+        }
+    }
+
+    #[test]
+    fn lookahead_adder_is_shallower() {
+        let registry = components();
+        let depth =
+            |graph: Graph| Statistics::new(&graph.flatten(&|name| registry.graph(name))).max_depth;
+
+        // after the XOR of the inputs of the first bit, the carry goes through
+        // four NANDs in every full adder
+        assert_eq!(depth(adder_graph(16)), 3 + 4 * 16);
+        // and through two for every level of the prefix network, give or take
+        // the inverters between them
+        assert_eq!(depth(adder_cla_graph(16)), 14);
+        assert!(depth(adder_cla_graph(32)) < depth(adder_graph(8)));
+    }
+}
@@ -0,0 +1,342 @@
+use crate::adder::adder;
+use crate::and::and;
+use crate::builder::{add_component, add_gate};
+use crate::comparator::comparator;
+use crate::mux::{mux, mux_2_to_1};
+use crate::not::not;
+use crate::or::{cascade_or, nor, or};
+use crate::shifter::barrel_shifter;
+use crate::xor::{xnor, xor};
+use digital_component::{
+    BitState, ComponentLogic, ComponentLogicFactory, ComponentOutput, DigitalComponent, Graph,
+    GraphNodeRef, NodeKind,
+};
+use parser::parse;
+use std::collections::HashMap;
+
+pub fn alu_flags() -> Box<ComponentLogic> {
+    alu_flags_graph().finalize()
+}
+
+/// Carry and overflow flags of the ALU: the adder's carry out, whether the
+/// operation adds or subtracts, the sign bits of a, of b as it reaches the
+/// adder and of the sum as inputs, carry and overflow as outputs.
+///
+/// Both flags are off for the other operations. The sum overflows when a and
+/// b have the same sign and it does not
+pub fn alu_flags_graph() -> Graph {
+    let mut functions: HashMap<&str, Box<ComponentLogicFactory>> = HashMap::new();
+    functions.insert("and", Box::new(and));
+    functions.insert("xor", Box::new(xor));
+    functions.insert("xnor", Box::new(xnor));
+    parse(
+        "
+                                          ┏━━━━━┓
+         ─────────────────────────────────┨     ┃
+                                          ┃ and ┠───
+         ──────────────────────────────┬──┨     ┃
+                                       │  ┗━━━━━┛
+                 ┏━━━━━━┓              │
+         ───┬────┨      ┃    ┏━━━━━┓   │  ┏━━━━━┓
+            │    ┃ xnor ┠────┨     ┃   └──┨     ┃
+         ───┼────┨      ┃    ┃     ┃      ┃     ┃
+            │    ┗━━━━━━┛    ┃ and ┃      ┃ and ┠───
+            │    ┏━━━━━━┓    ┃     ┠──────┨     ┃
+            └────┨      ┃    ┃     ┃      ┃     ┃
+                 ┃ xor  ┠────┨     ┃      ┗━━━━━┛
+         ────────┨      ┃    ┗━━━━━┛
+                 ┗━━━━━━┛
+    ",
+        &functions,
+    )
+    .unwrap()
+}
+
+pub fn alu(width: usize) -> Box<ComponentLogicFactory> {
+    Box::new(move || -> Box<ComponentLogic> { alu_graph(width).finalize() })
+}
+
+/// ALU of `width` bit words, `width` being a power of two: a, b and a four
+/// bit opcode as inputs, all most significant bit first, the result and then
+/// the zero, carry, negative and overflow flags as outputs. The opcodes are
+///
+/// * 0000: a + b
+/// * 0001: a - b, carry being on unless it borrows
+/// * 0010: a AND b
+/// * 0011: a OR b
+/// * 0100: a XOR b
+/// * 0101: a NOR b
+/// * 0110: 1 if a < b as two's complement numbers, 0 otherwise
+/// * 0111: 1 if a < b as unsigned numbers, 0 otherwise
+/// * 1000: a shifted left by b
+/// * 1001: a shifted right by b, logically
+/// * 1010: a shifted right by b, arithmetically
+/// * 1011: a rotated right by b
+///
+/// with 1100 to 1111 repeating the shifts, which only take the log2(width)
+/// low bits of b. Carry and overflow are only ever on for additions and
+/// subtractions
+pub fn alu_graph(width: usize) -> Graph {
+    assert!(
+        width.is_power_of_two(),
+        "an ALU width must be a power of two"
+    );
+    let amount_bits = width.trailing_zeros() as usize;
+    let mut graph = Graph::default();
+    let a = (0..width)
+        .map(|bit| graph.add_node(NodeKind::Input(bit)))
+        .collect::<Vec<_>>();
+    let b = (0..width)
+        .map(|bit| graph.add_node(NodeKind::Input(width + bit)))
+        .collect::<Vec<_>>();
+    let opcode = (0..4)
+        .map(|bit| graph.add_node(NodeKind::Input(2 * width + bit)))
+        .collect::<Vec<_>>();
+    let subtract = opcode[3];
+
+    // a - b is a + NOT b + 1
+    let adder_b = b
+        .iter()
+        .map(|bit| add_gate(&mut graph, "xor", xor(), &[*bit, subtract]))
+        .collect::<Vec<_>>();
+    let adder_inputs = [a.clone(), adder_b.clone(), vec![subtract]].concat();
+    let adder_outputs = add_component(
+        &mut graph,
+        &format!("adder({width})"),
+        adder(width)(),
+        &adder_inputs,
+        width + 1,
+    );
+    let sum = &adder_outputs[..width];
+
+    let gate_words = [
+        ("and", and as fn() -> Box<ComponentLogic>),
+        ("or", or),
+        ("xor", xor),
+        ("nor", nor),
+    ]
+    .map(|(name, gate)| {
+        a.iter()
+            .zip(&b)
+            .map(|(a_bit, b_bit)| add_gate(&mut graph, name, gate(), &[*a_bit, *b_bit]))
+            .collect::<Vec<_>>()
+    });
+
+    let unsigned = opcode[3];
+    let signed = add_gate(&mut graph, "not", not(), &[unsigned]);
+    let comparator_inputs = [a.clone(), b.clone(), vec![signed]].concat();
+    let comparison = add_component(
+        &mut graph,
+        &format!("comparator({width})"),
+        comparator(width)(),
+        &comparator_inputs,
+        3,
+    );
+    let gnd = graph.add_component(DigitalComponent::constant(BitState::Off));
+    let gnd = graph.add_node(NodeKind::ComponentOutput(ComponentOutput::new(gnd, 0)));
+    let less = [vec![gnd; width - 1], vec![comparison[1]]].concat();
+
+    let words: [&[GraphNodeRef]; 8] = [
+        sum,
+        sum,
+        &gate_words[0],
+        &gate_words[1],
+        &gate_words[2],
+        &gate_words[3],
+        &less,
+        &less,
+    ];
+    let mux_inputs = [words.concat(), opcode[1..].to_vec()].concat();
+    let not_shifted = add_component(
+        &mut graph,
+        &format!("mux(3,{width})"),
+        mux(3, width)(),
+        &mux_inputs,
+        width,
+    );
+
+    let shifter_inputs = [
+        a.clone(),
+        b[width - amount_bits..].to_vec(),
+        opcode[2..].to_vec(),
+    ]
+    .concat();
+    let shifted = add_component(
+        &mut graph,
+        &format!("barrel_shifter({width})"),
+        barrel_shifter(width)(),
+        &shifter_inputs,
+        width,
+    );
+
+    let result = not_shifted
+        .iter()
+        .zip(&shifted)
+        .map(|(not_shifted, shifted)| {
+            add_gate(
+                &mut graph,
+                "mux_2_to_1",
+                mux_2_to_1(),
+                &[*not_shifted, *shifted, opcode[0]],
+            )
+        })
+        .collect::<Vec<_>>();
+
+    let any_on = add_gate(
+        &mut graph,
+        &format!("cascade_or({width})"),
+        cascade_or(width)(),
+        &result,
+    );
+    let zero = add_gate(&mut graph, "not", not(), &[any_on]);
+    let negative = result[0];
+
+    let other_operation = add_gate(&mut graph, "or", or(), &[opcode[1], opcode[2]]);
+    let other_operation = add_gate(&mut graph, "or", or(), &[opcode[0], other_operation]);
+    let arithmetic = add_gate(&mut graph, "not", not(), &[other_operation]);
+    let flags = add_component(
+        &mut graph,
+        "alu_flags",
+        alu_flags(),
+        &[adder_outputs[width], arithmetic, a[0], adder_b[0], sum[0]],
+        2,
+    );
+
+    let outputs = [result, vec![zero, flags[0], negative, flags[1]]].concat();
+    for (idx, output) in outputs.iter().enumerate() {
+        let outer_output = graph.add_node(NodeKind::Output(idx));
+        graph.add_edge(&outer_output, output);
+    }
+    graph
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::registry::components;
+    use crate::testing::{bits, evaluate, logic_network};
+    use digital_component::BitState::*;
+    use parser::{names, render};
+
+    /// What the ALU should output: the result and the zero, carry, negative
+    /// and overflow flags
+    fn model(opcode: usize, a: usize, b: usize, width: usize) -> (usize, [bool; 4]) {
+        let mask = (1 << width) - 1;
+        let sign = |value: usize| value >> (width - 1) & 1 == 1;
+        let signed = |value: usize| {
+            if sign(value) {
+                value as i64 - (1 << width)
+            } else {
+                value as i64
+            }
+        };
+        let amount = b & (width - 1);
+        let (result, carry, overflow) = match opcode {
+            0 => {
+                let sum = a + b;
+                let overflow = sign(a) == sign(b) && sign(sum) != sign(a);
+                (sum & mask, sum > mask, overflow)
+            }
+            1 => {
+                let difference = a.wrapping_sub(b) & mask;
+                let overflow = sign(a) != sign(b) && sign(difference) != sign(a);
+                (difference, a >= b, overflow)
+            }
+            2 => (a & b, false, false),
+            3 => (a | b, false, false),
+            4 => (a ^ b, false, false),
+            5 => (!(a | b) & mask, false, false),
+            6 => ((signed(a) < signed(b)) as usize, false, false),
+            7 => ((a < b) as usize, false, false),
+            _ => {
+                let shifted = match opcode & 3 {
+                    0 => a << amount & mask,
+                    1 => a >> amount,
+                    2 => (signed(a) >> amount) as usize & mask,
+                    _ => (a >> amount | a << (width - amount)) & mask,
+                };
+                (shifted, false, false)
+            }
+        };
+        (result, [result == 0, carry, sign(result), overflow])
+    }
+
+    /// Checks the ALU against the model on every combination of inputs the
+    /// iterator yields
+    fn check(width: usize, cases: impl Iterator<Item = (usize, usize, usize)>) {
+        let network = logic_network(alu_graph(width));
+        for (opcode, a, b) in cases {
+            let inputs = [bits(a, width), bits(b, width), bits(opcode, 4)].concat();
+            let (result, flags) = model(opcode, a, b, width);
+            let flags = flags.iter().flat_map(|flag| bits(*flag as usize, 1));
+            assert_eq!(
+                evaluate(&network, &inputs),
+                bits(result, width)
+                    .into_iter()
+                    .chain(flags)
+                    .collect::<Vec<_>>(),
+                "opcode {opcode:04b} of {a:#x} and {b:#x} in {width} bits"
+            );
+        }
+    }
+
+    #[test]
+    fn matches_model_on_every_input() {
+        for width in [1, 2, 4] {
+            check(
+                width,
+                (0..16).flat_map(|opcode| {
+                    (0..1 << width).flat_map(move |a| (0..1 << width).map(move |b| (opcode, a, b)))
+                }),
+            );
+        }
+    }
+
+    #[test]
+    fn matches_model_on_random_inputs() {
+        // xorshift, so that every run checks the same inputs
+        let mut state = 0x2545_f491_4f6c_dd1d_u64;
+        let mut random = move || {
+            state ^= state << 13;
+            state ^= state >> 7;
+            state ^= state << 17;
+            state as usize
+        };
+        for width in [8, 16] {
+            let mask = (1 << width) - 1;
+            let cases = (0..2000)
+                .map(|_| (random() & 15, random() & mask, random() & mask))
+                .collect::<Vec<_>>();
+            check(width, cases.into_iter());
+        }
+    }
+
+    #[test]
+    fn renders_a_diagram_that_parses() {
+        let registry = components();
+        let rendered = render(alu_graph(2));
+        let names = names(&rendered);
+
+        assert!(parse(&rendered, &registry.functions(&names)).is_ok());
+    }
+
+    #[test]
+    fn simulates_operations() {
+        let mut alu = alu(4)();
+        let mut output = vec![Undefined; 8];
+
+        // 0111 + 0001 overflows into the sign bit
+        alu(
+            &[Off, On, On, On, Off, Off, Off, On, Off, Off, Off, Off],
+            &mut output,
+        );
+        assert_eq!(output, vec![On, Off, Off, Off, Off, Off, On, On]);
+
+        // 0011 - 0011 is zero without a borrow
+        alu(
+            &[Off, Off, On, On, Off, Off, On, On, Off, Off, Off, On],
+            &mut output,
+        );
+        assert_eq!(output, vec![Off, Off, Off, Off, On, On, Off, Off]);
+    }
+}
@@ -1,13 +1,15 @@
+use crate::builder::cascade_graph;
 use crate::nand::nand;
 use crate::not::not;
-use digital_component::{
-    ComponentInput, ComponentLogic, ComponentLogicFactory, ComponentOutput, DigitalComponent,
-    Graph, NodeKind,
-};
+use digital_component::{ComponentLogic, ComponentLogicFactory, Graph};
 use parser::parse;
 use std::collections::HashMap;
 
 pub fn and() -> Box<ComponentLogic> {
+    and_graph().finalize()
+}
+
+pub fn and_graph() -> Graph {
     let mut functions: HashMap<&str, Box<ComponentLogicFactory>> = HashMap::new();
     functions.insert("NAND", Box::new(|| Box::new(nand)));
     functions.insert("NOT", Box::new(not));
@@ -22,70 +24,30 @@ pub fn and() -> Box<ComponentLogic> {
         &functions,
     )
     .unwrap()
-    .finalize()
 }
 
 /// Cascade n - 1 AND gates to have an n input AND gate.
-/// For example for n = 3 an equivalent to the following circuit will be generated:
+/// The gates are paired up level by level into a balanced tree, so the
+/// output is only log2(n) gates away from any input.
+/// For example for n = 4 an equivalent to the following circuit will be generated:
 ///
 ///     ┏━━━━━━━┓
 ///  ───┨       ┃
 ///     ┃  AND  ┠──┐  ┏━━━━━━━┓
 ///  ───┨       ┃  └──┨       ┃
 ///     ┗━━━━━━━┛     ┃  AND  ┠────
-///  ─────────────────┃       ┃
-///                   ┗━━━━━━━┛
+///     ┏━━━━━━━┓  ┌──┨       ┃
+///  ───┨       ┃  │  ┗━━━━━━━┛
+///     ┃  AND  ┠──┘
+///  ───┨       ┃
+///     ┗━━━━━━━┛
 ///
 pub fn cascade_and(n: usize) -> Box<ComponentLogicFactory> {
-    Box::new(move || -> Box<ComponentLogic> {
-        let mut graph = Graph::default();
-        let and_gates = (0..n - 1)
-            .map(|_| graph.add_component(DigitalComponent::new(2, 1, and())))
-            .collect::<Vec<_>>();
-
-        let outer_inputs = (0..n)
-            .map(|input_idx| graph.add_node(NodeKind::Input(input_idx)))
-            .collect::<Vec<_>>();
-
-        let outer_output = graph.add_node(NodeKind::Output(0));
-
-        let and_gates_inputs = and_gates
-            .iter()
-            .map(|and_gate| {
-                (
-                    graph.add_node(NodeKind::ComponentInput(ComponentInput::new(*and_gate, 0))),
-                    graph.add_node(NodeKind::ComponentInput(ComponentInput::new(*and_gate, 1))),
-                )
-            })
-            .collect::<Vec<_>>();
-
-        let and_gates_outputs = and_gates
-            .iter()
-            .map(|and_gate| {
-                graph.add_node(NodeKind::ComponentOutput(ComponentOutput::new(
-                    *and_gate, 0,
-                )))
-            })
-            .collect::<Vec<_>>();
-
-        graph.add_edge(&outer_inputs[0], &and_gates_inputs[0].0);
-
-        for (outer_input, and_gate_inputs) in outer_inputs[1..n].iter().zip(and_gates_inputs.iter())
-        {
-            graph.add_edge(outer_input, &and_gate_inputs.1);
-        }
-
-        for (and_gate_output, next_and_gate_inputs) in and_gates_outputs
-            .iter()
-            .zip(and_gates_inputs.iter().skip(1))
-        {
-            graph.add_edge(and_gate_output, &next_and_gate_inputs.0);
-        }
-
-        graph.add_edge(&outer_output, and_gates_outputs.iter().last().unwrap());
-
-        graph.finalize()
-    })
+    Box::new(move || -> Box<ComponentLogic> { cascade_and_graph(n).finalize() })
+}
+
+pub fn cascade_and_graph(n: usize) -> Graph {
+    cascade_graph(n, "and", and)
 }
 
 #[cfg(test)]
@@ -130,4 +92,17 @@ mod tests {
         and_gate(&[On, On, On], &mut output);
         assert_eq!(output, vec![On]);
     }
+
+    #[test]
+    fn cascades_a_single_input() {
+        let mut and_gate = cascade_and(1)();
+
+        let mut output = vec![Undefined];
+
+        and_gate(&[On], &mut output);
+        assert_eq!(output, vec![On]);
+
+        and_gate(&[Off], &mut output);
+        assert_eq!(output, vec![Off]);
+    }
 }
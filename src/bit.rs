@@ -1,13 +1,17 @@
 use crate::nand::nand;
-use digital_component::{ComponentLogic, ComponentLogicFactory};
+use digital_component::{ComponentLogic, ComponentLogicFactory, Graph};
 use parser::parse;
 use std::collections::HashMap;
 
 pub fn bit() -> Box<ComponentLogic> {
+    bit_graph().finalize()
+}
+
+pub fn bit_graph() -> Graph {
     let mut functions: HashMap<&str, Box<ComponentLogicFactory>> = HashMap::new();
     functions.insert("NAND", Box::new(|| Box::new(nand)));
     parse(
-       "
+        "
              ┏━━━━━━┓                    ┏━━━━━━┓
          ────┨      ┠─┬──────────────────┨      ┃
              ┃ NAND ┃ │                  ┃ NAND ┠───┬───
@@ -25,7 +29,6 @@ pub fn bit() -> Box<ComponentLogic> {
         &functions,
     )
     .unwrap()
-    .finalize()
 }
 
 #[cfg(test)]
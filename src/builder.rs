@@ -0,0 +1,65 @@
+use digital_component::{
+    ComponentInput, ComponentLogic, ComponentOutput, DigitalComponent, Graph, GraphNodeRef,
+    NodeKind,
+};
+
+/// Combines n inputs with n - 1 of the 2 input gates to have an n input gate,
+/// like [`cascade_and`](crate::and::cascade_and) does with AND gates. The gates pair up the signals
+/// level by level into a balanced tree, so no input is more than log2(n)
+/// gates away from the output. For n = 1 the input is wired straight to the
+/// output
+pub fn cascade_graph(n: usize, name: &str, gate: fn() -> Box<ComponentLogic>) -> Graph {
+    let mut graph = Graph::default();
+    let mut level = (0..n)
+        .map(|input_idx| graph.add_node(NodeKind::Input(input_idx)))
+        .collect::<Vec<_>>();
+    while level.len() > 1 {
+        level = level
+            .chunks(2)
+            .map(|pair| match pair {
+                [a, b] => add_gate(&mut graph, name, gate(), &[*a, *b]),
+                // an odd one out waits for the next level
+                _ => pair[0],
+            })
+            .collect();
+    }
+
+    let outer_output = graph.add_node(NodeKind::Output(0));
+    graph.add_edge(&outer_output, &level[0]);
+    graph
+}
+
+/// Adds a single output gate taking the nodes as its inputs and returns its output
+pub fn add_gate(
+    graph: &mut Graph,
+    name: &str,
+    logic: Box<ComponentLogic>,
+    inputs: &[GraphNodeRef],
+) -> GraphNodeRef {
+    add_component(graph, name, logic, inputs, 1)[0]
+}
+
+/// Adds a component taking the nodes as its inputs and returns its outputs
+pub fn add_component(
+    graph: &mut Graph,
+    name: &str,
+    logic: Box<ComponentLogic>,
+    inputs: &[GraphNodeRef],
+    output_num: usize,
+) -> Vec<GraphNodeRef> {
+    let component =
+        graph.add_component(DigitalComponent::new(inputs.len(), output_num, logic).with_name(name));
+    for (idx, input) in inputs.iter().enumerate() {
+        let component_input = graph.add_node(NodeKind::ComponentInput(ComponentInput::new(
+            component, idx,
+        )));
+        graph.add_edge(input, &component_input);
+    }
+    (0..output_num)
+        .map(|idx| {
+            graph.add_node(NodeKind::ComponentOutput(ComponentOutput::new(
+                component, idx,
+            )))
+        })
+        .collect()
+}
@@ -0,0 +1,107 @@
+use crate::and::and;
+use crate::builder::add_gate;
+use crate::not::not;
+use crate::or::{cascade_or, nor};
+use crate::xor::{xnor, xor};
+use digital_component::{ComponentLogic, ComponentLogicFactory, Graph, GraphNodeRef, NodeKind};
+
+pub fn comparator(n: usize) -> Box<ComponentLogicFactory> {
+    Box::new(move || -> Box<ComponentLogic> { comparator_graph(n).finalize() })
+}
+
+/// Comparator of two n bit numbers: a and b, both most significant bit first,
+/// and a signed input as inputs, a == b, a < b and a > b as outputs. The
+/// numbers are unsigned while signed is off and two's complement while it is
+/// on.
+///
+/// Flipping the sign bits of two's complement numbers orders them like
+/// unsigned ones, so signed flips the most significant bits of both. Then a
+/// is less than b when at the first bit they differ in a is off and b is on
+pub fn comparator_graph(n: usize) -> Graph {
+    let mut graph = Graph::default();
+    let mut a = (0..n)
+        .map(|bit| graph.add_node(NodeKind::Input(bit)))
+        .collect::<Vec<_>>();
+    let mut b = (0..n)
+        .map(|bit| graph.add_node(NodeKind::Input(n + bit)))
+        .collect::<Vec<_>>();
+    let signed = graph.add_node(NodeKind::Input(2 * n));
+
+    a[0] = add_gate(&mut graph, "xor", xor(), &[a[0], signed]);
+    b[0] = add_gate(&mut graph, "xor", xor(), &[b[0], signed]);
+
+    let mut equal_above: Option<GraphNodeRef> = None;
+    let mut less_terms = vec![];
+    for (a_bit, b_bit) in a.iter().zip(&b) {
+        let not_a = add_gate(&mut graph, "not", not(), &[*a_bit]);
+        let mut less = add_gate(&mut graph, "and", and(), &[not_a, *b_bit]);
+        let mut equal = add_gate(&mut graph, "xnor", xnor(), &[*a_bit, *b_bit]);
+        if let Some(equal_above) = equal_above {
+            less = add_gate(&mut graph, "and", and(), &[less, equal_above]);
+            equal = add_gate(&mut graph, "and", and(), &[equal, equal_above]);
+        }
+        less_terms.push(less);
+        equal_above = Some(equal);
+    }
+
+    let equal = equal_above.unwrap();
+    let less = add_gate(
+        &mut graph,
+        &format!("cascade_or({n})"),
+        cascade_or(n)(),
+        &less_terms,
+    );
+    let greater = add_gate(&mut graph, "nor", nor(), &[equal, less]);
+
+    for (idx, output) in [equal, less, greater].iter().enumerate() {
+        let outer_output = graph.add_node(NodeKind::Output(idx));
+        graph.add_edge(&outer_output, output);
+    }
+    graph
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::bits;
+    use digital_component::{BitState, BitState::*};
+    use std::cmp::Ordering;
+
+    /// The n bit two's complement number
+    fn signed(value: usize, n: usize) -> i64 {
+        if value >> (n - 1) & 1 == 1 {
+            value as i64 - (1 << n)
+        } else {
+            value as i64
+        }
+    }
+
+    fn outputs(ordering: Ordering) -> Vec<BitState> {
+        match ordering {
+            Ordering::Equal => vec![On, Off, Off],
+            Ordering::Less => vec![Off, On, Off],
+            Ordering::Greater => vec![Off, Off, On],
+        }
+    }
+
+    #[test]
+    fn compares_every_pair_of_numbers() {
+        for n in 1..=4 {
+            let mut comparator = comparator(n)();
+            let mut output = vec![Undefined; 3];
+
+            for a in 0..1 << n {
+                for b in 0..1 << n {
+                    let input = [bits(a, n), bits(b, n)].concat();
+
+                    comparator(&[input.clone(), vec![Off]].concat(), &mut output);
+                    assert_eq!(output, outputs(a.cmp(&b)), "unsigned {a} and {b}");
+
+                    comparator(&[input, vec![On]].concat(), &mut output);
+                    let (a, b) = (signed(a, n), signed(b, n));
+                    assert_eq!(output, outputs(a.cmp(&b)), "signed {a} and {b}");
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,170 @@
+use crate::and::and;
+use crate::builder::{add_component, add_gate};
+use crate::not::not;
+use crate::or::{cascade_or, or};
+use digital_component::{ComponentLogic, ComponentLogicFactory, Graph, GraphNodeRef, NodeKind};
+
+pub fn encoder(n: usize) -> Box<ComponentLogicFactory> {
+    Box::new(move || -> Box<ComponentLogic> { encoder_graph(n).finalize() })
+}
+
+/// 2^n to n encoder, the inverse of the decoder: 2^n inputs of which at most
+/// one is on, the number of that input in n bits, most significant first, and
+/// a valid output on while any input is.
+///
+/// Every output bit ORs together the inputs whose numbers have it set
+pub fn encoder_graph(n: usize) -> Graph {
+    let mut graph = Graph::default();
+    let inputs = (0..1 << n)
+        .map(|input| graph.add_node(NodeKind::Input(input)))
+        .collect::<Vec<_>>();
+
+    for bit in 0..n {
+        let weight = 1 << (n - 1 - bit);
+        let numbers = inputs
+            .iter()
+            .enumerate()
+            .filter(|(number, _)| number & weight != 0)
+            .map(|(_, input)| *input)
+            .collect::<Vec<_>>();
+        let or_output = add_cascade_or(&mut graph, &numbers);
+        let outer_output = graph.add_node(NodeKind::Output(bit));
+        graph.add_edge(&outer_output, &or_output);
+    }
+
+    let valid = add_cascade_or(&mut graph, &inputs);
+    let outer_output = graph.add_node(NodeKind::Output(n));
+    graph.add_edge(&outer_output, &valid);
+    graph
+}
+
+pub fn priority_encoder(n: usize) -> Box<ComponentLogicFactory> {
+    Box::new(move || -> Box<ComponentLogic> { priority_encoder_graph(n).finalize() })
+}
+
+/// 2^n to n priority encoder: 2^n inputs, the number of the highest numbered
+/// one that is on in n bits, most significant first, and a valid output on
+/// while any input is.
+///
+/// An input only reaches the encoder while none of the inputs above it is
+/// on. A prefix network of OR gates tells that for every input at once,
+/// ORing signals twice as far apart on every level like the lookahead adder
+/// does with its carries, so it is only log2(2^n) = n ORs deep
+pub fn priority_encoder_graph(n: usize) -> Graph {
+    let mut graph = Graph::default();
+    let inputs = (0..1 << n)
+        .map(|input| graph.add_node(NodeKind::Input(input)))
+        .collect::<Vec<_>>();
+
+    // whether any of the inputs from every one up is on
+    let mut any_from = inputs.clone();
+    let mut distance = 1;
+    while distance < any_from.len() {
+        any_from = (0..any_from.len())
+            .map(|input| match any_from.get(input + distance) {
+                Some(above) => add_gate(&mut graph, "or", or(), &[any_from[input], *above]),
+                None => any_from[input],
+            })
+            .collect();
+        distance *= 2;
+    }
+    let winners = inputs
+        .iter()
+        .enumerate()
+        .map(|(input, node)| match any_from.get(input + 1) {
+            Some(any_above) => {
+                let none_above = add_gate(&mut graph, "not", not(), &[*any_above]);
+                add_gate(&mut graph, "and", and(), &[*node, none_above])
+            }
+            None => *node,
+        })
+        .collect::<Vec<_>>();
+
+    add_encoder(&mut graph, n, &winners);
+    graph
+}
+
+/// Adds an `encoder(n)` component taking the nodes as its inputs, wired to
+/// the outputs of the graph
+fn add_encoder(graph: &mut Graph, n: usize, inputs: &[GraphNodeRef]) {
+    let outputs = add_component(graph, &format!("encoder({n})"), encoder(n)(), inputs, n + 1);
+    for (idx, output) in outputs.iter().enumerate() {
+        let outer_output = graph.add_node(NodeKind::Output(idx));
+        graph.add_edge(&outer_output, output);
+    }
+}
+
+fn add_cascade_or(graph: &mut Graph, inputs: &[GraphNodeRef]) -> GraphNodeRef {
+    let width = inputs.len();
+    add_gate(
+        graph,
+        &format!("cascade_or({width})"),
+        cascade_or(width)(),
+        inputs,
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::decoder::decoder;
+    use crate::registry::components;
+    use crate::testing::bits;
+    use digital_component::{BitState::*, Statistics};
+
+    #[test]
+    fn encodes_what_the_decoder_decodes() {
+        for n in 1..=4 {
+            let mut decoder = decoder(n)();
+            let mut encoder = encoder(n)();
+            let mut decoded = vec![Undefined; 1 << n];
+            let mut encoded = vec![Undefined; n + 1];
+
+            for value in 0..1 << n {
+                decoder(&bits(value, n), &mut decoded);
+                encoder(&decoded, &mut encoded);
+                assert_eq!(encoded[..n], bits(value, n), "encoder({n}) of {value}");
+                assert_eq!(encoded[n], On);
+            }
+            encoder(&vec![Off; 1 << n], &mut encoded);
+            assert_eq!(encoded[n], Off);
+        }
+    }
+
+    #[test]
+    fn encodes_highest_input_on() {
+        for n in 1..=3 {
+            let mut encoder = priority_encoder(n)();
+            let mut output = vec![Undefined; n + 1];
+
+            for inputs in 0..1_usize << (1 << n) {
+                // input 0 is the most significant bit, so the highest numbered
+                // input on is the lowest bit set
+                let input = bits(inputs, 1 << n);
+                encoder(&input, &mut output);
+                let expected = if inputs == 0 {
+                    [bits(0, n), vec![Off]].concat()
+                } else {
+                    let highest = (1 << n) - 1 - inputs.trailing_zeros() as usize;
+                    [bits(highest, n), vec![On]].concat()
+                };
+                assert_eq!(output, expected, "priority_encoder({n}) of {input:?}");
+            }
+        }
+    }
+
+    #[test]
+    fn finds_the_highest_input_through_a_tree() {
+        let registry = components();
+        let depth =
+            |graph: Graph| Statistics::new(&graph.flatten(&|name| registry.graph(name))).max_depth;
+
+        // every extra bit adds a level of ORs, two NANDs deep, to the prefix
+        // network and one to the ORs of the encoder, where a chain of ORs
+        // would get twice as deep
+        let depths = (1..=5)
+            .map(|n| depth(priority_encoder_graph(n)))
+            .collect::<Vec<_>>();
+        assert_eq!(depths, vec![5, 11, 15, 19, 23]);
+    }
+}
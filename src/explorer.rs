@@ -0,0 +1,447 @@
+use crate::registry::ComponentRegistry;
+use digital_component::{BitState, ComponentLogic, ComponentLogicFactory, Probe};
+use parser::{boxes, colorize, parse_with_wires, render, DiagramBox, ParseError, WireMap};
+use std::cell::RefCell;
+use std::collections::{HashMap, VecDeque};
+use std::rc::Rc;
+
+#[derive(Debug, PartialEq)]
+pub enum ExploreError {
+    Parse {
+        component: String,
+        error: ParseError,
+    },
+    /// the diagram of the component contains the component itself
+    Recursive {
+        component: String,
+    },
+    UnknownComponent {
+        component: String,
+    },
+}
+
+/// Diagrams of a `.circ` library, every one of them starting with a
+/// `# name` line, and the registered components to build them from
+pub struct Library<'a> {
+    registry: &'a ComponentRegistry,
+    diagrams: Vec<(String, String)>,
+    rendered: RefCell<HashMap<String, String>>,
+}
+
+impl<'a> Library<'a> {
+    pub fn new(registry: &'a ComponentRegistry) -> Library<'a> {
+        Library {
+            registry,
+            diagrams: vec![],
+            rendered: RefCell::new(HashMap::new()),
+        }
+    }
+
+    pub fn parse(text: &str, registry: &'a ComponentRegistry) -> Library<'a> {
+        let mut library = Library::new(registry);
+        for line in text.lines() {
+            match line.strip_prefix("# ") {
+                Some(name) => library.add(name.trim(), String::new()),
+                None => {
+                    if let Some((_, source)) = library.diagrams.last_mut() {
+                        *source += line;
+                        source.push('\n');
+                    }
+                }
+            }
+        }
+        library
+    }
+
+    pub fn add(&mut self, name: &str, source: String) {
+        self.diagrams.push((name.to_string(), source));
+    }
+
+    /// The last diagram, which is usually the one built from the others
+    pub fn last(&self) -> Option<&str> {
+        self.diagrams.last().map(|(name, _)| name.as_str())
+    }
+
+    /// Diagram of the component, drawn with [`render`] for the registered
+    /// ones, or [`None`] for the primitives
+    fn source(&self, name: &str) -> Option<String> {
+        if let Some((_, source)) = self
+            .diagrams
+            .iter()
+            .rev()
+            .find(|(diagram, _)| diagram.eq_ignore_ascii_case(name))
+        {
+            return Some(source.clone());
+        }
+        if let Some(source) = self.rendered.borrow().get(name) {
+            return Some(source.clone());
+        }
+        let source = render(self.registry.graph(name)?);
+        self.rendered
+            .borrow_mut()
+            .insert(name.to_string(), source.clone());
+        Some(source)
+    }
+
+    /// Builds the component with every box simulated through its own diagram,
+    /// so the signals inside the boxes can be looked at while it runs
+    pub fn circuit(&self, name: &str) -> Result<(Circuit, Box<ComponentLogic>), ExploreError> {
+        let source = self
+            .source(name)
+            .ok_or_else(|| ExploreError::UnknownComponent {
+                component: name.to_string(),
+            })?;
+        self.build(name, source, &mut vec![])
+    }
+
+    fn build(
+        &self,
+        name: &str,
+        source: String,
+        building: &mut Vec<String>,
+    ) -> Result<(Circuit, Box<ComponentLogic>), ExploreError> {
+        let parse_error = |error| ExploreError::Parse {
+            component: name.to_string(),
+            error,
+        };
+        let diagram_boxes = boxes(&source).map_err(parse_error)?;
+
+        building.push(name.to_lowercase());
+        // the parser asks for the logic of the boxes in the order they are
+        // listed in, so every box gets the logic built for it here
+        let mut logics: HashMap<&str, VecDeque<Box<ComponentLogic>>> = HashMap::new();
+        let mut nested = vec![];
+        for diagram_box in &diagram_boxes {
+            let (circuit, logic) = match self.source(&diagram_box.name) {
+                Some(_) if building.contains(&diagram_box.name.to_lowercase()) => {
+                    return Err(ExploreError::Recursive {
+                        component: diagram_box.name.clone(),
+                    });
+                }
+                Some(nested_source) => {
+                    let (circuit, logic) =
+                        self.build(&diagram_box.name, nested_source, building)?;
+                    (Some(circuit), logic)
+                }
+                None => match self.registry.logic(&diagram_box.name) {
+                    Some(factory) => (None, factory()),
+                    None => {
+                        return Err(ExploreError::UnknownComponent {
+                            component: diagram_box.name.clone(),
+                        })
+                    }
+                },
+            };
+            nested.push(circuit);
+            logics
+                .entry(&diagram_box.name)
+                .or_default()
+                .push_back(logic);
+        }
+        building.pop();
+
+        let comp_funcs = logics
+            .into_iter()
+            .map(|(name, logics)| {
+                let logics = Rc::new(RefCell::new(logics));
+                let factory: Box<ComponentLogicFactory> = Box::new(move || {
+                    logics
+                        .borrow_mut()
+                        .pop_front()
+                        .expect("one logic is built for every box")
+                });
+                (name, factory)
+            })
+            .collect();
+        let (graph, wires) = parse_with_wires(&source, &comp_funcs).map_err(parse_error)?;
+        let input_num = graph.input_num();
+        let output_num = graph.output_num();
+        let (logic, probe) = graph.finalize_probed();
+        Ok((
+            Circuit {
+                name: name.to_string(),
+                source,
+                wires,
+                probe,
+                input_num,
+                output_num,
+                boxes: diagram_boxes,
+                nested,
+            },
+            logic,
+        ))
+    }
+}
+
+/// A diagram whose signals can be looked at while its logic runs
+pub struct Circuit {
+    pub name: String,
+    source: String,
+    wires: WireMap,
+    probe: Probe,
+    input_num: usize,
+    output_num: usize,
+    boxes: Vec<DiagramBox>,
+    /// circuits of the boxes in the same order, [`None`] for the primitives
+    nested: Vec<Option<Circuit>>,
+}
+
+impl Circuit {
+    /// The diagram with the wires coloured by their signals and the frame of
+    /// the selected box drawn with double lines
+    fn draw(&self, selected: Option<usize>) -> String {
+        let mut lines = self
+            .source
+            .lines()
+            .map(|line| line.chars().collect::<Vec<_>>())
+            .collect::<Vec<_>>();
+        if let Some(selected) = selected.and_then(|selected| self.boxes.get(selected)) {
+            let (top, left) = (selected.top_left.line, selected.top_left.column);
+            let (bottom, right) = (selected.bottom_right.line, selected.bottom_right.column);
+            for line in lines.iter_mut().take(bottom + 1).skip(top) {
+                for c in line.iter_mut().take(right + 1).skip(left) {
+                    *c = match *c {
+                        '━' => '═',
+                        '┃' => '║',
+                        '┏' => '╔',
+                        '┓' => '╗',
+                        '┗' => '╚',
+                        '┛' => '╝',
+                        c => c,
+                    };
+                }
+            }
+        }
+        let source = lines
+            .into_iter()
+            .map(|line| line.into_iter().collect::<String>() + "\n")
+            .collect::<String>();
+        colorize(&source, &self.wires, |node| self.probe.state(node))
+    }
+}
+
+pub enum Key {
+    /// toggles the outer input with that index
+    Toggle(usize),
+    NextBox,
+    Open,
+    Up,
+    Clock,
+    Quit,
+}
+
+impl Key {
+    pub fn from_byte(byte: u8) -> Option<Key> {
+        match byte {
+            b'1'..=b'9' => Some(Key::Toggle((byte - b'1') as usize)),
+            b'0' => Some(Key::Toggle(9)),
+            b'\t' => Some(Key::NextBox),
+            b'\n' | b'\r' => Some(Key::Open),
+            0x7f | 0x08 | b'u' => Some(Key::Up),
+            b'c' => Some(Key::Clock),
+            // Ctrl-C comes in as a key, the terminal not turning it into a signal
+            b'q' | 0x03 => Some(Key::Quit),
+            _ => None,
+        }
+    }
+}
+
+/// Interactive view of a circuit: the outer inputs are toggled from the
+/// keyboard and the boxes can be opened to watch the signals inside them
+pub struct Explorer {
+    top: Circuit,
+    logic: Box<ComponentLogic>,
+    inputs: Vec<BitState>,
+    outputs: Vec<BitState>,
+    /// the input pulsed by [`Key::Clock`]
+    clock: Option<usize>,
+    /// indices of the boxes opened, starting from the top circuit
+    path: Vec<usize>,
+    selected: Option<usize>,
+}
+
+impl Explorer {
+    /// Starts with all the inputs `Off`
+    pub fn new(top: Circuit, logic: Box<ComponentLogic>, clock: Option<usize>) -> Explorer {
+        let mut explorer = Explorer {
+            inputs: vec![BitState::Off; top.input_num],
+            outputs: vec![BitState::Undefined; top.output_num],
+            top,
+            logic,
+            clock,
+            path: vec![],
+            selected: None,
+        };
+        explorer.step();
+        explorer
+    }
+
+    fn step(&mut self) {
+        (self.logic)(&self.inputs, &mut self.outputs);
+    }
+
+    fn view(&self) -> &Circuit {
+        self.path.iter().fold(&self.top, |circuit, idx| {
+            circuit.nested[*idx]
+                .as_ref()
+                .expect("only the boxes with diagrams are opened")
+        })
+    }
+
+    /// Returns `false` once the explorer is to be closed
+    pub fn handle(&mut self, key: Key) -> bool {
+        match key {
+            Key::Toggle(input) if input < self.inputs.len() => {
+                self.inputs[input] = match self.inputs[input] {
+                    BitState::On => BitState::Off,
+                    _ => BitState::On,
+                };
+                self.step();
+            }
+            Key::Clock => {
+                if let Some(clock) = self.clock.filter(|clock| *clock < self.inputs.len()) {
+                    for state in [BitState::On, BitState::Off] {
+                        self.inputs[clock] = state;
+                        self.step();
+                    }
+                }
+            }
+            Key::NextBox => {
+                let box_num = self.view().boxes.len();
+                self.selected = match self.selected {
+                    _ if box_num == 0 => None,
+                    Some(selected) => Some((selected + 1) % box_num),
+                    None => Some(0),
+                };
+            }
+            Key::Open => {
+                if let Some(selected) = self.selected {
+                    if self.view().nested[selected].is_some() {
+                        self.path.push(selected);
+                        self.selected = None;
+                    }
+                }
+            }
+            Key::Up => {
+                self.selected = self.path.pop();
+            }
+            Key::Quit => return false,
+            Key::Toggle(_) => {}
+        }
+        true
+    }
+
+    pub fn draw(&self) -> String {
+        let view = self.view();
+        let mut path = vec![self.top.name.clone()];
+        let mut circuit = &self.top;
+        for idx in &self.path {
+            path.push(format!("{} #{idx}", circuit.boxes[*idx].name));
+            circuit = circuit.nested[*idx].as_ref().unwrap();
+        }
+
+        let mut drawn = path.join(" > ") + "\n";
+        drawn += &view.draw(self.selected);
+        drawn += &format!(
+            "inputs: {}  outputs: {}\n",
+            crate::bits(&self.inputs),
+            crate::bits(&self.outputs)
+        );
+        if let Some(selected) = self.selected {
+            let selected_box = &view.boxes[selected];
+            drawn += &format!("selected: {} #{selected}\n", selected_box.name);
+        }
+        drawn += "1-9,0 toggle inputs  tab select box  enter open it  backspace go up";
+        if self.clock.is_some() {
+            drawn += "  c clock";
+        }
+        drawn += "  q quit\n";
+        drawn
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::registry::components;
+
+    const LIBRARY: &str = "
+# inverter
+   ┏━━━━━━┓
+ ─┬┨      ┃
+  │┃ nand ┠─
+  └┨      ┃
+   ┗━━━━━━┛
+
+# buffer
+   ┏━━━━━━━━━━┓   ┏━━━━━━━━━━┓
+ ──┨ inverter ┠───┨ inverter ┠──
+   ┗━━━━━━━━━━┛   ┗━━━━━━━━━━┛
+";
+
+    #[test]
+    fn opens_boxes_while_simulating() {
+        let registry = components();
+        let library = Library::parse(LIBRARY, &registry);
+        assert_eq!(library.last(), Some("buffer"));
+        let (circuit, logic) = library.circuit("buffer").unwrap();
+        let mut explorer = Explorer::new(circuit, logic, None);
+
+        explorer.handle(Key::Toggle(0));
+        assert_eq!(explorer.outputs, [BitState::On]);
+
+        explorer.handle(Key::NextBox);
+        explorer.handle(Key::NextBox);
+        explorer.handle(Key::Open);
+        assert_eq!(explorer.view().name, "inverter");
+        assert!(explorer.draw().starts_with("buffer > inverter #1\n"));
+        // the second inverter sees the output of the first one
+        let input = explorer.view().wires[&parser::Position::new(1, 1)];
+        assert_eq!(explorer.view().probe.state(input), BitState::Off);
+
+        explorer.handle(Key::Up);
+        assert_eq!(explorer.view().name, "buffer");
+        assert_eq!(explorer.selected, Some(1));
+        assert!(!explorer.handle(Key::Quit));
+        assert!(matches!(Key::from_byte(0x03), Some(Key::Quit)));
+    }
+
+    #[test]
+    fn opens_registered_components() {
+        let registry = components();
+        let (circuit, logic) = Library::new(&registry).circuit("register").unwrap();
+        // the set input is pulsed as the clock
+        let mut explorer = Explorer::new(circuit, logic, Some(8));
+        assert_eq!(explorer.inputs.len(), 10);
+
+        explorer.handle(Key::Toggle(0));
+        explorer.handle(Key::Clock);
+        assert_eq!(explorer.outputs[0], BitState::Off);
+        explorer.handle(Key::Toggle(9));
+        assert_eq!(explorer.outputs[0], BitState::On);
+
+        explorer.handle(Key::NextBox);
+        explorer.handle(Key::Open);
+        assert_eq!(explorer.path, [0]);
+    }
+
+    #[test]
+    fn rejects_recursive_diagrams() {
+        let registry = components();
+        let library = Library::parse(
+            "
+# loop
+   ┏━━━━━━┓
+ ──┨ loop ┠──
+   ┗━━━━━━┛
+",
+            &registry,
+        );
+        assert_eq!(
+            library.circuit("loop").err(),
+            Some(ExploreError::Recursive {
+                component: "loop".to_string()
+            })
+        );
+    }
+}
@@ -0,0 +1,261 @@
+use crate::and::and;
+use crate::bit::bit;
+use crate::latch::gated_sr_latch;
+use crate::not::not;
+use digital_component::{ComponentLogic, ComponentLogicFactory, Graph};
+use parser::parse;
+use std::collections::HashMap;
+
+pub fn d_flip_flop() -> Box<ComponentLogic> {
+    d_flip_flop_graph().finalize()
+}
+
+/// Rising edge triggered D flip-flop: data and clock inputs, stored value output.
+///
+/// Two gated latches in a row, the first one letting the data through while
+/// the clock is low and the second one while it is high, so the output only
+/// takes the data the moment the clock rises
+pub fn d_flip_flop_graph() -> Graph {
+    let mut functions: HashMap<&str, Box<ComponentLogicFactory>> = HashMap::new();
+    functions.insert("bit", Box::new(bit));
+    functions.insert("not", Box::new(not));
+    parse(
+        "
+                  ┏━━━━━┓      ┏━━━━━┓
+         ─────────┨ bit ┠──────┨ bit ┠──
+          ┏━━━━━┓┌┨     ┃    ┌─┨     ┃
+        ─┬┨ not ┠┘┗━━━━━┛    │ ┗━━━━━┛
+         │┗━━━━━┛            │
+         └───────────────────┘
+    ",
+        &functions,
+    )
+    .unwrap()
+}
+
+pub fn sr_flip_flop() -> Box<ComponentLogic> {
+    sr_flip_flop_graph().finalize()
+}
+
+/// Rising edge triggered SR flip-flop: asynchronous set, s, clock, r and
+/// asynchronous reset inputs, stored value and its complement outputs.
+///
+/// A master gated SR latch taking s and r while the clock is low and a slave
+/// one copying the master while it is high
+pub fn sr_flip_flop_graph() -> Graph {
+    let mut functions: HashMap<&str, Box<ComponentLogicFactory>> = HashMap::new();
+    functions.insert("not", Box::new(not));
+    functions.insert("gated_sr", Box::new(gated_sr_latch));
+    parse(
+        "
+                         ┏━━━━━━━━━━┓        ┏━━━━━━━━━━┓
+         ──▶set   set▶───┨          ┃  set▶──┨          ┃
+                         ┃          ┃        ┃          ┃
+         ────────────────┨          ┠────────┨          ┠───
+                         ┃          ┃        ┃          ┃
+         ──▶clk  nclk▶───┨ gated_sr ┃   clk▶─┨ gated_sr ┃
+                         ┃          ┃        ┃          ┃
+         ────────────────┨          ┠────────┨          ┠───
+                         ┃          ┃        ┃          ┃
+                reset▶───┨          ┃reset▶──┨          ┃
+         ──▶reset        ┗━━━━━━━━━━┛        ┗━━━━━━━━━━┛
+                   ┏━━━━━┓
+             clk▶──┨ not ┠──▶nclk
+                   ┗━━━━━┛
+    ",
+        &functions,
+    )
+    .unwrap()
+}
+
+pub fn jk_flip_flop() -> Box<ComponentLogic> {
+    jk_flip_flop_graph().finalize()
+}
+
+/// JK flip-flop: asynchronous set, j, clock, k and asynchronous reset inputs,
+/// stored value and its complement outputs.
+///
+/// An SR flip-flop taking j only while the stored value is off and k only
+/// while it is on, so that both of them toggle it. The feedback goes around
+/// the whole flip-flop, whose latches settle before it sees the new value.
+///
+/// j and k have to hold while the clock falls, as the master latch opens
+/// then: changing them on the same step lets the new values race the clock
+pub fn jk_flip_flop_graph() -> Graph {
+    let mut functions: HashMap<&str, Box<ComponentLogicFactory>> = HashMap::new();
+    functions.insert("and", Box::new(and));
+    functions.insert("srff", Box::new(sr_flip_flop));
+    parse(
+        "
+                              ┏━━━━━━┓
+         ─────────────────────┨      ┃
+                ┏━━━━━┓       ┃      ┃
+         ───────┨ and ┠───────┨      ┠──┬───
+           nq▶──┨     ┃       ┃      ┃  └──▶q
+         ──▶clk ┗━━━━━┛ clk▶──┨ srff ┃
+                ┏━━━━━┓       ┃      ┃
+         ───────┨ and ┠───────┨      ┠──┬───
+            q▶──┨     ┃       ┃      ┃  └──▶nq
+                ┗━━━━━┛       ┃      ┃
+         ─────────────────────┨      ┃
+                              ┗━━━━━━┛
+    ",
+        &functions,
+    )
+    .unwrap()
+}
+
+pub fn t_flip_flop() -> Box<ComponentLogic> {
+    t_flip_flop_graph().finalize()
+}
+
+/// T flip-flop: asynchronous set, t, clock and asynchronous reset inputs,
+/// stored value and its complement outputs. The stored value toggles on
+/// every rising edge of the clock while t is on.
+///
+/// Built on [`jk_flip_flop_graph`], so t has to hold while the clock falls
+pub fn t_flip_flop_graph() -> Graph {
+    let mut functions: HashMap<&str, Box<ComponentLogicFactory>> = HashMap::new();
+    functions.insert("jkff", Box::new(jk_flip_flop));
+    parse(
+        "
+                  ┏━━━━━━┓
+         ─────────┨      ┃
+                  ┃      ┃
+         ─────┬───┨      ┠───
+              │   ┃      ┃
+         ─────┼───┨ jkff ┃
+              │   ┃      ┃
+              └───┨      ┠───
+                  ┃      ┃
+         ─────────┨      ┃
+                  ┗━━━━━━┛
+    ",
+        &functions,
+    )
+    .unwrap()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use digital_component::BitState::*;
+    use digital_component::TruthTable;
+
+    #[test]
+    fn stores_data_on_rising_edge() {
+        let mut flip_flop = d_flip_flop();
+        let mut output = vec![Undefined];
+
+        flip_flop(&[Off, Off], &mut output);
+        flip_flop(&[Off, On], &mut output);
+        assert_eq!(output, vec![Off]);
+
+        // neither a high nor a low clock lets the data through
+        flip_flop(&[On, On], &mut output);
+        assert_eq!(output, vec![Off]);
+        flip_flop(&[On, Off], &mut output);
+        assert_eq!(output, vec![Off]);
+
+        flip_flop(&[On, On], &mut output);
+        assert_eq!(output, vec![On]);
+
+        flip_flop(&[Off, On], &mut output);
+        flip_flop(&[Off, Off], &mut output);
+        assert_eq!(output, vec![On]);
+        flip_flop(&[Off, On], &mut output);
+        assert_eq!(output, vec![Off]);
+    }
+
+    #[test]
+    fn sr_flip_flop_follows_its_truth_table() {
+        let expected = TruthTable::parse(
+            "
+            | set | s | clk | r | reset | q | q' |
+            |-----|---|-----|---|-------|---|----|
+            | 0   | 0 | 0   | 0 | 1     | 0 | 1  |
+            | 0   | 1 | 0   | 0 | 0     | 0 | 1  |
+            | 0   | 1 | 1   | 0 | 0     | 1 | 0  |
+            | 0   | 0 | 1   | 1 | 0     | 1 | 0  |
+            | 0   | 0 | 0   | 1 | 0     | 1 | 0  |
+            | 0   | 0 | 1   | 1 | 0     | 0 | 1  |
+            | 1   | 0 | 1   | 0 | 0     | 1 | 0  |
+            | 0   | 0 | 1   | 0 | 0     | 1 | 0  |
+            ",
+            5,
+        )
+        .unwrap();
+
+        assert_eq!(
+            expected.replay(&mut *sr_flip_flop()).to_string(),
+            expected.to_string()
+        );
+    }
+
+    #[test]
+    fn jk_flip_flop_follows_its_truth_table() {
+        // j and k only change while the clock is high: the master latch takes
+        // them as soon as it falls
+        let expected = TruthTable::parse(
+            "
+            | set | j | clk | k | reset | q | q' |
+            |-----|---|-----|---|-------|---|----|
+            | 0   | 0 | 0   | 0 | 1     | 0 | 1  |
+            | 0   | 0 | 0   | 0 | 0     | 0 | 1  |
+            | 0   | 1 | 0   | 0 | 0     | 0 | 1  |
+            | 0   | 1 | 1   | 0 | 0     | 1 | 0  |
+            | 0   | 1 | 0   | 0 | 0     | 1 | 0  |
+            | 0   | 1 | 1   | 0 | 0     | 1 | 0  |
+            | 0   | 0 | 0   | 1 | 0     | 1 | 0  |
+            | 0   | 0 | 1   | 1 | 0     | 0 | 1  |
+            | 0   | 1 | 0   | 1 | 0     | 0 | 1  |
+            | 0   | 1 | 1   | 1 | 0     | 1 | 0  |
+            | 0   | 1 | 0   | 1 | 0     | 1 | 0  |
+            | 0   | 1 | 1   | 1 | 0     | 0 | 1  |
+            | 0   | 0 | 1   | 0 | 0     | 0 | 1  |
+            | 0   | 0 | 0   | 0 | 0     | 0 | 1  |
+            | 0   | 0 | 1   | 0 | 0     | 0 | 1  |
+            | 1   | 0 | 1   | 0 | 0     | 1 | 0  |
+            | 0   | 0 | 1   | 0 | 0     | 1 | 0  |
+            | 0   | 0 | 0   | 0 | 0     | 1 | 0  |
+            | 0   | 0 | 0   | 0 | 1     | 0 | 1  |
+            ",
+            5,
+        )
+        .unwrap();
+
+        assert_eq!(
+            expected.replay(&mut *jk_flip_flop()).to_string(),
+            expected.to_string()
+        );
+    }
+
+    #[test]
+    fn t_flip_flop_follows_its_truth_table() {
+        let expected = TruthTable::parse(
+            "
+            | set | t | clk | reset | q | q' |
+            |-----|---|-----|-------|---|----|
+            | 0   | 0 | 0   | 1     | 0 | 1  |
+            | 0   | 1 | 0   | 0     | 0 | 1  |
+            | 0   | 1 | 1   | 0     | 1 | 0  |
+            | 0   | 1 | 0   | 0     | 1 | 0  |
+            | 0   | 1 | 1   | 0     | 0 | 1  |
+            | 0   | 1 | 0   | 0     | 0 | 1  |
+            | 0   | 1 | 1   | 0     | 1 | 0  |
+            | 0   | 0 | 1   | 0     | 1 | 0  |
+            | 0   | 0 | 0   | 0     | 1 | 0  |
+            | 0   | 0 | 1   | 0     | 1 | 0  |
+            | 0   | 0 | 0   | 1     | 0 | 1  |
+            | 1   | 0 | 0   | 0     | 1 | 0  |
+            ",
+            4,
+        )
+        .unwrap();
+
+        assert_eq!(
+            expected.replay(&mut *t_flip_flop()).to_string(),
+            expected.to_string()
+        );
+    }
+}
@@ -0,0 +1,249 @@
+use parser::{format, ParseError};
+use std::ops::Range;
+use std::path::Path;
+
+#[derive(Debug, PartialEq)]
+pub struct FormatError {
+    /// line of the file the diagram starts on, counted from 1
+    pub line: usize,
+    pub error: ParseError,
+}
+
+/// Formats a file holding a single diagram, a `.circ` library of diagrams
+/// each under a `# name` line or, for Rust sources, every diagram written as
+/// a string literal passed to `parse(`
+pub fn format_file(path: &Path, source: &str) -> Result<String, FormatError> {
+    if path.extension().is_some_and(|extension| extension == "rs") {
+        format_rust_source(source)
+    } else {
+        format_library(source)
+    }
+}
+
+/// Byte ranges of the contents of the string literals passed to the parser's
+/// `parse(` that draw a diagram, that is hold box drawing characters.
+/// Literals with escapes are left out, formatting would redraw them
+pub fn rust_diagrams(source: &str) -> Vec<Range<usize>> {
+    let mut diagrams = vec![];
+    let mut offset = 0;
+    while let Some(call) = source[offset..].find("parse(") {
+        let call_start = offset + call;
+        let after_call = call_start + "parse(".len();
+        offset = after_call;
+        if !is_parser_call(&source[..call_start]) {
+            continue;
+        }
+        let literal_start = source.len() - source[after_call..].trim_start().len();
+        if let Some((contents, false)) = string_literal(&source[literal_start..]) {
+            let contents = literal_start + contents.start..literal_start + contents.end;
+            if source[contents.clone()].chars().any(is_box_drawing) {
+                offset = contents.end;
+                diagrams.push(contents);
+            }
+        }
+    }
+    diagrams
+}
+
+/// Whether the `parse(` after the text calls the parser, rather than a method
+/// or the `parse` of some other type like `Expression::parse(`
+fn is_parser_call(before: &str) -> bool {
+    let is_identifier = |c: char| c.is_alphanumeric() || c == '_';
+    match before.strip_suffix("::") {
+        Some(path) => path
+            .strip_suffix("parser")
+            .is_some_and(|rest| !rest.ends_with(is_identifier)),
+        None => !before.ends_with(|c: char| is_identifier(c) || c == '.'),
+    }
+}
+
+/// Range of the contents of the string literal the text starts with, raw or
+/// not, and whether they hold escapes
+fn string_literal(text: &str) -> Option<(Range<usize>, bool)> {
+    if let Some(raw) = text.strip_prefix('r') {
+        let hashes = raw.len() - raw.trim_start_matches('#').len();
+        raw[hashes..].strip_prefix('"')?;
+        let start = 1 + hashes + 1;
+        let end = text[start..].find(&format!("\"{}", "#".repeat(hashes)))?;
+        return Some((start..start + end, false));
+    }
+
+    let contents = text.strip_prefix('"')?;
+    let mut has_escapes = false;
+    let mut escaped = false;
+    for (idx, c) in contents.char_indices() {
+        if escaped {
+            escaped = false;
+        } else if c == '\\' {
+            escaped = true;
+            has_escapes = true;
+        } else if c == '"' {
+            return Some((1..1 + idx, has_escapes));
+        }
+    }
+    None
+}
+
+fn is_box_drawing(c: char) -> bool {
+    ('\u{2500}'..='\u{257f}').contains(&c)
+}
+
+/// Formats every diagram of a library separately, keeping the `# name` lines
+/// and a blank line between the diagrams. A file without any is formatted as
+/// a single diagram
+fn format_library(source: &str) -> Result<String, FormatError> {
+    // the line each diagram starts on and its byte range
+    let mut diagrams: Vec<(usize, Range<usize>)> = vec![];
+    let mut offset = 0;
+    for (line_num, line) in source.split_inclusive('\n').enumerate() {
+        offset += line.len();
+        if line.starts_with("# ") {
+            diagrams.push((line_num + 2, offset..offset));
+        } else if let Some((_, diagram)) = diagrams.last_mut() {
+            diagram.end = offset;
+        }
+    }
+    if diagrams.is_empty() {
+        return format(source).map_err(|error| FormatError { line: 1, error });
+    }
+
+    let mut formatted = String::new();
+    let mut copied = 0;
+    for (idx, (line, diagram)) in diagrams.iter().enumerate() {
+        formatted += &source[copied..diagram.start];
+        copied = diagram.end;
+        let diagram = &source[diagram.clone()];
+        if diagram.trim().is_empty() {
+            formatted += diagram;
+            continue;
+        }
+        formatted += &format(diagram).map_err(|error| FormatError { line: *line, error })?;
+        if idx + 1 < diagrams.len() {
+            formatted += "\n";
+        }
+    }
+    Ok(formatted)
+}
+
+fn format_rust_source(source: &str) -> Result<String, FormatError> {
+    let mut formatted = String::new();
+    let mut copied = 0;
+    for diagram in rust_diagrams(source) {
+        formatted += &source[copied..diagram.start];
+        formatted += &format_literal(&source[diagram.clone()]).map_err(|error| FormatError {
+            line: source[..diagram.start].matches('\n').count() + 1,
+            error,
+        })?;
+        copied = diagram.end;
+    }
+    Ok(formatted + &source[copied..])
+}
+
+/// Keeps the indentation of the diagram and of the closing quote
+fn format_literal(literal: &str) -> Result<String, ParseError> {
+    let indent = literal
+        .lines()
+        .filter(|line| !line.trim().is_empty())
+        .map(|line| line.len() - line.trim_start().len())
+        .min()
+        .unwrap_or(0);
+    let closing = match literal.rfind('\n') {
+        Some(newline) if literal[newline + 1..].trim().is_empty() => &literal[newline + 1..],
+        _ => "",
+    };
+
+    let mut formatted = String::from("\n");
+    for line in format(literal)?.lines() {
+        if !line.is_empty() {
+            formatted += &" ".repeat(indent);
+        }
+        formatted += line;
+        formatted += "\n";
+    }
+    Ok(formatted + closing)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn formats_diagrams_in_rust_sources() {
+        for source in [
+            include_str!("and.rs"),
+            include_str!("bit.rs"),
+            include_str!("decoder.rs"),
+            include_str!("not.rs"),
+            include_str!("register.rs"),
+        ] {
+            let formatted = format_rust_source(source).unwrap();
+
+            assert_eq!(format_rust_source(&formatted), Ok(formatted.clone()));
+            // only the string literals change
+            let code = |source: &str| {
+                source
+                    .split('"')
+                    .step_by(2)
+                    .map(str::to_string)
+                    .collect::<Vec<_>>()
+            };
+            assert_eq!(code(&formatted), code(source));
+        }
+    }
+
+    #[test]
+    fn finds_only_diagrams_passed_to_the_parser() {
+        let source = "\
+let expression = Expression::parse(\"a & !b | c\");
+let call = source.find(\"parse(\");
+let number = \"1\".parse::<usize>();
+let graph = parse(\"\\n ┏━━━┓\\n─┨not┠─\\n ┗━━━┛\\n\", &functions);
+let graph = parser::parse(r#\"
+   ┏━━━┓
+  ─┨not┠─
+   ┗━━━┛
+\"#, &functions);
+";
+
+        let diagrams = rust_diagrams(source);
+        assert_eq!(diagrams.len(), 1);
+        assert!(source[diagrams[0].clone()].contains("─┨not┠─"));
+        assert!(source[..diagrams[0].start].ends_with("parser::parse(r#\""));
+
+        let formatted = format_rust_source(source).unwrap();
+        let before_diagram = |source: &str| source[..source.find("r#").unwrap()].to_string();
+        assert_eq!(before_diagram(&formatted), before_diagram(source));
+    }
+
+    #[test]
+    fn formats_every_diagram_of_a_library() {
+        let inverter = "
+   ┏━━━━━━┓
+ ─┬┨      ┃
+  │┃ nand ┠─
+  └┨      ┃
+   ┗━━━━━━┛
+";
+        let buffer = "
+   ┏━━━━━━━━━━┓   ┏━━━━━┓
+ ──┨ inverter ┠───┨ not ┠──
+   ┗━━━━━━━━━━┛   ┗━━━━━┛
+";
+        let library = format!("# inverter{inverter}\n# buffer{buffer}");
+
+        let formatted = format_file(Path::new("lib.circ"), &library).unwrap();
+
+        assert_eq!(
+            formatted,
+            format!(
+                "# inverter\n{}\n# buffer\n{}",
+                format(inverter).unwrap(),
+                format(buffer).unwrap()
+            )
+        );
+        assert_eq!(
+            format_file(Path::new("lib.circ"), &formatted),
+            Ok(formatted.clone())
+        );
+    }
+}
@@ -0,0 +1,131 @@
+use crate::nand::nand;
+use crate::not::not;
+use digital_component::{ComponentLogic, ComponentLogicFactory, Graph};
+use parser::parse;
+use std::collections::HashMap;
+
+pub fn sr_latch() -> Box<ComponentLogic> {
+    sr_latch_graph().finalize()
+}
+
+/// SR latch: set and reset inputs, stored value and its complement outputs.
+///
+/// Two cross coupled NAND gates, each forcing its output on while its
+/// inverted input is on. With both inputs on, both outputs are on
+pub fn sr_latch_graph() -> Graph {
+    let mut functions: HashMap<&str, Box<ComponentLogicFactory>> = HashMap::new();
+    functions.insert("nand", Box::new(|| Box::new(nand)));
+    functions.insert("not", Box::new(not));
+    parse(
+        "
+             ┏━━━━━┓ ┏━━━━━━┓
+          ───┨ not ┠─┨      ┃
+             ┗━━━━━┛ ┃ nand ┠──┬────
+                   ┌─┨      ┃  │
+                   │ ┗━━━━━━┛  │
+                   └─────────┐ │
+                   ┌─────────┼─┘
+                   │ ┏━━━━━━┓│
+                   └─┨      ┃│
+             ┏━━━━━┓ ┃ nand ┠┴────
+          ───┨ not ┠─┨      ┃
+             ┗━━━━━┛ ┗━━━━━━┛
+    ",
+        &functions,
+    )
+    .unwrap()
+}
+
+pub fn gated_sr_latch() -> Box<ComponentLogic> {
+    gated_sr_latch_graph().finalize()
+}
+
+/// Gated SR latch: asynchronous set, s, enable, r and asynchronous reset
+/// inputs, stored value and its complement outputs.
+///
+/// s and r only reach the latch while enable is on, the asynchronous set and
+/// reset whenever they are on
+pub fn gated_sr_latch_graph() -> Graph {
+    let mut functions: HashMap<&str, Box<ComponentLogicFactory>> = HashMap::new();
+    functions.insert("nand", Box::new(|| Box::new(nand)));
+    functions.insert("not", Box::new(not));
+    functions.insert("sr_latch", Box::new(sr_latch));
+    parse(
+        "
+             ┏━━━━━┓
+          ───┨ not ┠──────────┐
+             ┗━━━━━┛          │ ┏━━━━━━┓
+             ┏━━━━━━┓         └─┨      ┃   ┏━━━━━━━━━━┓
+          ───┨      ┃           ┃ nand ┠───┨          ┠───
+             ┃ nand ┠───────────┨      ┃   ┃ sr_latch ┃
+           ┌─┨      ┃           ┗━━━━━━┛ ┌─┨          ┠───
+           │ ┗━━━━━━┛                    │ ┗━━━━━━━━━━┛
+          ─┤ ┏━━━━━━┓           ┏━━━━━━┓ │
+           └─┨      ┃         ┌─┨      ┃ │
+             ┃ nand ┠─────────┘ ┃ nand ┠─┘
+          ───┨      ┃         ┌─┨      ┃
+             ┗━━━━━━┛         │ ┗━━━━━━┛
+             ┏━━━━━┓          │
+          ───┨ not ┠──────────┘
+             ┗━━━━━┛
+    ",
+        &functions,
+    )
+    .unwrap()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use digital_component::TruthTable;
+
+    #[test]
+    fn sr_latch_follows_its_truth_table() {
+        let expected = TruthTable::parse(
+            "
+            | s | r | q | q' |
+            |---|---|---|----|
+            | 1 | 0 | 1 | 0  |
+            | 0 | 0 | 1 | 0  |
+            | 0 | 1 | 0 | 1  |
+            | 0 | 0 | 0 | 1  |
+            | 1 | 1 | 1 | 1  |
+            | 1 | 0 | 1 | 0  |
+            ",
+            2,
+        )
+        .unwrap();
+
+        assert_eq!(
+            expected.replay(&mut *sr_latch()).to_string(),
+            expected.to_string()
+        );
+    }
+
+    #[test]
+    fn gated_sr_latch_follows_its_truth_table() {
+        let expected = TruthTable::parse(
+            "
+            | set | s | e | r | reset | q | q' |
+            |-----|---|---|---|-------|---|----|
+            | 0   | 1 | 1 | 0 | 0     | 1 | 0  |
+            | 0   | 0 | 1 | 0 | 0     | 1 | 0  |
+            | 0   | 0 | 1 | 1 | 0     | 0 | 1  |
+            | 0   | 1 | 0 | 0 | 0     | 0 | 1  |
+            | 0   | 1 | 0 | 1 | 0     | 0 | 1  |
+            | 1   | 0 | 0 | 0 | 0     | 1 | 0  |
+            | 0   | 0 | 0 | 1 | 0     | 1 | 0  |
+            | 0   | 0 | 0 | 0 | 1     | 0 | 1  |
+            | 0   | 0 | 1 | 1 | 1     | 0 | 1  |
+            | 0   | 1 | 1 | 0 | 0     | 1 | 0  |
+            ",
+            5,
+        )
+        .unwrap();
+
+        assert_eq!(
+            expected.replay(&mut *gated_sr_latch()).to_string(),
+            expected.to_string()
+        );
+    }
+}
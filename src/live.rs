@@ -0,0 +1,110 @@
+use crate::registry::ComponentRegistry;
+use digital_component::{BitState, ComponentLogic, Probe};
+use parser::{colorize, names, parse_with_wires, svg, ParseError, WireMap};
+
+/// Clears the terminal and moves the cursor to its top left corner
+pub const CLEAR_SCREEN: &str = "\x1b[2J\x1b[H";
+
+/// A diagram being simulated, drawn with the signals on its wires
+pub struct LiveDiagram {
+    source: String,
+    wires: WireMap,
+    logic: Box<ComponentLogic>,
+    probe: Probe,
+    inputs: Vec<BitState>,
+    outputs: Vec<BitState>,
+}
+
+impl LiveDiagram {
+    /// Parses the diagram with the components of the registry
+    pub fn new(source: String, registry: &ComponentRegistry) -> Result<LiveDiagram, ParseError> {
+        let names = names(&source);
+        let (graph, wires) = parse_with_wires(&source, &registry.functions(&names))?;
+        let inputs = vec![BitState::Undefined; graph.input_num()];
+        let outputs = vec![BitState::Undefined; graph.output_num()];
+        let (logic, probe) = graph.finalize_probed();
+        Ok(LiveDiagram {
+            source,
+            wires,
+            logic,
+            probe,
+            inputs,
+            outputs,
+        })
+    }
+
+    pub fn input_num(&self) -> usize {
+        self.inputs.len()
+    }
+
+    /// Runs the simulation with new inputs
+    pub fn step(&mut self, inputs: &[BitState]) {
+        self.inputs = inputs.to_vec();
+        (self.logic)(&self.inputs, &mut self.outputs);
+    }
+
+    /// The coloured diagram followed by the inputs and outputs
+    pub fn draw(&self) -> String {
+        let mut drawn = colorize(&self.source, &self.wires, |node| self.probe.state(node));
+        drawn += &format!(
+            "inputs: {}  outputs: {}\n",
+            crate::bits(&self.inputs),
+            crate::bits(&self.outputs)
+        );
+        drawn
+    }
+
+    /// The diagram as an SVG image with the wires coloured by their signals
+    pub fn svg(&self) -> String {
+        svg(
+            &self.source,
+            Some((&self.wires, &|node| self.probe.state(node))),
+        )
+        .expect("the diagram was parsed already")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::registry::components;
+
+    #[test]
+    fn simulates_diagrams_of_registered_components() {
+        let mut live = LiveDiagram::new(
+            "
+              ┏━━━━━━┓   ┏━━━━━┓
+           ───┨      ┃   ┃     ┃
+              ┃ nand ┠───┨ NOT ┠───
+           ───┨      ┃   ┃     ┃
+              ┗━━━━━━┛   ┗━━━━━┛
+            "
+            .to_string(),
+            &components(),
+        )
+        .unwrap();
+
+        assert_eq!(live.input_num(), 2);
+        live.step(&[BitState::On, BitState::On]);
+        assert_eq!(live.outputs, [BitState::On]);
+        live.step(&[BitState::On, BitState::Off]);
+        assert_eq!(live.outputs, [BitState::Off]);
+        assert!(live.draw().ends_with("inputs: 10  outputs: 0\n"));
+    }
+
+    #[test]
+    fn reports_unknown_components() {
+        assert!(matches!(
+            LiveDiagram::new(
+                "
+                  ┏━━━━━━┓
+               ───┨ buf  ┠───
+                  ┗━━━━━━┛
+                "
+                .to_string(),
+                &components(),
+            ),
+            Err(ParseError::UnknownComponent { name, .. }) if name == "buf"
+        ));
+    }
+}
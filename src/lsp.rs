@@ -0,0 +1,679 @@
+use crate::formatting::rust_diagrams;
+use crate::registry::ComponentRegistry;
+use digital_component::{BitState, ComponentLogicFactory, Graph};
+use parser::{names, parse, ParseError};
+use serde_json::{json, Value};
+use std::collections::HashMap;
+use std::fs;
+use std::io::{self, BufRead, Write};
+
+/// Language server for diagrams in `.circ` libraries and in the string
+/// literals passed to `parse(` in Rust sources, talking JSON-RPC over the
+/// given streams until the client sends `exit`.
+///
+/// Columns are counted in characters, which are single UTF-16 code units for
+/// the box drawing characters diagrams are made of
+pub fn run(
+    input: &mut impl BufRead,
+    output: &mut impl Write,
+    registry: &ComponentRegistry,
+) -> io::Result<()> {
+    let mut server = Server::new(registry);
+    while let Some(message) = read_message(input)? {
+        for reply in server.handle(&message) {
+            write_message(output, &reply)?;
+        }
+        if server.exited {
+            break;
+        }
+    }
+    Ok(())
+}
+
+fn read_message(input: &mut impl BufRead) -> io::Result<Option<Value>> {
+    let mut content_length = None;
+    loop {
+        let mut header = String::new();
+        if input.read_line(&mut header)? == 0 {
+            return Ok(None);
+        }
+        let header = header.trim();
+        if header.is_empty() {
+            break;
+        }
+        if let Some(length) = header.strip_prefix("Content-Length:") {
+            content_length = length.trim().parse::<usize>().ok();
+        }
+    }
+    let Some(content_length) = content_length else {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            "missing Content-Length header",
+        ));
+    };
+    let mut content = vec![0; content_length];
+    input.read_exact(&mut content)?;
+    serde_json::from_slice(&content)
+        .map(Some)
+        .map_err(|error| io::Error::new(io::ErrorKind::InvalidData, error))
+}
+
+fn write_message(output: &mut impl Write, message: &Value) -> io::Result<()> {
+    let content = message.to_string();
+    write!(output, "Content-Length: {}\r\n\r\n{content}", content.len())?;
+    output.flush()
+}
+
+/// A diagram in a document, `line` and `column` being where its first
+/// character is
+struct Diagram<'t> {
+    /// the `# name` of a diagram in a `.circ` library
+    name: Option<&'t str>,
+    source: &'t str,
+    line: usize,
+    column: usize,
+}
+
+impl Diagram<'_> {
+    /// Position in the document of a position in the diagram
+    fn document_position(&self, line: usize, column: usize) -> (usize, usize) {
+        if line == 0 {
+            (self.line, self.column + column)
+        } else {
+            (self.line + line, column)
+        }
+    }
+
+    fn contains(&self, line: usize) -> bool {
+        line >= self.line && line <= self.line + self.source.lines().count()
+    }
+}
+
+fn diagrams<'t>(uri: &str, text: &'t str) -> Vec<Diagram<'t>> {
+    if uri.ends_with(".rs") {
+        return rust_diagrams(text)
+            .into_iter()
+            .map(|range| {
+                let before = &text[..range.start];
+                let line_start = before.rfind('\n').map_or(0, |newline| newline + 1);
+                Diagram {
+                    name: None,
+                    source: &text[range],
+                    line: before.matches('\n').count(),
+                    column: before[line_start..].chars().count(),
+                }
+            })
+            .collect();
+    }
+
+    let mut diagrams: Vec<Diagram> = vec![];
+    let mut offset = 0;
+    for (line_num, line) in text.split_inclusive('\n').enumerate() {
+        if let Some(name) = line.strip_prefix("# ") {
+            diagrams.push(Diagram {
+                name: Some(name.trim()),
+                source: "",
+                line: line_num + 1,
+                column: 0,
+            });
+        } else if let Some(diagram) = diagrams.last_mut() {
+            let start = offset - diagram.source.len();
+            diagram.source = &text[start..offset + line.len()];
+        }
+        offset += line.len();
+    }
+    diagrams
+}
+
+struct Server<'a> {
+    registry: &'a ComponentRegistry,
+    documents: HashMap<String, String>,
+    /// directory of the workspace the client opened, where the sources of
+    /// the registered components are looked for
+    root: Option<String>,
+    exited: bool,
+}
+
+impl<'a> Server<'a> {
+    fn new(registry: &'a ComponentRegistry) -> Server<'a> {
+        Server {
+            registry,
+            documents: HashMap::new(),
+            root: None,
+            exited: false,
+        }
+    }
+
+    /// Replies and notifications to send for the message
+    fn handle(&mut self, message: &Value) -> Vec<Value> {
+        let method = message["method"].as_str().unwrap_or_default();
+        let params = &message["params"];
+        let uri = params["textDocument"]["uri"]
+            .as_str()
+            .unwrap_or_default()
+            .to_string();
+        let result = match method {
+            "initialize" => {
+                self.root = params["rootUri"]
+                    .as_str()
+                    .and_then(|root| root.strip_prefix("file://"))
+                    .or(params["rootPath"].as_str())
+                    .map(|root| root.trim_end_matches('/').to_string());
+                json!({
+                    "capabilities": {
+                        "textDocumentSync": 1,
+                        "hoverProvider": true,
+                        "definitionProvider": true,
+                        "completionProvider": {}
+                    },
+                    "serverInfo": { "name": "cpu" }
+                })
+            }
+            "shutdown" => Value::Null,
+            "exit" => {
+                self.exited = true;
+                return vec![];
+            }
+            "textDocument/didOpen" => {
+                let text = params["textDocument"]["text"].as_str().unwrap_or_default();
+                self.documents.insert(uri.clone(), text.to_string());
+                return vec![self.publish_diagnostics(&uri)];
+            }
+            "textDocument/didChange" => {
+                // full synchronisation, the last change is the whole text
+                if let Some(text) = params["contentChanges"]
+                    .as_array()
+                    .and_then(|changes| changes.last())
+                    .and_then(|change| change["text"].as_str())
+                {
+                    self.documents.insert(uri.clone(), text.to_string());
+                }
+                return vec![self.publish_diagnostics(&uri)];
+            }
+            "textDocument/didClose" => {
+                self.documents.remove(&uri);
+                return vec![json!({
+                    "jsonrpc": "2.0",
+                    "method": "textDocument/publishDiagnostics",
+                    "params": { "uri": uri, "diagnostics": [] }
+                })];
+            }
+            "textDocument/hover" => self.hover(&uri, &params["position"]),
+            "textDocument/definition" => self.definition(&uri, &params["position"]),
+            "textDocument/completion" => self.completion(&uri),
+            _ if message.get("id").is_none() => return vec![],
+            _ => {
+                return vec![json!({
+                    "jsonrpc": "2.0",
+                    "id": message["id"],
+                    "error": { "code": -32601, "message": format!("unknown method {method}") }
+                })]
+            }
+        };
+        vec![json!({ "jsonrpc": "2.0", "id": message["id"], "result": result })]
+    }
+
+    fn text(&self, uri: &str) -> &str {
+        self.documents.get(uri).map_or("", String::as_str)
+    }
+
+    /// Names of the diagrams of a `.circ` library, usable as components in it
+    fn library_names<'t>(&self, uri: &str, text: &'t str) -> Vec<&'t str> {
+        diagrams(uri, text)
+            .into_iter()
+            .filter_map(|diagram| diagram.name)
+            .collect()
+    }
+
+    /// Parses the diagram with the known components, the ones of the library
+    /// getting logic that does nothing as only the structure is checked
+    fn parse(&self, diagram: &Diagram, library: &[&str]) -> Result<Graph, ParseError> {
+        let names = names(diagram.source);
+        let mut functions = self.registry.functions(&names);
+        for name in &names {
+            if library.iter().any(|known| known.eq_ignore_ascii_case(name)) {
+                let nothing: Box<ComponentLogicFactory> =
+                    Box::new(|| Box::new(|_: &[BitState], _: &mut [BitState]| {}));
+                functions.entry(name.as_str()).or_insert(nothing);
+            }
+        }
+        parse(diagram.source, &functions)
+    }
+
+    fn publish_diagnostics(&self, uri: &str) -> Value {
+        let text = self.text(uri);
+        let library = self.library_names(uri, text);
+        let diagnostics = diagrams(uri, text)
+            .iter()
+            .filter_map(|diagram| {
+                let error = self.parse(diagram, &library).err()?;
+                let message = match &error {
+                    ParseError::UnknownComponent { name, .. } => self
+                        .registry
+                        .arguments_error(name)
+                        .map(|arguments_error| format!("`{name}`: {arguments_error}")),
+                    _ => None,
+                }
+                .unwrap_or_else(|| error.to_string());
+                let (line, column, length) = match (&error, error.position()) {
+                    (ParseError::UnknownComponent { name, .. }, Some(position)) => {
+                        let (line, column) =
+                            diagram.document_position(position.line, position.column);
+                        (line, column, name.chars().count())
+                    }
+                    (_, Some(position)) => {
+                        let (line, column) =
+                            diagram.document_position(position.line, position.column);
+                        (line, column, 1)
+                    }
+                    (_, None) => (diagram.line, diagram.column, 0),
+                };
+                Some(json!({
+                    "range": range(line, column, length),
+                    "severity": 1,
+                    "source": "cpu",
+                    "message": message
+                }))
+            })
+            .collect::<Vec<_>>();
+        json!({
+            "jsonrpc": "2.0",
+            "method": "textDocument/publishDiagnostics",
+            "params": { "uri": uri, "diagnostics": diagnostics }
+        })
+    }
+
+    /// The component name under the cursor, with the arguments of a
+    /// generator call like `decoder(3)`, if it is inside a diagram
+    fn name_at<'t>(&self, uri: &str, text: &'t str, position: &Value) -> Option<&'t str> {
+        let line_num = position["line"].as_u64()? as usize;
+        let column = position["character"].as_u64()? as usize;
+        if !diagrams(uri, text)
+            .iter()
+            .any(|diagram| diagram.contains(line_num))
+        {
+            return None;
+        }
+        let line = text.lines().nth(line_num)?;
+        let chars = line.char_indices().collect::<Vec<_>>();
+        let is_name = |idx: usize| {
+            chars
+                .get(idx)
+                .is_some_and(|(_, c)| c.is_ascii_alphanumeric() || *c == '_')
+        };
+        // right after the name counts as on it
+        let column = if is_name(column) {
+            column
+        } else {
+            column.checked_sub(1)?
+        };
+        if !is_name(column) {
+            return None;
+        }
+        let mut start = column;
+        while start > 0 && is_name(start - 1) {
+            start -= 1;
+        }
+        let mut end = column;
+        while is_name(end + 1) {
+            end += 1;
+        }
+        if chars.get(end + 1).is_some_and(|(_, c)| *c == '(') {
+            if let Some(close) = chars[end + 1..].iter().position(|(_, c)| *c == ')') {
+                end += 1 + close;
+            }
+        }
+        let byte_end = chars.get(end + 1).map_or(line.len(), |(idx, _)| *idx);
+        Some(&line[chars[start].0..byte_end])
+    }
+
+    fn hover(&self, uri: &str, position: &Value) -> Value {
+        let text = self.text(uri);
+        let Some(name) = self.name_at(uri, text, position) else {
+            return Value::Null;
+        };
+        let description = if let Some(diagram) = diagrams(uri, text).into_iter().find(|diagram| {
+            diagram
+                .name
+                .is_some_and(|known| known.eq_ignore_ascii_case(name))
+        }) {
+            let library = self.library_names(uri, text);
+            let graph = self.parse(&diagram, &library).ok();
+            format!("**{name}**, drawn in this file{}", pin_counts(graph))
+        } else if let Some(component) = self.registry.component(name) {
+            format!(
+                "**{name}**: {}{}",
+                component.description,
+                pin_counts(self.registry.graph(name))
+            )
+        } else {
+            let generator_name = name.split('(').next().unwrap_or(name);
+            let Some(generator) = self.registry.generator(generator_name) else {
+                return Value::Null;
+            };
+            // the generator is not even called with arguments it rejects
+            let details = match self.registry.arguments_error(name) {
+                Some(error) => format!("\n\n`{name}`: {error}"),
+                None => pin_counts(self.registry.graph(name)),
+            };
+            format!(
+                "**{generator_name}({})**: {}{details}",
+                generator.parameters.join(", "),
+                generator.description,
+            )
+        };
+        json!({ "contents": { "kind": "markdown", "value": description } })
+    }
+
+    fn definition(&self, uri: &str, position: &Value) -> Value {
+        let text = self.text(uri);
+        let Some(name) = self.name_at(uri, text, position) else {
+            return Value::Null;
+        };
+        if let Some(diagram) = diagrams(uri, text).into_iter().find(|diagram| {
+            diagram
+                .name
+                .is_some_and(|known| known.eq_ignore_ascii_case(name))
+        }) {
+            // the `# name` line
+            return json!({ "uri": uri, "range": range(diagram.line - 1, 0, 0) });
+        }
+        // the sources are only there when the workspace is this repository
+        let (Some(root), Some((source_file, function))) =
+            (&self.root, self.registry.definition(name))
+        else {
+            return Value::Null;
+        };
+        let path = format!("{root}/{source_file}");
+        let line = fs::read_to_string(&path)
+            .ok()
+            .and_then(|source| {
+                source
+                    .lines()
+                    .position(|line| line.contains(&format!("fn {function}(")))
+            })
+            .unwrap_or(0);
+        json!({ "uri": format!("file://{path}"), "range": range(line, 0, 0) })
+    }
+
+    fn completion(&self, uri: &str) -> Value {
+        let text = self.text(uri);
+        let components = self.registry.component_names().map(|name| {
+            json!({
+                "label": name,
+                "kind": 7,
+                "detail": self.registry.component(name).unwrap().description
+            })
+        });
+        let generators = self.registry.generator_names().map(|name| {
+            let generator = self.registry.generator(name).unwrap();
+            json!({
+                "label": format!("{name}({})", generator.parameters.join(", ")),
+                "kind": 7,
+                "detail": generator.description,
+                "insertText": format!("{name}()")
+            })
+        });
+        let library = self
+            .library_names(uri, text)
+            .into_iter()
+            .map(|name| json!({ "label": name, "kind": 7, "detail": "drawn in this file" }));
+        Value::Array(components.chain(generators).chain(library).collect())
+    }
+}
+
+fn range(line: usize, column: usize, length: usize) -> Value {
+    json!({
+        "start": { "line": line, "character": column },
+        "end": { "line": line, "character": column + length }
+    })
+}
+
+fn pin_counts(graph: Option<Graph>) -> String {
+    graph.map_or(String::new(), |graph| {
+        format!(
+            "\n\n{} inputs, {} outputs",
+            graph.input_num(),
+            graph.output_num()
+        )
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::registry::components;
+
+    const LIBRARY: &str = "\
+# inverter
+   ┏━━━━━━┓
+ ─┬┨      ┃
+  │┃ nand ┠─
+  └┨      ┃
+   ┗━━━━━━┛
+
+# buffer
+   ┏━━━━━━━━━━┓   ┏━━━━━┓
+ ──┨ inverter ┠───┨ buf ┠──
+   ┗━━━━━━━━━━┛   ┗━━━━━┛
+";
+
+    fn open(server: &mut Server, uri: &str, text: &str) -> Vec<Value> {
+        server.handle(&json!({
+            "jsonrpc": "2.0",
+            "method": "textDocument/didOpen",
+            "params": { "textDocument": { "uri": uri, "text": text } }
+        }))
+    }
+
+    fn request(server: &mut Server, method: &str, uri: &str, line: usize, column: usize) -> Value {
+        server.handle(&json!({
+            "jsonrpc": "2.0",
+            "id": 1,
+            "method": method,
+            "params": {
+                "textDocument": { "uri": uri },
+                "position": { "line": line, "character": column }
+            }
+        }))[0]["result"]
+            .clone()
+    }
+
+    #[test]
+    fn publishes_diagnostics() {
+        let registry = components();
+        let mut server = Server::new(&registry);
+
+        let replies = open(&mut server, "file:///lib.circ", LIBRARY);
+        let diagnostics = &replies[0]["params"]["diagnostics"];
+        assert_eq!(
+            diagnostics,
+            &json!([{
+                "range": range(9, 20, 3),
+                "severity": 1,
+                "source": "cpu",
+                "message": "unknown component `buf` at 1:20"
+            }])
+        );
+
+        let invalid = "\
+# invalid
+   ┏━━━━━━━━━━━━┓
+ ──┨ decoder(0) ┠──
+   ┗━━━━━━━━━━━━┛
+";
+        let replies = open(&mut server, "file:///invalid.circ", invalid);
+        assert_eq!(
+            replies[0]["params"]["diagnostics"][0]["message"],
+            "`decoder(0)`: n must be between 1 and 8, not 0"
+        );
+
+        let fixed = LIBRARY.replace("buf", "not");
+        let replies = open(&mut server, "file:///lib.circ", &fixed);
+        assert_eq!(replies[0]["params"]["diagnostics"], json!([]));
+    }
+
+    #[test]
+    fn finds_diagrams_in_rust_sources() {
+        let registry = components();
+        let mut server = Server::new(&registry);
+        let source = "fn and() {\n    parse(\"\n   ┏━━━━━┓\n ──┨ buf ┠──\n   ┗━━━━━┛\n\", &f)\n}\n";
+
+        let replies = open(&mut server, "file:///and.rs", source);
+        assert_eq!(
+            replies[0]["params"]["diagnostics"][0]["range"],
+            range(3, 5, 3)
+        );
+        // `and` outside of the diagram is just Rust
+        assert_eq!(
+            request(&mut server, "textDocument/hover", "file:///and.rs", 0, 4),
+            Value::Null
+        );
+    }
+
+    #[test]
+    fn describes_components() {
+        let registry = components();
+        let mut server = Server::new(&registry);
+        let text = LIBRARY.replace("buf", "decoder(2)");
+        open(&mut server, "file:///lib.circ", &text);
+
+        let hover = request(&mut server, "textDocument/hover", "file:///lib.circ", 3, 6);
+        assert_eq!(
+            hover["contents"]["value"],
+            "**nand**: NAND gate, the only primitive everything else is built from"
+        );
+        let hover = request(&mut server, "textDocument/hover", "file:///lib.circ", 9, 21);
+        assert_eq!(
+            hover["contents"]["value"],
+            "**decoder(n)**: n to 2^n decoder\n\n2 inputs, 4 outputs"
+        );
+        let hover = request(&mut server, "textDocument/hover", "file:///lib.circ", 9, 5);
+        assert_eq!(
+            hover["contents"]["value"],
+            "**inverter**, drawn in this file\n\n1 inputs, 1 outputs"
+        );
+
+        let invalid = "\
+# invalid
+   ┏━━━━━━━━━━━━┓
+ ──┨ decoder(0) ┠──
+   ┗━━━━━━━━━━━━┛
+";
+        open(&mut server, "file:///invalid.circ", invalid);
+        let hover = request(
+            &mut server,
+            "textDocument/hover",
+            "file:///invalid.circ",
+            2,
+            6,
+        );
+        assert_eq!(
+            hover["contents"]["value"],
+            "**decoder(n)**: n to 2^n decoder\n\n`decoder(0)`: n must be between 1 and 8, not 0"
+        );
+
+        let definition = request(
+            &mut server,
+            "textDocument/definition",
+            "file:///lib.circ",
+            9,
+            5,
+        );
+        assert_eq!(definition["range"], range(0, 0, 0));
+        // the registered components are only found in the workspace
+        let definition = request(
+            &mut server,
+            "textDocument/definition",
+            "file:///lib.circ",
+            3,
+            6,
+        );
+        assert_eq!(definition, Value::Null);
+        server.handle(&json!({
+            "jsonrpc": "2.0",
+            "id": 1,
+            "method": "initialize",
+            "params": { "rootUri": format!("file://{}", env!("CARGO_MANIFEST_DIR")) }
+        }));
+        let definition = request(
+            &mut server,
+            "textDocument/definition",
+            "file:///lib.circ",
+            3,
+            6,
+        );
+        assert!(definition["uri"]
+            .as_str()
+            .unwrap()
+            .ends_with("/src/nand.rs"));
+        assert_eq!(definition["range"], range(5, 0, 0));
+        // named differently from the function defining it
+        let stored = "\
+# stored
+   ┏━━━━━┓
+ ──┨ dff ┠──
+ ──┨     ┃
+   ┗━━━━━┛
+";
+        open(&mut server, "file:///stored.circ", stored);
+        let definition = request(
+            &mut server,
+            "textDocument/definition",
+            "file:///stored.circ",
+            2,
+            6,
+        );
+        assert!(definition["uri"]
+            .as_str()
+            .unwrap()
+            .ends_with("/src/flip_flop.rs"));
+        let line = include_str!("flip_flop.rs")
+            .lines()
+            .position(|line| line.starts_with("pub fn d_flip_flop("))
+            .unwrap();
+        assert_eq!(definition["range"], range(line, 0, 0));
+
+        let completion = request(
+            &mut server,
+            "textDocument/completion",
+            "file:///lib.circ",
+            0,
+            0,
+        );
+        let labels = completion
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|item| item["label"].as_str().unwrap())
+            .collect::<Vec<_>>();
+        assert!(labels.contains(&"register"));
+        assert!(labels.contains(&"decoder(n)"));
+        assert!(labels.contains(&"inverter"));
+    }
+
+    #[test]
+    fn talks_json_rpc() {
+        let mut input = vec![];
+        for message in [
+            json!({ "jsonrpc": "2.0", "id": 1, "method": "initialize", "params": {} }),
+            json!({ "jsonrpc": "2.0", "method": "initialized", "params": {} }),
+            json!({ "jsonrpc": "2.0", "id": 2, "method": "shutdown" }),
+            json!({ "jsonrpc": "2.0", "method": "exit" }),
+        ] {
+            write_message(&mut input, &message).unwrap();
+        }
+        let mut output = vec![];
+        run(&mut input.as_slice(), &mut output, &components()).unwrap();
+
+        let mut output = output.as_slice();
+        let initialized = read_message(&mut output).unwrap().unwrap();
+        assert_eq!(initialized["result"]["capabilities"]["hoverProvider"], true);
+        let shutdown = read_message(&mut output).unwrap().unwrap();
+        assert_eq!(
+            shutdown,
+            json!({ "jsonrpc": "2.0", "id": 2, "result": null })
+        );
+        assert_eq!(read_message(&mut output).unwrap(), None);
+    }
+}
@@ -1,17 +1,606 @@
+mod adder;
+mod alu;
 mod and;
 mod bit;
+mod builder;
+mod comparator;
 mod decoder;
+mod encoder;
+mod explorer;
+mod flip_flop;
+mod formatting;
+mod latch;
+mod live;
+mod lsp;
+mod mux;
 mod nand;
 mod not;
+mod or;
 mod register;
+mod registry;
+mod shifter;
+mod synthesis;
+#[cfg(test)]
+mod testing;
+mod xor;
 
-use digital_component::BitState;
+use digital_component::{
+    check_equivalence, BitState, Clock, DelayModel, Equivalence, OptimizationError, Scheduler,
+    Statistics, TimingReport, TruthTable,
+};
+use explorer::{Explorer, Key, Library};
+use formatting::{format_file, FormatError};
+use live::{LiveDiagram, CLEAR_SCREEN};
+use parser::render;
+use std::env;
+use std::fs;
+use std::io::{self, BufRead, Read, Write};
+use std::path::Path;
+use std::process::{Command, ExitCode, Stdio};
+use std::thread;
+use std::time::Duration;
+use synthesis::{synthesize, synthesize_expression, Expression};
 
-fn main() {
-    let _ = bit::bit;
-    let _ = not::not;
-    let _ = and::and;
-    let _ = register::register;
-    let _ = decoder::decoder_2_to_4;
-    let _ = decoder::decoder;
+const USAGE: &str = "\
+usage: cpu <command> [arguments]
+
+commands:
+    list               list known components
+    stats <component>  print gate counts, logic depth, fan-out and feedback loops
+                       of a component, e.g. `cpu stats register` or `cpu stats decoder(4)`
+    optimize <component>
+                       print the statistics of a component after constant propagation,
+                       double NOT elimination, merging of identical gates and dead gate removal
+    timing <component> print arrival times and the critical path of a component assuming
+                       every NAND takes one time unit to switch
+    table <component> [--csv]
+                       print the outputs of a component for every combination of its inputs
+                       as a markdown or CSV table
+    synth <expression> | synth --table <file> <input count>
+                       build a NAND only circuit from an expression like `a & !b | c` or
+                       from a markdown or CSV truth table and print its statistics
+    render <component> draw a component, including the generated ones, as a diagram
+    fmt [--check] <file>...
+                       redraw the diagrams of the files in place with the layout of `render`,
+                       the files being single diagrams or Rust sources with diagrams passed to
+                       `parse`; with `--check` only list the files that would change
+    live <file> [<inputs>...]
+                       simulate a diagram, redrawing it with the wires coloured by their signals
+                       after every input vector like `0110`, read from stdin if none are given
+    explore <file> [<component>] [--clock <input>]
+                       browse a diagram or a `.circ` library of diagrams, each starting with a
+                       `# name` line, toggling inputs from the keyboard and opening boxes to watch
+                       the signals inside them; `c` pulses the clock input, numbered from 0
+    svg <file | component> [<inputs>]
+                       print a diagram file or a component as an SVG image, with the wires coloured
+                       by their signals for inputs like `0110`
+    run <file> <cycles> [<clock>=<period>[:<phase>[:<duty>]]...] [<inputs>]
+                       simulate a diagram for a number of cycles of its first clock, the clocks
+                       being the wires starting from labels like `~clk──`, printing the inputs and
+                       outputs after every tick; clocks default to a period of 2 ticks and the
+                       other inputs to 0
+    lsp                run a language server over stdin and stdout reporting parse errors in `.circ`
+                       libraries and diagrams in Rust sources, with hover, go to definition and
+                       completion of component names
+    equiv <component> <component>
+                       prove two combinational components produce the same outputs or print
+                       the inputs they differ on, e.g. `cpu equiv decoder_2_to_4 decoder(2)`
+";
+
+fn main() -> ExitCode {
+    let args = env::args().skip(1).collect::<Vec<_>>();
+    let registry = registry::components();
+    match args.iter().map(String::as_str).collect::<Vec<_>>()[..] {
+        ["list"] => {
+            for name in registry.component_names() {
+                let description = registry.component(name).unwrap().description;
+                println!("{name:20} {description}");
+            }
+            for name in registry.generator_names() {
+                let generator = registry.generator(name).unwrap();
+                let signature = format!("{name}({})", generator.parameters.join(", "));
+                println!("{signature:20} {}", generator.description);
+            }
+            ExitCode::SUCCESS
+        }
+        ["stats", component] => match registry.graph(component) {
+            Some(graph) => {
+                let netlist = graph.flatten(&|name| registry.graph(name));
+                print!("{}", Statistics::new(&netlist));
+                ExitCode::SUCCESS
+            }
+            None => {
+                report_unknown(&registry, component);
+                ExitCode::FAILURE
+            }
+        },
+        ["optimize", component] => match registry.graph(component) {
+            Some(graph) => match graph.flatten(&|name| registry.graph(name)).optimize() {
+                Ok(netlist) => {
+                    print!("{}", Statistics::new(&netlist));
+                    ExitCode::SUCCESS
+                }
+                Err(OptimizationError::Unverified(error)) => {
+                    eprintln!("`{component}` can not be optimized safely: {error:?}");
+                    ExitCode::FAILURE
+                }
+                Err(error) => {
+                    eprintln!("optimization changed the behaviour of `{component}`: {error:?}");
+                    ExitCode::FAILURE
+                }
+            },
+            None => {
+                report_unknown(&registry, component);
+                ExitCode::FAILURE
+            }
+        },
+        ["timing", component] => match registry.graph(component) {
+            Some(graph) => {
+                let netlist = graph.flatten(&|name| registry.graph(name));
+                print!("{}", TimingReport::new(&netlist, &DelayModel::default()));
+                ExitCode::SUCCESS
+            }
+            None => {
+                report_unknown(&registry, component);
+                ExitCode::FAILURE
+            }
+        },
+        ["table", component] | ["table", component, "--csv"] => {
+            match (registry.graph(component), registry.logic(component)) {
+                (Some(graph), Some(logic)) => {
+                    let (input_num, output_num) = (graph.input_num(), graph.output_num());
+                    match TruthTable::generate(&mut *logic(), input_num, output_num) {
+                        Ok(table) if args.len() == 3 => {
+                            print!("{}", table.to_csv());
+                            ExitCode::SUCCESS
+                        }
+                        Ok(table) => {
+                            print!("{}", table.to_markdown());
+                            ExitCode::SUCCESS
+                        }
+                        Err(error) => {
+                            eprintln!("can not tabulate `{component}`: {error:?}");
+                            ExitCode::FAILURE
+                        }
+                    }
+                }
+                _ => {
+                    report_unknown(&registry, component);
+                    ExitCode::FAILURE
+                }
+            }
+        }
+        ["synth", expression] => match Expression::parse(expression).and_then(|parsed| {
+            Ok((
+                parsed.minimize()?.to_string(),
+                synthesize_expression(expression)?,
+            ))
+        }) {
+            Ok((minimized, graph)) => {
+                println!("{minimized}");
+                print!("{}", Statistics::new(&graph.flatten(&|_| None)));
+                ExitCode::SUCCESS
+            }
+            Err(error) => {
+                eprintln!("can not synthesize `{expression}`: {error:?}");
+                ExitCode::FAILURE
+            }
+        },
+        ["synth", "--table", file, input_num] => {
+            let table = input_num
+                .parse()
+                .map_err(|_| format!("`{input_num}` is not a number"))
+                .and_then(|input_num| {
+                    TruthTable::load(file, input_num).map_err(|error| format!("{error:?}"))
+                });
+            match table.and_then(|table| synthesize(&table).map_err(|error| format!("{error:?}"))) {
+                Ok(graph) => {
+                    print!("{}", Statistics::new(&graph.flatten(&|_| None)));
+                    ExitCode::SUCCESS
+                }
+                Err(error) => {
+                    eprintln!("can not synthesize `{file}`: {error}");
+                    ExitCode::FAILURE
+                }
+            }
+        }
+        ["render", component] => match registry.graph(component) {
+            Some(graph) => {
+                print!("{}", render(graph));
+                ExitCode::SUCCESS
+            }
+            None => {
+                report_unknown(&registry, component);
+                ExitCode::FAILURE
+            }
+        },
+        ["fmt", ref arguments @ ..] if !arguments.is_empty() => {
+            let (check, files) = match arguments {
+                ["--check", files @ ..] => (true, files),
+                files => (false, files),
+            };
+            let mut status = ExitCode::SUCCESS;
+            for file in files {
+                let path = Path::new(file);
+                let source = match fs::read_to_string(path) {
+                    Ok(source) => source,
+                    Err(error) => {
+                        eprintln!("{file}: {error}");
+                        status = ExitCode::FAILURE;
+                        continue;
+                    }
+                };
+                match format_file(path, &source) {
+                    Ok(formatted) if formatted == source => {}
+                    Ok(_) if check => {
+                        println!("{file} is not formatted");
+                        status = ExitCode::FAILURE;
+                    }
+                    Ok(formatted) => {
+                        if let Err(error) = fs::write(path, formatted) {
+                            eprintln!("{file}: {error}");
+                            status = ExitCode::FAILURE;
+                        }
+                    }
+                    Err(FormatError { line, error }) => {
+                        eprintln!("{file}:{line}: diagram could not be parsed: {error:?}");
+                        status = ExitCode::FAILURE;
+                    }
+                }
+            }
+            status
+        }
+        ["live", file, ref vectors @ ..] => {
+            let mut live = match fs::read_to_string(file) {
+                Ok(source) => match LiveDiagram::new(source, &registry) {
+                    Ok(live) => live,
+                    Err(error) => {
+                        eprintln!("{file}: diagram could not be parsed: {error:?}");
+                        return ExitCode::FAILURE;
+                    }
+                },
+                Err(error) => {
+                    eprintln!("{file}: {error}");
+                    return ExitCode::FAILURE;
+                }
+            };
+            let from_arguments = !vectors.is_empty();
+            let vectors: Box<dyn Iterator<Item = String>> = if from_arguments {
+                Box::new(vectors.iter().map(|vector| vector.to_string()))
+            } else {
+                Box::new(io::stdin().lock().lines().map_while(Result::ok))
+            };
+            print!("{CLEAR_SCREEN}{}", live.draw());
+            for vector in vectors {
+                match parse_bits(vector.trim()) {
+                    Some(inputs) if inputs.len() == live.input_num() => live.step(&inputs),
+                    _ => {
+                        eprintln!("expected {} bits of 1, 0 or x", live.input_num());
+                        continue;
+                    }
+                }
+                if from_arguments {
+                    thread::sleep(Duration::from_millis(500));
+                }
+                print!("{CLEAR_SCREEN}{}", live.draw());
+            }
+            ExitCode::SUCCESS
+        }
+        ["explore", file, ref options @ ..] => {
+            let (component, clock) = match options {
+                [] => (None, None),
+                [component] => (Some(*component), None),
+                ["--clock", clock] => (None, Some(*clock)),
+                [component, "--clock", clock] => (Some(*component), Some(*clock)),
+                _ => {
+                    eprint!("{USAGE}");
+                    return ExitCode::FAILURE;
+                }
+            };
+            let Ok(clock) = clock.map(str::parse::<usize>).transpose() else {
+                eprintln!("the clock is the index of an input");
+                return ExitCode::FAILURE;
+            };
+            let text = match fs::read_to_string(file) {
+                Ok(text) => text,
+                Err(error) => {
+                    eprintln!("{file}: {error}");
+                    return ExitCode::FAILURE;
+                }
+            };
+            let path = Path::new(file);
+            let library = if path
+                .extension()
+                .is_some_and(|extension| extension == "circ")
+            {
+                Library::parse(&text, &registry)
+            } else {
+                let mut library = Library::new(&registry);
+                let name = path.file_stem().and_then(|stem| stem.to_str());
+                library.add(name.unwrap_or("diagram"), text);
+                library
+            };
+            let Some(component) = component.or(library.last()) else {
+                eprintln!("{file}: no diagrams");
+                return ExitCode::FAILURE;
+            };
+            match library.circuit(component) {
+                Ok((circuit, logic)) => explore(Explorer::new(circuit, logic, clock)),
+                Err(error) => {
+                    eprintln!("{file}: {error:?}");
+                    ExitCode::FAILURE
+                }
+            }
+        }
+        ["svg", diagram, ref inputs @ ..] if inputs.len() <= 1 => {
+            let source = match (fs::read_to_string(diagram), registry.graph(diagram)) {
+                (Ok(source), _) => source,
+                (Err(_), Some(graph)) => render(graph),
+                (Err(error), None) => {
+                    match registry.arguments_error(diagram) {
+                        Some(arguments_error) => eprintln!("`{diagram}`: {arguments_error}"),
+                        None => eprintln!(
+                            "{diagram} is neither a component nor a readable file: {error}"
+                        ),
+                    }
+                    return ExitCode::FAILURE;
+                }
+            };
+            let image = match inputs.first() {
+                None => parser::svg(&source, None).map(Ok),
+                Some(inputs) => LiveDiagram::new(source, &registry).map(|mut live| {
+                    let inputs =
+                        parse_bits(inputs).filter(|inputs| inputs.len() == live.input_num());
+                    inputs
+                        .map(|inputs| {
+                            live.step(&inputs);
+                            live.svg()
+                        })
+                        .ok_or(live.input_num())
+                }),
+            };
+            match image {
+                Ok(Ok(image)) => {
+                    print!("{image}");
+                    ExitCode::SUCCESS
+                }
+                Ok(Err(input_num)) => {
+                    eprintln!("expected {input_num} bits of 1, 0 or x");
+                    ExitCode::FAILURE
+                }
+                Err(error) => {
+                    eprintln!("{diagram}: diagram could not be parsed: {error:?}");
+                    ExitCode::FAILURE
+                }
+            }
+        }
+        ["run", file, cycles, ref options @ ..] => match cycles.parse() {
+            Ok(cycles) => run(file, cycles, options, &registry),
+            Err(_) => {
+                eprintln!("expected a number of cycles, got `{cycles}`");
+                ExitCode::FAILURE
+            }
+        },
+        ["lsp"] => match lsp::run(&mut io::stdin().lock(), &mut io::stdout().lock(), &registry) {
+            Ok(()) => ExitCode::SUCCESS,
+            Err(error) => {
+                eprintln!("language server stopped: {error}");
+                ExitCode::FAILURE
+            }
+        },
+        ["equiv", left, right] => match (registry.graph(left), registry.graph(right)) {
+            (Some(left_graph), Some(right_graph)) => {
+                match check_equivalence(left_graph, right_graph, &|name| registry.graph(name)) {
+                    Ok(Equivalence::Equivalent) => {
+                        println!("`{left}` and `{right}` are equivalent");
+                        ExitCode::SUCCESS
+                    }
+                    Ok(Equivalence::Counterexample {
+                        inputs,
+                        left: left_outputs,
+                        right: right_outputs,
+                    }) => {
+                        println!("`{left}` and `{right}` differ");
+                        println!("  inputs:  {}", bits(&inputs));
+                        println!("  {left}: {}", bits(&left_outputs));
+                        println!("  {right}: {}", bits(&right_outputs));
+                        ExitCode::FAILURE
+                    }
+                    Err(error) => {
+                        eprintln!("can not compare `{left}` and `{right}`: {error:?}");
+                        ExitCode::FAILURE
+                    }
+                }
+            }
+            (None, _) => {
+                report_unknown(&registry, left);
+                ExitCode::FAILURE
+            }
+            (_, None) => {
+                report_unknown(&registry, right);
+                ExitCode::FAILURE
+            }
+        },
+        _ => {
+            eprint!("{USAGE}");
+            ExitCode::FAILURE
+        }
+    }
+}
+
+/// Tells why the component can not be built: the arguments of a generator
+/// call it does not accept, or that there is no such component
+fn report_unknown(registry: &registry::ComponentRegistry, component: &str) {
+    match registry.arguments_error(component) {
+        Some(error) => eprintln!("`{component}`: {error}"),
+        None => eprintln!("unknown or primitive component `{component}`"),
+    }
+}
+
+/// Simulates the diagram in the file with its clocks, given like
+/// `clk=4:1:2` in the options, and the other inputs set to the bits of the
+/// option without a `=`
+fn run(
+    file: &str,
+    cycles: usize,
+    options: &[&str],
+    registry: &registry::ComponentRegistry,
+) -> ExitCode {
+    let source = match fs::read_to_string(file) {
+        Ok(source) => source,
+        Err(error) => {
+            eprintln!("{file}: {error}");
+            return ExitCode::FAILURE;
+        }
+    };
+    let names = parser::names(&source);
+    let (graph, clocks) = match parser::parse(&source, &registry.functions(&names))
+        .and_then(|graph| Ok((graph, parser::clocks(&source)?)))
+    {
+        Ok(parsed) => parsed,
+        Err(error) => {
+            eprintln!("{file}: diagram could not be parsed: {error}");
+            return ExitCode::FAILURE;
+        }
+    };
+    let input_num = graph.input_num() - clocks.len();
+    let mut scheduler = Scheduler::new(graph);
+    for input in 0..input_num {
+        scheduler.set_input(input, BitState::Off);
+    }
+    for (_, input) in &clocks {
+        scheduler.add_clock(*input, Clock::new(2));
+    }
+    for option in options {
+        let Some((name, spec)) = option.split_once('=') else {
+            match parse_bits(option).filter(|inputs| inputs.len() == input_num) {
+                Some(inputs) => inputs
+                    .into_iter()
+                    .enumerate()
+                    .for_each(|(input, value)| scheduler.set_input(input, value)),
+                None => {
+                    eprintln!("expected {input_num} bits of 1, 0 or x for the inputs");
+                    return ExitCode::FAILURE;
+                }
+            }
+            continue;
+        };
+        let Some((_, input)) = clocks.iter().find(|(clock, _)| clock == name) else {
+            eprintln!("{file} has no clock `{name}`");
+            return ExitCode::FAILURE;
+        };
+        let numbers = spec
+            .split(':')
+            .map(str::parse::<usize>)
+            .collect::<Result<Vec<_>, _>>();
+        let clock = match numbers.as_deref() {
+            Ok([period]) if *period > 0 => Clock::new(*period),
+            Ok([period, phase]) if *period > 0 => Clock::new(*period).with_phase(*phase),
+            Ok([period, phase, duty]) if *period > 0 && duty <= period => {
+                Clock::new(*period).with_phase(*phase).with_duty(*duty)
+            }
+            _ => {
+                eprintln!(
+                    "expected <period>[:<phase>[:<duty>]] in ticks for `{name}`, got `{spec}`"
+                );
+                return ExitCode::FAILURE;
+            }
+        };
+        scheduler.add_clock(*input, clock);
+    }
+
+    // without clocks a cycle is a tick
+    let ticks = match clocks
+        .first()
+        .and_then(|(_, input)| scheduler.clock(*input))
+    {
+        Some(clock) => cycles * clock.period(),
+        None => cycles,
+    };
+    println!("tick  inputs  outputs");
+    for _ in 0..ticks {
+        let tick = scheduler.tick();
+        scheduler.step();
+        println!(
+            "{tick:>4}  {}  {}",
+            bits(scheduler.inputs()),
+            bits(scheduler.outputs())
+        );
+    }
+    ExitCode::SUCCESS
+}
+
+/// Terminal settings as `stty -g` prints them, put back when dropped so the
+/// terminal is restored however the explorer exits
+struct TerminalSettings(String);
+
+impl TerminalSettings {
+    fn save() -> Option<TerminalSettings> {
+        let output = Command::new("stty")
+            .arg("-g")
+            .stdin(Stdio::inherit())
+            .output()
+            .ok()
+            .filter(|output| output.status.success())?;
+        let settings = String::from_utf8(output.stdout).ok()?;
+        Some(TerminalSettings(settings.trim().to_string()))
+    }
+}
+
+impl Drop for TerminalSettings {
+    fn drop(&mut self) {
+        let _ = Command::new("stty").arg(&self.0).status();
+    }
+}
+
+/// Runs the explorer with the terminal reading single key presses
+fn explore(mut explorer: Explorer) -> ExitCode {
+    let Some(_settings) = TerminalSettings::save() else {
+        eprintln!("the terminal settings could not be read");
+        return ExitCode::FAILURE;
+    };
+    let switched = Command::new("stty")
+        .args(["-icanon", "-echo", "-isig", "min", "1"])
+        .status()
+        .is_ok_and(|status| status.success());
+    if !switched {
+        eprintln!("the terminal could not be switched to reading single keys");
+        return ExitCode::FAILURE;
+    }
+    let mut stdout = io::stdout();
+    let _ = write!(stdout, "{CLEAR_SCREEN}{}", explorer.draw());
+    let _ = stdout.flush();
+    for byte in io::stdin().lock().bytes().map_while(Result::ok) {
+        let Some(key) = Key::from_byte(byte) else {
+            continue;
+        };
+        if !explorer.handle(key) {
+            break;
+        }
+        let _ = write!(stdout, "{CLEAR_SCREEN}{}", explorer.draw());
+        let _ = stdout.flush();
+    }
+    ExitCode::SUCCESS
+}
+
+fn parse_bits(text: &str) -> Option<Vec<BitState>> {
+    text.chars()
+        .map(|bit| match bit {
+            '1' => Some(BitState::On),
+            '0' => Some(BitState::Off),
+            'x' | 'X' | '?' => Some(BitState::Undefined),
+            _ => None,
+        })
+        .collect()
+}
+
+fn bits(states: &[BitState]) -> String {
+    states
+        .iter()
+        .map(|state| match state {
+            BitState::On => '1',
+            BitState::Off => '0',
+            BitState::Undefined => '?',
+        })
+        .collect()
 }
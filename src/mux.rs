@@ -0,0 +1,199 @@
+use crate::and::{and, cascade_and};
+use crate::builder::{add_component, add_gate};
+use crate::decoder::decoder;
+use crate::nand::nand;
+use crate::not::not;
+use digital_component::{ComponentLogic, ComponentLogicFactory, Graph, GraphNodeRef, NodeKind};
+use parser::parse;
+use std::collections::HashMap;
+
+pub fn mux_2_to_1() -> Box<ComponentLogic> {
+    mux_2_to_1_graph().finalize()
+}
+
+/// 2 to 1 multiplexer drawn as a diagram: a, b and select inputs, a while
+/// select is off and b while it is on as the output
+pub fn mux_2_to_1_graph() -> Graph {
+    let mut functions: HashMap<&str, Box<ComponentLogicFactory>> = HashMap::new();
+    functions.insert("nand", Box::new(|| Box::new(nand)));
+    functions.insert("not", Box::new(not));
+    parse(
+        "
+                            ┏━━━━━━┓
+        ────────────────────┨ nand ┠──┐  ┏━━━━━━┓
+                         ┌──┨      ┃  └──┨ nand ┠───
+                         │  ┗━━━━━━┛  ┌──┨      ┃
+                         │  ┏━━━━━━┓  │  ┗━━━━━━┛
+        ─────────────────┼──┨ nand ┠──┘
+                         │┌─┨      ┃
+                         ││ ┗━━━━━━┛
+                         ││
+        ────┬────────────┼┘
+            │     ┏━━━━━┓│
+            └─────┨ not ┠┘
+                  ┗━━━━━┛
+    ",
+        &functions,
+    )
+    .unwrap()
+}
+
+pub fn mux(select_bits: usize, width: usize) -> Box<ComponentLogicFactory> {
+    Box::new(move || -> Box<ComponentLogic> { mux_graph(select_bits, width).finalize() })
+}
+
+/// Multiplexer of 2^select_bits words of `width` bits: the words one after
+/// another and then the select bits as inputs, the selected word as outputs.
+///
+/// The decoded select lines are NANDed with every bit of their words, and
+/// the bits of the same significance ORed together by a NOT of the AND of
+/// those NANDs
+pub fn mux_graph(select_bits: usize, width: usize) -> Graph {
+    let mut graph = Graph::default();
+    let words = 1 << select_bits;
+    let data_inputs = (0..words * width)
+        .map(|input| graph.add_node(NodeKind::Input(input)))
+        .collect::<Vec<_>>();
+    let select_lines = decoded_select_lines(&mut graph, select_bits, words * width);
+
+    for bit in 0..width {
+        let nand_gates = select_lines
+            .iter()
+            .enumerate()
+            .map(|(word, select_line)| {
+                let data_input = data_inputs[word * width + bit];
+                add_gate(
+                    &mut graph,
+                    "nand",
+                    Box::new(nand),
+                    &[*select_line, data_input],
+                )
+            })
+            .collect::<Vec<_>>();
+        let and_gate = add_gate(
+            &mut graph,
+            &format!("cascade_and({words})"),
+            cascade_and(words)(),
+            &nand_gates,
+        );
+        let not_gate = add_gate(&mut graph, "not", not(), &[and_gate]);
+        let outer_output = graph.add_node(NodeKind::Output(bit));
+        graph.add_edge(&outer_output, &not_gate);
+    }
+
+    graph
+}
+
+pub fn demux(select_bits: usize, width: usize) -> Box<ComponentLogicFactory> {
+    Box::new(move || -> Box<ComponentLogic> { demux_graph(select_bits, width).finalize() })
+}
+
+/// Demultiplexer to 2^select_bits words of `width` bits: a word and then the
+/// select bits as inputs, the words one after another as outputs, the
+/// selected one being the input word and the others all off
+pub fn demux_graph(select_bits: usize, width: usize) -> Graph {
+    let mut graph = Graph::default();
+    let data_inputs = (0..width)
+        .map(|input| graph.add_node(NodeKind::Input(input)))
+        .collect::<Vec<_>>();
+    let select_lines = decoded_select_lines(&mut graph, select_bits, width);
+
+    for (word, select_line) in select_lines.iter().enumerate() {
+        for (bit, data_input) in data_inputs.iter().enumerate() {
+            let and_gate = add_gate(&mut graph, "and", and(), &[*select_line, *data_input]);
+            let outer_output = graph.add_node(NodeKind::Output(word * width + bit));
+            graph.add_edge(&outer_output, &and_gate);
+        }
+    }
+    graph
+}
+
+/// Adds a decoder of the select inputs, numbered from `first_input`, and
+/// returns its outputs, one for every word
+fn decoded_select_lines(
+    graph: &mut Graph,
+    select_bits: usize,
+    first_input: usize,
+) -> Vec<GraphNodeRef> {
+    let select_inputs = (0..select_bits)
+        .map(|bit| graph.add_node(NodeKind::Input(first_input + bit)))
+        .collect::<Vec<_>>();
+    add_component(
+        graph,
+        &format!("decoder({select_bits})"),
+        decoder(select_bits)(),
+        &select_inputs,
+        1 << select_bits,
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::registry::components;
+    use crate::testing::bits;
+    use digital_component::{check_equivalence, BitState::*, Equivalence, TruthTable};
+
+    #[test]
+    fn mux_2_to_1_selects_an_input() {
+        let table = TruthTable::generate(&mut *mux_2_to_1(), 3, 1).unwrap();
+        let expected = TruthTable::parse(
+            "
+            | a | b | select | y |
+            |---|---|--------|---|
+            | 0 | 0 | 0      | 0 |
+            | 0 | 0 | 1      | 0 |
+            | 0 | 1 | 0      | 0 |
+            | 0 | 1 | 1      | 1 |
+            | 1 | 0 | 0      | 1 |
+            | 1 | 0 | 1      | 0 |
+            | 1 | 1 | 0      | 1 |
+            | 1 | 1 | 1      | 1 |
+            ",
+            3,
+        )
+        .unwrap();
+
+        assert_eq!(table.mismatches(&expected), vec![]);
+    }
+
+    #[test]
+    fn generated_mux_matches_diagram() {
+        let registry = components();
+
+        assert_eq!(
+            check_equivalence(mux_2_to_1_graph(), mux_graph(1, 1), &|name| registry
+                .graph(name)),
+            Ok(Equivalence::Equivalent)
+        );
+    }
+
+    #[test]
+    fn mux_selects_words() {
+        let words = [0b101, 0b011, 0b110, 0b000];
+        let mut mux = mux(2, 3)();
+        let mut output = vec![Undefined; 3];
+        for (select, word) in words.iter().enumerate() {
+            let input = words
+                .iter()
+                .flat_map(|word| bits(*word, 3))
+                .chain(bits(select, 2))
+                .collect::<Vec<_>>();
+            mux(&input, &mut output);
+            assert_eq!(output, bits(*word, 3), "select {select}");
+        }
+    }
+
+    #[test]
+    fn demux_routes_word_to_selected_output() {
+        let mut demux = demux(2, 2)();
+        let mut output = vec![Undefined; 8];
+        for select in 0..4 {
+            demux(&[bits(0b10, 2), bits(select, 2)].concat(), &mut output);
+            let expected = (0..4)
+                .flat_map(|word| bits(if word == select { 0b10 } else { 0 }, 2))
+                .collect::<Vec<_>>();
+            assert_eq!(output, expected, "select {select}");
+        }
+    }
+}
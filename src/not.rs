@@ -1,9 +1,13 @@
 use crate::nand::nand;
-use digital_component::{ComponentLogic, ComponentLogicFactory};
+use digital_component::{ComponentLogic, ComponentLogicFactory, Graph};
 use parser::parse;
 use std::collections::HashMap;
 
 pub fn not() -> Box<ComponentLogic> {
+    not_graph().finalize()
+}
+
+pub fn not_graph() -> Graph {
     let mut functions: HashMap<&str, Box<ComponentLogicFactory>> = HashMap::new();
     functions.insert("nand", Box::new(|| Box::new(nand)));
     parse(
@@ -16,7 +20,6 @@ pub fn not() -> Box<ComponentLogic> {
         &functions,
     )
     .unwrap()
-    .finalize()
 }
 
 #[cfg(test)]
@@ -0,0 +1,99 @@
+use crate::builder::cascade_graph;
+use crate::nand::nand;
+use crate::not::not;
+use digital_component::{ComponentLogic, ComponentLogicFactory, Graph};
+use parser::parse;
+use std::collections::HashMap;
+
+pub fn or() -> Box<ComponentLogic> {
+    or_graph().finalize()
+}
+
+/// 2 input OR gate: a NAND of the inverted inputs
+pub fn or_graph() -> Graph {
+    let mut functions: HashMap<&str, Box<ComponentLogicFactory>> = HashMap::new();
+    functions.insert("nand", Box::new(|| Box::new(nand)));
+    functions.insert("not", Box::new(not));
+    parse(
+        "
+          ┏━━━━━┓ ┏━━━━━━┓
+       ───┨ not ┠─┨      ┃
+          ┗━━━━━┛ ┃      ┃
+                  ┃ nand ┠───
+          ┏━━━━━┓ ┃      ┃
+       ───┨ not ┠─┨      ┃
+          ┗━━━━━┛ ┗━━━━━━┛
+    ",
+        &functions,
+    )
+    .unwrap()
+}
+
+pub fn nor() -> Box<ComponentLogic> {
+    nor_graph().finalize()
+}
+
+pub fn nor_graph() -> Graph {
+    let mut functions: HashMap<&str, Box<ComponentLogicFactory>> = HashMap::new();
+    functions.insert("or", Box::new(or));
+    functions.insert("not", Box::new(not));
+    parse(
+        "
+          ┏━━━━┓   ┏━━━━━┓
+       ───┨    ┃   ┃     ┃
+          ┃ or ┠───┨ not ┠────
+       ───┨    ┃   ┃     ┃
+          ┗━━━━┛   ┗━━━━━┛
+    ",
+        &functions,
+    )
+    .unwrap()
+}
+
+/// Cascade n - 1 OR gates to have an n input OR gate, the same way
+/// [`crate::and::cascade_and`] does with AND gates
+pub fn cascade_or(n: usize) -> Box<ComponentLogicFactory> {
+    Box::new(move || -> Box<ComponentLogic> { cascade_or_graph(n).finalize() })
+}
+
+pub fn cascade_or_graph(n: usize) -> Graph {
+    cascade_graph(n, "or", or)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use digital_component::{BitState::*, TruthTable};
+
+    #[test]
+    fn ors() {
+        let table = TruthTable::generate(&mut *or(), 2, 1).unwrap();
+        let expected = TruthTable::parse("a,b,y\n0,0,0\n0,1,1\n1,0,1\n1,1,1\n", 2).unwrap();
+
+        assert_eq!(table.mismatches(&expected), vec![]);
+    }
+
+    #[test]
+    fn nors() {
+        let table = TruthTable::generate(&mut *nor(), 2, 1).unwrap();
+        let expected = TruthTable::parse("a,b,y\n0,0,1\n0,1,0\n1,0,0\n1,1,0\n", 2).unwrap();
+
+        assert_eq!(table.mismatches(&expected), vec![]);
+    }
+
+    #[test]
+    fn cascades_or_gates() {
+        let mut or_gate = cascade_or(3)();
+
+        let mut output = vec![Undefined];
+
+        or_gate(&[Off, Off, Off], &mut output);
+        assert_eq!(output, vec![Off]);
+
+        or_gate(&[Off, Off, On], &mut output);
+        assert_eq!(output, vec![On]);
+
+        or_gate(&[On, Undefined, Undefined], &mut output);
+        assert_eq!(output, vec![On]);
+    }
+}
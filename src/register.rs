@@ -1,15 +1,35 @@
 use crate::and::and;
 use crate::bit::bit;
-use digital_component::{ComponentLogic, ComponentLogicFactory};
+use crate::flip_flop::d_flip_flop;
+use digital_component::{ComponentLogic, ComponentLogicFactory, Graph};
 use parser::parse;
 use std::collections::HashMap;
 
 pub fn register() -> Box<ComponentLogic> {
+    register_graph().finalize()
+}
+
+pub fn register_graph() -> Graph {
     let mut functions: HashMap<&str, Box<ComponentLogicFactory>> = HashMap::new();
     functions.insert("bit", Box::new(bit));
     functions.insert("and", Box::new(and));
-    parse(
-        "
+    parse(REGISTER, &functions).unwrap()
+}
+
+pub fn dff_register() -> Box<ComponentLogic> {
+    dff_register_graph().finalize()
+}
+
+/// [`register_graph`] with edge triggered flip-flops instead of latches, the
+/// set input being the clock the data is stored on the rising edge of
+pub fn dff_register_graph() -> Graph {
+    let mut functions: HashMap<&str, Box<ComponentLogicFactory>> = HashMap::new();
+    functions.insert("dff", Box::new(d_flip_flop));
+    functions.insert("and", Box::new(and));
+    parse(DFF_REGISTER, &functions).unwrap()
+}
+
+const REGISTER: &str = "
         ┏━━━┓  ┏━━━┓
      ───┨bit┠──┨and┠─
       ┌─┨   ┃ ┌┨   ┃
@@ -44,17 +64,50 @@ pub fn register() -> Box<ComponentLogic> {
       │ ┗━━━┛ │┗━━━┛
      ─┘       │
      ─────────┘
-    ",
-        &functions,
-    )
-    .unwrap()
-    .finalize()
-}
+    ";
+
+const DFF_REGISTER: &str = "
+        ┏━━━┓  ┏━━━┓
+     ───┨dff┠──┨and┠─
+      ┌─┨   ┃ ┌┨   ┃
+      │ ┗━━━┛ │┗━━━┛
+      │ ┏━━━┓ │┏━━━┓
+     ─┼─┨dff┠─┼┨and┠─
+      ├─┨   ┃ ├┨   ┃
+      │ ┗━━━┛ │┗━━━┛
+      │ ┏━━━┓ │┏━━━┓
+     ─┼─┨dff┠─┼┨and┠─
+      ├─┨   ┃ ├┨   ┃
+      │ ┗━━━┛ │┗━━━┛
+      │ ┏━━━┓ │┏━━━┓
+     ─┼─┨dff┠─┼┨and┠─
+      ├─┨   ┃ ├┨   ┃
+      │ ┗━━━┛ │┗━━━┛
+      │ ┏━━━┓ │┏━━━┓
+     ─┼─┨dff┠─┼┨and┠─
+      ├─┨   ┃ ├┨   ┃
+      │ ┗━━━┛ │┗━━━┛
+      │ ┏━━━┓ │┏━━━┓
+     ─┼─┨dff┠─┼┨and┠─
+      ├─┨   ┃ ├┨   ┃
+      │ ┗━━━┛ │┗━━━┛
+      │ ┏━━━┓ │┏━━━┓
+     ─┼─┨dff┠─┼┨and┠─
+      ├─┨   ┃ ├┨   ┃
+      │ ┗━━━┛ │┗━━━┛
+      │ ┏━━━┓ │┏━━━┓
+     ─┼─┨dff┠─┼┨and┠─
+      ├─┨   ┃ ├┨   ┃
+      │ ┗━━━┛ │┗━━━┛
+     ─┘       │
+     ─────────┘
+    ";
 
 #[cfg(test)]
 mod tests {
     use super::*;
     use digital_component::BitState::*;
+    use digital_component::{Clock, Scheduler};
 
     pub const SET_BIT: usize = 8;
     pub const ENABLE_BIT: usize = 9;
@@ -70,7 +123,6 @@ mod tests {
         reg(&input, &mut output);
         assert_eq!(output, vec![Off; 8]);
 
-        input[ENABLE_BIT] = On;
         assert_eq!(output, vec![Off; 8]);
 
         input = [
@@ -85,4 +137,31 @@ mod tests {
         reg(&input, &mut output);
         assert_eq!(output, vec![Off, On, On, Off, On, On, Off, Off]);
     }
+
+    #[test]
+    fn stores_values_on_rising_clock_edge_only() {
+        let mut scheduler = Scheduler::new(dff_register_graph());
+        scheduler.add_clock(SET_BIT, Clock::new(4).with_phase(2));
+        scheduler.set_input(ENABLE_BIT, On);
+        let value = [On, Off, On, On, Off, Off, On, Off];
+        let other_value = [Off, On, On, Off, Off, On, Off, On];
+
+        // the clock rises at tick 2
+        value
+            .iter()
+            .enumerate()
+            .for_each(|(input, bit)| scheduler.set_input(input, *bit));
+        assert_eq!(scheduler.run(3), &value);
+
+        // changing the data while the clock is high or falling does nothing,
+        // unlike with latches
+        other_value
+            .iter()
+            .enumerate()
+            .for_each(|(input, bit)| scheduler.set_input(input, *bit));
+        assert_eq!(scheduler.run(3), &value);
+        assert_eq!(scheduler.inputs()[SET_BIT], Off);
+
+        assert_eq!(scheduler.run(1), &other_value);
+    }
 }
@@ -0,0 +1,863 @@
+use crate::adder::{
+    adder, adder_cla, adder_cla_graph, adder_graph, full_adder, full_adder_graph, half_adder,
+    half_adder_graph,
+};
+use crate::alu::{alu, alu_flags, alu_flags_graph, alu_graph};
+use crate::and::{and, and_graph, cascade_and, cascade_and_graph};
+use crate::bit::{bit, bit_graph};
+use crate::comparator::{comparator, comparator_graph};
+use crate::decoder::{decoder, decoder_2_to_4, decoder_2_to_4_graph, decoder_graph};
+use crate::encoder::{encoder, encoder_graph, priority_encoder, priority_encoder_graph};
+use crate::flip_flop::{
+    d_flip_flop, d_flip_flop_graph, jk_flip_flop, jk_flip_flop_graph, sr_flip_flop,
+    sr_flip_flop_graph, t_flip_flop, t_flip_flop_graph,
+};
+use crate::latch::{gated_sr_latch, gated_sr_latch_graph, sr_latch, sr_latch_graph};
+use crate::mux::{demux, demux_graph, mux, mux_2_to_1, mux_2_to_1_graph, mux_graph};
+use crate::nand::nand;
+use crate::not::{not, not_graph};
+use crate::or::{cascade_or, cascade_or_graph, nor, nor_graph, or, or_graph};
+use crate::register::{dff_register, dff_register_graph, register, register_graph};
+use crate::shifter::{barrel_shifter, barrel_shifter_graph};
+use crate::xor::{cascade_xor, cascade_xor_graph, xnor, xnor_graph, xor, xor_graph};
+use digital_component::{ComponentLogic, ComponentLogicFactory, Graph};
+use std::collections::{BTreeMap, HashMap};
+
+/// The most bits of the words generators are asked for
+const MAX_WIDTH: usize = 64;
+
+/// The most select bits of decoders and multiplexers, whose sizes grow with
+/// 2^select_bits
+const MAX_SELECT_BITS: usize = 8;
+
+pub type GraphFactory = dyn Fn() -> Graph;
+
+pub type GraphGenerator = dyn Fn(&[usize]) -> Graph;
+
+pub struct ComponentDefinition {
+    pub description: &'static str,
+    /// [`None`] for primitives
+    pub graph: Option<Box<GraphFactory>>,
+    pub logic: fn() -> Box<ComponentLogic>,
+    /// where the component is defined, relative to the crate root
+    pub source_file: &'static str,
+    /// the function of `source_file` the component is defined by
+    pub function: &'static str,
+}
+
+pub struct GeneratorDefinition {
+    pub description: &'static str,
+    pub parameters: &'static [&'static str],
+    /// Checks the arguments, as many as there are parameters, before anything
+    /// is generated from them
+    pub validate: fn(&[usize]) -> Result<(), String>,
+    pub graph: Box<GraphGenerator>,
+    pub logic: fn(&[usize]) -> Box<ComponentLogicFactory>,
+    /// where the generator is defined, relative to the crate root
+    pub source_file: &'static str,
+    /// the function of `source_file` the generator is defined by
+    pub function: &'static str,
+}
+
+/// Every component known by name, both the ones with a fixed structure like
+/// `and` and the ones generated from parameters like `cascade_and(3)`.
+///
+/// Names are case insensitive, so `NAND` and `nand` refer to the same component
+#[derive(Default)]
+pub struct ComponentRegistry {
+    components: BTreeMap<String, ComponentDefinition>,
+    generators: BTreeMap<String, GeneratorDefinition>,
+}
+
+impl ComponentRegistry {
+    pub fn register(&mut self, name: &str, definition: ComponentDefinition) {
+        self.components.insert(name.to_lowercase(), definition);
+    }
+
+    pub fn register_generator(&mut self, name: &str, definition: GeneratorDefinition) {
+        self.generators.insert(name.to_lowercase(), definition);
+    }
+
+    pub fn component_names(&self) -> impl Iterator<Item = &str> {
+        self.components.keys().map(String::as_str)
+    }
+
+    pub fn generator_names(&self) -> impl Iterator<Item = &str> {
+        self.generators.keys().map(String::as_str)
+    }
+
+    pub fn component(&self, name: &str) -> Option<&ComponentDefinition> {
+        self.components.get(&name.to_lowercase())
+    }
+
+    pub fn generator(&self, name: &str) -> Option<&GeneratorDefinition> {
+        self.generators.get(&name.to_lowercase())
+    }
+
+    /// Structure of the named component, `name` being either the name of a
+    /// component or a generator call like `decoder(3)`
+    pub fn graph(&self, name: &str) -> Option<Graph> {
+        if let Some(component) = self.component(name) {
+            return component.graph.as_ref().map(|graph| graph());
+        }
+        let (generator, arguments) = self.generator_call(name)?.ok()?;
+        Some((generator.graph)(&arguments))
+    }
+
+    /// Logic of the named component, `name` being either the name of a
+    /// component or a generator call like `decoder(3)`
+    pub fn logic(&self, name: &str) -> Option<Box<ComponentLogicFactory>> {
+        if let Some(component) = self.component(name) {
+            return Some(Box::new(component.logic));
+        }
+        let (generator, arguments) = self.generator_call(name)?.ok()?;
+        Some((generator.logic)(&arguments))
+    }
+
+    /// Why the arguments of a call to a known generator, like `decoder(0)`,
+    /// are not accepted, or [`None`] for any other name
+    pub fn arguments_error(&self, name: &str) -> Option<String> {
+        self.generator_call(name)?.err()
+    }
+
+    /// The generator and the arguments of a call to a known generator, if
+    /// they are valid
+    fn generator_call(
+        &self,
+        name: &str,
+    ) -> Option<Result<(&GeneratorDefinition, Vec<usize>), String>> {
+        let (generator_name, arguments) = parse_generator_call(name)?;
+        let generator = self.generator(generator_name)?;
+        if generator.parameters.len() != arguments.len() {
+            return Some(Err(format!(
+                "expected {generator_name}({})",
+                generator.parameters.join(", ")
+            )));
+        }
+        Some((generator.validate)(&arguments).map(|()| (generator, arguments)))
+    }
+
+    /// File and function defining the component, `name` being either the
+    /// name of a component or a generator call like `decoder(3)`
+    pub fn definition(&self, name: &str) -> Option<(&'static str, &'static str)> {
+        if let Some(component) = self.component(name) {
+            return Some((component.source_file, component.function));
+        }
+        let generator = parse_generator_call(name).map_or(name, |(generator, _)| generator);
+        let generator = self.generator(generator)?;
+        Some((generator.source_file, generator.function))
+    }
+
+    /// Logic for every one of the names that is a known component, as
+    /// [`parser::parse`] wants it for the labels of a diagram
+    pub fn functions<'a>(
+        &self,
+        names: &'a [String],
+    ) -> HashMap<&'a str, Box<ComponentLogicFactory>> {
+        names
+            .iter()
+            .filter_map(|name| Some((name.as_str(), self.logic(name)?)))
+            .collect()
+    }
+}
+
+/// Splits `decoder(3)` into `("decoder", vec![3])`
+fn parse_generator_call(name: &str) -> Option<(&str, Vec<usize>)> {
+    let (generator, arguments) = name.trim().strip_suffix(')')?.split_once('(')?;
+    let arguments = arguments
+        .split(',')
+        .map(|argument| argument.trim().parse().ok())
+        .collect::<Option<Vec<_>>>()?;
+    Some((generator.trim(), arguments))
+}
+
+/// Checks that the argument lies between `min` and `max`, both included
+fn in_range(parameter: &str, value: usize, min: usize, max: usize) -> Result<(), String> {
+    if (min..=max).contains(&value) {
+        Ok(())
+    } else {
+        Err(format!(
+            "{parameter} must be between {min} and {max}, not {value}"
+        ))
+    }
+}
+
+/// Checks that the argument is a power of two no greater than `max`
+fn power_of_two(parameter: &str, value: usize, max: usize) -> Result<(), String> {
+    if value.is_power_of_two() && value <= max {
+        Ok(())
+    } else {
+        Err(format!(
+            "{parameter} must be a power of two up to {max}, not {value}"
+        ))
+    }
+}
+
+/// All the components this CPU is built from
+pub fn components() -> ComponentRegistry {
+    let mut registry = ComponentRegistry::default();
+    registry.register(
+        "nand",
+        ComponentDefinition {
+            description: "NAND gate, the only primitive everything else is built from",
+            graph: None,
+            logic: || Box::new(nand),
+            source_file: "src/nand.rs",
+            function: "nand",
+        },
+    );
+    registry.register(
+        "not",
+        ComponentDefinition {
+            description: "NOT gate",
+            graph: Some(Box::new(not_graph)),
+            logic: not,
+            source_file: "src/not.rs",
+            function: "not",
+        },
+    );
+    registry.register(
+        "and",
+        ComponentDefinition {
+            description: "2 input AND gate",
+            graph: Some(Box::new(and_graph)),
+            logic: and,
+            source_file: "src/and.rs",
+            function: "and",
+        },
+    );
+    registry.register(
+        "or",
+        ComponentDefinition {
+            description: "2 input OR gate",
+            graph: Some(Box::new(or_graph)),
+            logic: or,
+            source_file: "src/or.rs",
+            function: "or",
+        },
+    );
+    registry.register(
+        "nor",
+        ComponentDefinition {
+            description: "2 input NOR gate",
+            graph: Some(Box::new(nor_graph)),
+            logic: nor,
+            source_file: "src/or.rs",
+            function: "nor",
+        },
+    );
+    registry.register(
+        "xor",
+        ComponentDefinition {
+            description: "2 input XOR gate",
+            graph: Some(Box::new(xor_graph)),
+            logic: xor,
+            source_file: "src/xor.rs",
+            function: "xor",
+        },
+    );
+    registry.register(
+        "xnor",
+        ComponentDefinition {
+            description: "2 input XNOR gate",
+            graph: Some(Box::new(xnor_graph)),
+            logic: xnor,
+            source_file: "src/xor.rs",
+            function: "xnor",
+        },
+    );
+    registry.register(
+        "half_adder",
+        ComponentDefinition {
+            description: "adds two bits: a and b inputs, sum and carry outputs",
+            graph: Some(Box::new(half_adder_graph)),
+            logic: half_adder,
+            source_file: "src/adder.rs",
+            function: "half_adder",
+        },
+    );
+    registry.register(
+        "full_adder",
+        ComponentDefinition {
+            description: "adds three bits: a, b and carry in inputs, sum and carry out outputs",
+            graph: Some(Box::new(full_adder_graph)),
+            logic: full_adder,
+            source_file: "src/adder.rs",
+            function: "full_adder",
+        },
+    );
+    registry.register(
+        "alu_flags",
+        ComponentDefinition {
+            description: "carry and overflow flags of the ALU: carry out, arithmetic, the sign \
+                bits of a, b and the sum as inputs, carry and overflow as outputs",
+            graph: Some(Box::new(alu_flags_graph)),
+            logic: alu_flags,
+            source_file: "src/alu.rs",
+            function: "alu_flags",
+        },
+    );
+    registry.register(
+        "bit",
+        ComponentDefinition {
+            description: "1 bit memory cell: data and set inputs, stored value output",
+            graph: Some(Box::new(bit_graph)),
+            logic: bit,
+            source_file: "src/bit.rs",
+            function: "bit",
+        },
+    );
+    registry.register(
+        "sr_latch",
+        ComponentDefinition {
+            description: "SR latch: set and reset inputs, stored value and its complement outputs",
+            graph: Some(Box::new(sr_latch_graph)),
+            logic: sr_latch,
+            source_file: "src/latch.rs",
+            function: "sr_latch",
+        },
+    );
+    registry.register(
+        "gated_sr",
+        ComponentDefinition {
+            description: "gated SR latch: asynchronous set, s, enable, r and asynchronous reset \
+                inputs, stored value and its complement outputs",
+            graph: Some(Box::new(gated_sr_latch_graph)),
+            logic: gated_sr_latch,
+            source_file: "src/latch.rs",
+            function: "gated_sr_latch",
+        },
+    );
+    registry.register(
+        "dff",
+        ComponentDefinition {
+            description:
+                "rising edge triggered D flip-flop: data and clock inputs, stored value output",
+            graph: Some(Box::new(d_flip_flop_graph)),
+            logic: d_flip_flop,
+            source_file: "src/flip_flop.rs",
+            function: "d_flip_flop",
+        },
+    );
+    registry.register(
+        "srff",
+        ComponentDefinition {
+            description: "rising edge triggered SR flip-flop: asynchronous set, s, clock, r and \
+                asynchronous reset inputs, stored value and its complement outputs",
+            graph: Some(Box::new(sr_flip_flop_graph)),
+            logic: sr_flip_flop,
+            source_file: "src/flip_flop.rs",
+            function: "sr_flip_flop",
+        },
+    );
+    registry.register(
+        "jkff",
+        ComponentDefinition {
+            description: "JK flip-flop: asynchronous set, j, clock, k and asynchronous reset \
+                inputs, stored value and its complement outputs",
+            graph: Some(Box::new(jk_flip_flop_graph)),
+            logic: jk_flip_flop,
+            source_file: "src/flip_flop.rs",
+            function: "jk_flip_flop",
+        },
+    );
+    registry.register(
+        "tff",
+        ComponentDefinition {
+            description: "T flip-flop: asynchronous set, t, clock and asynchronous reset \
+                inputs, stored value and its complement outputs",
+            graph: Some(Box::new(t_flip_flop_graph)),
+            logic: t_flip_flop,
+            source_file: "src/flip_flop.rs",
+            function: "t_flip_flop",
+        },
+    );
+    registry.register(
+        "register",
+        ComponentDefinition {
+            description: "8 bit register: 8 data inputs, set and enable inputs, 8 outputs",
+            graph: Some(Box::new(register_graph)),
+            logic: register,
+            source_file: "src/register.rs",
+            function: "register",
+        },
+    );
+    registry.register(
+        "dff_register",
+        ComponentDefinition {
+            description:
+                "8 bit register of D flip-flops: 8 data inputs, clock and enable inputs, 8 outputs",
+            graph: Some(Box::new(dff_register_graph)),
+            logic: dff_register,
+            source_file: "src/register.rs",
+            function: "dff_register",
+        },
+    );
+    registry.register(
+        "decoder_2_to_4",
+        ComponentDefinition {
+            description: "2 to 4 decoder drawn as a diagram",
+            graph: Some(Box::new(decoder_2_to_4_graph)),
+            logic: decoder_2_to_4,
+            source_file: "src/decoder.rs",
+            function: "decoder_2_to_4",
+        },
+    );
+    registry.register(
+        "mux_2_to_1",
+        ComponentDefinition {
+            description: "2 to 1 multiplexer drawn as a diagram: a, b and select inputs, \
+                the selected one as output",
+            graph: Some(Box::new(mux_2_to_1_graph)),
+            logic: mux_2_to_1,
+            source_file: "src/mux.rs",
+            function: "mux_2_to_1",
+        },
+    );
+    registry.register_generator(
+        "cascade_and",
+        GeneratorDefinition {
+            description: "n input AND gate made of n - 1 cascaded AND gates",
+            parameters: &["n"],
+            validate: |arguments| in_range("n", arguments[0], 1, MAX_WIDTH),
+            graph: Box::new(|arguments| cascade_and_graph(arguments[0])),
+            logic: |arguments| cascade_and(arguments[0]),
+            source_file: "src/and.rs",
+            function: "cascade_and",
+        },
+    );
+    registry.register_generator(
+        "cascade_or",
+        GeneratorDefinition {
+            description: "n input OR gate made of n - 1 cascaded OR gates",
+            parameters: &["n"],
+            validate: |arguments| in_range("n", arguments[0], 1, MAX_WIDTH),
+            graph: Box::new(|arguments| cascade_or_graph(arguments[0])),
+            logic: |arguments| cascade_or(arguments[0]),
+            source_file: "src/or.rs",
+            function: "cascade_or",
+        },
+    );
+    registry.register_generator(
+        "cascade_xor",
+        GeneratorDefinition {
+            description: "n input XOR gate made of n - 1 cascaded XOR gates, on for an odd \
+                number of inputs on",
+            parameters: &["n"],
+            validate: |arguments| in_range("n", arguments[0], 1, MAX_WIDTH),
+            graph: Box::new(|arguments| cascade_xor_graph(arguments[0])),
+            logic: |arguments| cascade_xor(arguments[0]),
+            source_file: "src/xor.rs",
+            function: "cascade_xor",
+        },
+    );
+    registry.register_generator(
+        "adder",
+        GeneratorDefinition {
+            description: "n bit ripple carry adder: n bits of a, n bits of b and carry in \
+                inputs, n sum bits and carry out outputs",
+            parameters: &["n"],
+            validate: |arguments| in_range("n", arguments[0], 1, MAX_WIDTH),
+            graph: Box::new(|arguments| adder_graph(arguments[0])),
+            logic: |arguments| adder(arguments[0]),
+            source_file: "src/adder.rs",
+            function: "adder",
+        },
+    );
+    registry.register_generator(
+        "adder_cla",
+        GeneratorDefinition {
+            description: "n bit carry lookahead adder with a Kogge–Stone prefix network, \
+                with the same inputs and outputs as adder(n)",
+            parameters: &["n"],
+            validate: |arguments| in_range("n", arguments[0], 1, MAX_WIDTH),
+            graph: Box::new(|arguments| adder_cla_graph(arguments[0])),
+            logic: |arguments| adder_cla(arguments[0]),
+            source_file: "src/adder.rs",
+            function: "adder_cla",
+        },
+    );
+    registry.register_generator(
+        "comparator",
+        GeneratorDefinition {
+            description: "comparator of two n bit numbers: a, b and signed as inputs, \
+                a == b, a < b and a > b as outputs",
+            parameters: &["n"],
+            validate: |arguments| in_range("n", arguments[0], 1, MAX_WIDTH),
+            graph: Box::new(|arguments| comparator_graph(arguments[0])),
+            logic: |arguments| comparator(arguments[0]),
+            source_file: "src/comparator.rs",
+            function: "comparator",
+        },
+    );
+    registry.register_generator(
+        "decoder",
+        GeneratorDefinition {
+            description: "n to 2^n decoder",
+            parameters: &["n"],
+            validate: |arguments| in_range("n", arguments[0], 1, MAX_SELECT_BITS),
+            graph: Box::new(|arguments| decoder_graph(arguments[0])),
+            logic: |arguments| decoder(arguments[0]),
+            source_file: "src/decoder.rs",
+            function: "decoder",
+        },
+    );
+    registry.register_generator(
+        "encoder",
+        GeneratorDefinition {
+            description: "2^n to n encoder: the number of the input that is on, and a valid \
+                output on while any input is",
+            parameters: &["n"],
+            validate: |arguments| in_range("n", arguments[0], 1, MAX_SELECT_BITS),
+            graph: Box::new(|arguments| encoder_graph(arguments[0])),
+            logic: |arguments| encoder(arguments[0]),
+            source_file: "src/encoder.rs",
+            function: "encoder",
+        },
+    );
+    registry.register_generator(
+        "priority_encoder",
+        GeneratorDefinition {
+            description: "2^n to n priority encoder: the number of the highest numbered input \
+                that is on, and a valid output on while any input is",
+            parameters: &["n"],
+            validate: |arguments| in_range("n", arguments[0], 1, MAX_SELECT_BITS),
+            graph: Box::new(|arguments| priority_encoder_graph(arguments[0])),
+            logic: |arguments| priority_encoder(arguments[0]),
+            source_file: "src/encoder.rs",
+            function: "priority_encoder",
+        },
+    );
+    registry.register_generator(
+        "mux",
+        GeneratorDefinition {
+            description: "multiplexer of 2^select_bits words: the words and then the select \
+                bits as inputs, the selected word as outputs",
+            parameters: &["select_bits", "width"],
+            validate: |arguments| {
+                in_range("select_bits", arguments[0], 1, MAX_SELECT_BITS)?;
+                in_range("width", arguments[1], 1, MAX_WIDTH)
+            },
+            graph: Box::new(|arguments| mux_graph(arguments[0], arguments[1])),
+            logic: |arguments| mux(arguments[0], arguments[1]),
+            source_file: "src/mux.rs",
+            function: "mux",
+        },
+    );
+    registry.register_generator(
+        "demux",
+        GeneratorDefinition {
+            description: "demultiplexer to 2^select_bits words: a word and then the select \
+                bits as inputs, the selected output word getting it and the others off",
+            parameters: &["select_bits", "width"],
+            validate: |arguments| {
+                in_range("select_bits", arguments[0], 1, MAX_SELECT_BITS)?;
+                in_range("width", arguments[1], 1, MAX_WIDTH)
+            },
+            graph: Box::new(|arguments| demux_graph(arguments[0], arguments[1])),
+            logic: |arguments| demux(arguments[0], arguments[1]),
+            source_file: "src/mux.rs",
+            function: "demux",
+        },
+    );
+    registry.register_generator(
+        "barrel_shifter",
+        GeneratorDefinition {
+            description: "barrel shifter of a word of a power of two bits: the word, the \
+                amount and a two bit operation (shift left, logical or arithmetic shift \
+                right, rotate right) as inputs, the shifted word as outputs",
+            parameters: &["width"],
+            validate: |arguments| power_of_two("width", arguments[0], MAX_WIDTH),
+            graph: Box::new(|arguments| barrel_shifter_graph(arguments[0])),
+            logic: |arguments| barrel_shifter(arguments[0]),
+            source_file: "src/shifter.rs",
+            function: "barrel_shifter",
+        },
+    );
+    registry.register_generator(
+        "alu",
+        GeneratorDefinition {
+            description: "ALU of words of a power of two bits: a, b and a four bit opcode as \
+                inputs, the result and the zero, carry, negative and overflow flags as outputs",
+            parameters: &["width"],
+            validate: |arguments| power_of_two("width", arguments[0], MAX_WIDTH),
+            graph: Box::new(|arguments| alu_graph(arguments[0])),
+            logic: |arguments| alu(arguments[0]),
+            source_file: "src/alu.rs",
+            function: "alu",
+        },
+    );
+    registry
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::decoder::decoder;
+    use crate::nand::nand;
+    use crate::not::not;
+    use digital_component::{
+        check_equivalence, BitState::*, ComponentLogicFactory, DelayModel, Equivalence, Netlist,
+        Statistics, TimingReport,
+    };
+    use parser::{parse, render};
+    use std::collections::HashMap;
+
+    fn netlist(name: &str) -> Netlist {
+        let registry = components();
+        registry
+            .graph(name)
+            .unwrap()
+            .flatten(&|name| registry.graph(name))
+    }
+
+    fn statistics(name: &str) -> Statistics {
+        Statistics::new(&netlist(name))
+    }
+
+    #[test]
+    fn parses_generator_calls() {
+        assert_eq!(
+            parse_generator_call("decoder(3)"),
+            Some(("decoder", vec![3]))
+        );
+        assert_eq!(
+            parse_generator_call(" mux(2, 8) "),
+            Some(("mux", vec![2, 8]))
+        );
+        assert_eq!(parse_generator_call("decoder"), None);
+        assert_eq!(parse_generator_call("decoder(x)"), None);
+    }
+
+    #[test]
+    fn resolves_names_case_insensitively() {
+        let registry = components();
+        assert!(registry.graph("NOT").is_some());
+        assert!(registry.graph("nand").is_none());
+        assert!(registry.graph("decoder(2)").is_some());
+        assert!(registry.graph("decoder(2, 3)").is_none());
+        assert!(registry.component("NAND").is_some());
+    }
+
+    #[test]
+    fn rejects_invalid_arguments() {
+        let registry = components();
+        for name in [
+            "decoder(0)",
+            "decoder(30)",
+            "cascade_and(0)",
+            "adder(0)",
+            "mux(0, 1)",
+            "barrel_shifter(3)",
+            "alu(0)",
+        ] {
+            assert!(registry.graph(name).is_none(), "{name}");
+            assert!(registry.logic(name).is_none(), "{name}");
+        }
+        assert_eq!(
+            registry.arguments_error("decoder(0)"),
+            Some("n must be between 1 and 8, not 0".to_string())
+        );
+        assert_eq!(
+            registry.arguments_error("barrel_shifter(3)"),
+            Some("width must be a power of two up to 64, not 3".to_string())
+        );
+        assert_eq!(
+            registry.arguments_error("mux(2)"),
+            Some("expected mux(select_bits, width)".to_string())
+        );
+        assert_eq!(registry.arguments_error("decoder(3)"), None);
+        assert_eq!(registry.arguments_error("not"), None);
+    }
+
+    #[test]
+    fn counts_register_gates() {
+        let statistics = statistics("register");
+
+        // 8 bits of 4 NANDs each and 8 ANDs made of a NAND and a NOT
+        assert_eq!(statistics.primitives.get("nand"), Some(&48));
+        assert_eq!(statistics.instances.len(), 16);
+        // the set line goes into two NANDs of every bit
+        assert_eq!(statistics.max_fan_out, 16);
+        assert_eq!(statistics.feedback_loops.len(), 8);
+    }
+
+    #[test]
+    fn measures_decoder_depth() {
+        let statistics = statistics("decoder(4)");
+
+        // 4 NOTs plus 16 four input ANDs made of 3 ANDs each
+        assert_eq!(statistics.primitives.get("nand"), Some(&(4 + 16 * 3 * 2)));
+        // NOT followed by a tree of two levels of ANDs of a NAND and a NOT each
+        assert_eq!(statistics.max_depth, 5);
+        assert!(statistics.feedback_loops.is_empty());
+    }
+
+    #[test]
+    fn measures_cascade_depth() {
+        // 15 ANDs in a tree of four levels, not a chain of 15
+        let statistics = statistics("cascade_and(16)");
+        assert_eq!(statistics.primitives.get("nand"), Some(&(15 * 2)));
+        assert_eq!(statistics.max_depth, 4 * 2);
+    }
+
+    #[test]
+    fn times_decoder_critical_path() {
+        let report = TimingReport::new(&netlist("decoder(3)"), &DelayModel::new(2));
+
+        // NOT, then two cascaded ANDs, each made of two NANDs
+        assert_eq!(report.min_clock_period(), 2 * (1 + 2 * 2));
+        let critical_path = report.critical_path.unwrap();
+        assert_eq!(critical_path.steps.len(), 5);
+        assert_eq!(critical_path.steps[0].instance, "not#0/nand#0");
+        assert!(report.broken_loops.is_empty());
+    }
+
+    #[test]
+    fn breaks_register_loops() {
+        let report = TimingReport::new(&netlist("register"), &DelayModel::default());
+
+        // one for each of the cross coupled NAND pairs in the bits
+        assert_eq!(report.broken_loops.len(), 8);
+    }
+
+    #[test]
+    fn optimizes_decoder() {
+        let original = netlist("decoder(3)");
+        let optimized = netlist("decoder(3)").optimize().unwrap();
+
+        // the first ANDs of the cascades are shared between pairs of outputs
+        assert_eq!(original.cells.len() - optimized.cells.len(), 4 * 2);
+
+        let mut decoder = decoder(3)();
+        let mut optimized = optimized.into_graph().finalize();
+        for input in 0..8 {
+            let input =
+                [input & 4 != 0, input & 2 != 0, input & 1 != 0]
+                    .map(|bit| if bit { On } else { Off });
+            let mut expected = vec![Undefined; 8];
+            let mut output = vec![Undefined; 8];
+            decoder(&input, &mut expected);
+            optimized(&input, &mut output);
+            assert_eq!(output, expected);
+        }
+    }
+
+    #[test]
+    fn removes_double_negation() {
+        let mut functions: HashMap<&str, Box<ComponentLogicFactory>> = HashMap::new();
+        functions.insert("not", Box::new(not));
+        let graph = parse(
+            "
+          ┏━━━┓ ┏━━━┓
+         ─┨not┠─┨not┠─
+          ┗━━━┛ ┗━━━┛
+        ",
+            &functions,
+        )
+        .unwrap();
+        let registry = components();
+
+        let optimized = graph
+            .flatten(&|name| registry.graph(name))
+            .optimize()
+            .unwrap();
+
+        assert!(optimized.cells.is_empty());
+        let mut buffer = optimized.into_graph().finalize();
+        let mut output = vec![Undefined];
+        buffer(&[Off], &mut output);
+        assert_eq!(output, vec![Off]);
+    }
+
+    #[test]
+    fn proves_decoders_equivalent() {
+        let registry = components();
+
+        assert_eq!(
+            check_equivalence(
+                registry.graph("decoder_2_to_4").unwrap(),
+                registry.graph("decoder(2)").unwrap(),
+                &|name| registry.graph(name)
+            ),
+            Ok(Equivalence::Equivalent)
+        );
+        // too wide to try every input
+        assert_eq!(
+            check_equivalence(
+                registry.graph("cascade_and(24)").unwrap(),
+                registry.graph("cascade_and(24)").unwrap(),
+                &|name| registry.graph(name)
+            ),
+            Ok(Equivalence::Equivalent)
+        );
+    }
+
+    #[test]
+    fn finds_counterexample() {
+        let mut functions: HashMap<&str, Box<ComponentLogicFactory>> = HashMap::new();
+        functions.insert("nand", Box::new(|| Box::new(nand)));
+        let graph = parse(
+            "
+          ┏━━━━┓
+         ─┨    ┃
+          ┃nand┠─
+         ─┨    ┃
+          ┗━━━━┛
+        ",
+            &functions,
+        )
+        .unwrap();
+        let registry = components();
+
+        assert_eq!(
+            check_equivalence(registry.graph("and").unwrap(), graph, &|name| registry
+                .graph(name)),
+            Ok(Equivalence::Counterexample {
+                inputs: vec![Off, Off],
+                left: vec![Off],
+                right: vec![On],
+            })
+        );
+    }
+
+    #[test]
+    fn renders_generated_components() {
+        let registry = components();
+        for (name, functions) in [
+            ("decoder(3)", &["not", "cascade_and(3)"][..]),
+            ("cascade_and(4)", &["and"][..]),
+            ("decoder_2_to_4", &["not", "and"][..]),
+        ] {
+            let functions = functions
+                .iter()
+                .map(|function| (*function, registry.logic(function).unwrap()))
+                .collect::<HashMap<_, _>>();
+            let rendered = render(registry.graph(name).unwrap());
+            let parsed = parse(&rendered, &functions).unwrap();
+
+            assert_eq!(
+                check_equivalence(parsed, registry.graph(name).unwrap(), &|name| registry
+                    .graph(name)),
+                Ok(Equivalence::Equivalent),
+                "{rendered}"
+            );
+        }
+    }
+
+    #[test]
+    fn renders_feedback_loops() {
+        let registry = components();
+        let functions = ["bit", "and"]
+            .into_iter()
+            .map(|function| (function, registry.logic(function).unwrap()))
+            .collect::<HashMap<_, _>>();
+        let rendered = render(registry.graph("register").unwrap());
+        let parsed = parse(&rendered, &functions).unwrap();
+
+        assert_eq!(
+            Statistics::new(&parsed.flatten(&|name| registry.graph(name))),
+            statistics("register")
+        );
+    }
+}
@@ -0,0 +1,143 @@
+use crate::and::and;
+use crate::builder::add_gate;
+use crate::mux::mux_2_to_1;
+use crate::not::not;
+use crate::or::nor;
+use digital_component::{ComponentLogic, ComponentLogicFactory, Graph, GraphNodeRef, NodeKind};
+
+pub fn barrel_shifter(width: usize) -> Box<ComponentLogicFactory> {
+    Box::new(move || -> Box<ComponentLogic> { barrel_shifter_graph(width).finalize() })
+}
+
+/// Barrel shifter of a `width` bit word, `width` being a power of two: the
+/// word, the log2(width) bit amount and the two bit operation as inputs, all
+/// most significant bit first, the shifted word as outputs. The operations are
+///
+/// * 00: shift left
+/// * 01: logical shift right
+/// * 10: arithmetic shift right
+/// * 11: rotate right
+///
+/// Every stage shifts right by a power of two or not, as its bit of the
+/// amount says, so shifting left reverses the word before and after them
+pub fn barrel_shifter_graph(width: usize) -> Graph {
+    assert!(
+        width.is_power_of_two(),
+        "a barrel shifter width must be a power of two"
+    );
+    let amount_bits = width.trailing_zeros() as usize;
+    let mut graph = Graph::default();
+    let word = (0..width)
+        .map(|bit| graph.add_node(NodeKind::Input(bit)))
+        .collect::<Vec<_>>();
+    let amount = (0..amount_bits)
+        .map(|bit| graph.add_node(NodeKind::Input(width + bit)))
+        .collect::<Vec<_>>();
+    let operation = [0, 1].map(|bit| graph.add_node(NodeKind::Input(width + amount_bits + bit)));
+
+    let left = add_gate(&mut graph, "nor", nor(), &operation);
+    let rotate = add_gate(&mut graph, "and", and(), &operation);
+    let not_low = add_gate(&mut graph, "not", not(), &[operation[1]]);
+    let arithmetic = add_gate(&mut graph, "and", and(), &[operation[0], not_low]);
+    let fill = add_gate(&mut graph, "and", and(), &[arithmetic, word[0]]);
+
+    let mut bits = reverse_if(&mut graph, &word, left);
+    for (stage, select) in amount.iter().rev().enumerate() {
+        let shift = 1 << stage;
+        bits = (0..width)
+            .map(|bit| {
+                let shifted_in = if bit >= shift {
+                    bits[bit - shift]
+                } else {
+                    mux(&mut graph, fill, bits[bit + width - shift], rotate)
+                };
+                mux(&mut graph, bits[bit], shifted_in, *select)
+            })
+            .collect();
+    }
+    let bits = reverse_if(&mut graph, &bits, left);
+
+    for (bit, node) in bits.iter().enumerate() {
+        let outer_output = graph.add_node(NodeKind::Output(bit));
+        graph.add_edge(&outer_output, node);
+    }
+    graph
+}
+
+/// The bits in reverse order while select is on
+fn reverse_if(graph: &mut Graph, bits: &[GraphNodeRef], select: GraphNodeRef) -> Vec<GraphNodeRef> {
+    bits.iter()
+        .zip(bits.iter().rev())
+        .map(|(bit, reversed)| mux(graph, *bit, *reversed, select))
+        .collect()
+}
+
+/// Adds a 2 to 1 multiplexer and returns its output, a while select is off
+/// and b while it is on
+fn mux(graph: &mut Graph, a: GraphNodeRef, b: GraphNodeRef, select: GraphNodeRef) -> GraphNodeRef {
+    add_gate(graph, "mux_2_to_1", mux_2_to_1(), &[a, b, select])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::{bits, evaluate, logic_network};
+    use digital_component::BitState::*;
+
+    /// What the shifter should output for the operation on a `width` bit value
+    fn shift(value: usize, amount: usize, operation: usize, width: usize) -> usize {
+        let mask = (1 << width) - 1;
+        match operation {
+            0 => value << amount & mask,
+            1 => value >> amount,
+            2 => {
+                let sign_extended = if value >> (width - 1) == 1 {
+                    value | !mask
+                } else {
+                    value
+                };
+                sign_extended >> amount & mask
+            }
+            _ => (value >> amount | value << (width - amount)) & mask,
+        }
+    }
+
+    #[test]
+    fn shifts_and_rotates_every_word() {
+        for width in [1_usize, 2, 4, 8] {
+            let amount_bits = width.trailing_zeros() as usize;
+            let network = logic_network(barrel_shifter_graph(width));
+            for value in 0..1 << width {
+                for amount in 0..width {
+                    for operation in 0..4 {
+                        let inputs = [
+                            bits(value, width),
+                            bits(amount, amount_bits),
+                            bits(operation, 2),
+                        ]
+                        .concat();
+                        assert_eq!(
+                            evaluate(&network, &inputs),
+                            bits(shift(value, amount, operation, width), width),
+                            "operation {operation} of {value:#b} by {amount} in {width} bits"
+                        );
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn simulates_shifts() {
+        let mut shifter = barrel_shifter(4)();
+        let mut output = vec![Undefined; 4];
+
+        // 1011 rotated right by 1
+        shifter(&[On, Off, On, On, Off, On, On, On], &mut output);
+        assert_eq!(output, vec![On, On, Off, On]);
+
+        // 1011 shifted right by 2, arithmetically
+        shifter(&[On, Off, On, On, On, Off, On, Off], &mut output);
+        assert_eq!(output, vec![On, On, On, Off]);
+    }
+}
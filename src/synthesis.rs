@@ -0,0 +1,627 @@
+use crate::nand::nand;
+use digital_component::{
+    BitState, ComponentInput, ComponentOutput, DigitalComponent, Graph, GraphNodeRef, NodeKind,
+    TruthTable,
+};
+use std::collections::{BTreeSet, HashSet};
+use std::fmt::{Display, Error, Formatter};
+
+/// Functions of more inputs than that are not minimized
+pub const MAX_SYNTHESIS_INPUTS: usize = 12;
+
+#[derive(Debug, PartialEq)]
+pub enum SynthesisError {
+    /// `position` is counted in characters from the start of the expression
+    UnexpectedCharacter {
+        position: usize,
+    },
+    UnexpectedEnd,
+    /// the table has a row with an undefined input
+    UndefinedInput {
+        row: usize,
+    },
+    TooManyInputs {
+        input_num: usize,
+    },
+}
+
+/// Boolean expression like `a & !b | c`, `!` binding tighter than `&` and `&` tighter than `|`
+#[derive(Debug, PartialEq)]
+pub enum Expression {
+    Constant(bool),
+    Variable(String),
+    Not(Box<Expression>),
+    And(Box<Expression>, Box<Expression>),
+    Or(Box<Expression>, Box<Expression>),
+}
+
+impl Expression {
+    pub fn parse(text: &str) -> Result<Expression, SynthesisError> {
+        let mut parser = ExpressionParser {
+            chars: text.chars().collect(),
+            position: 0,
+        };
+        let expression = parser.or()?;
+        match parser.peek() {
+            None => Ok(expression),
+            Some(_) => Err(SynthesisError::UnexpectedCharacter {
+                position: parser.position,
+            }),
+        }
+    }
+
+    /// Names of the variables in the order they first appear in
+    pub fn variables(&self) -> Vec<&str> {
+        let mut variables = vec![];
+        self.collect_variables(&mut variables);
+        variables
+    }
+
+    fn collect_variables<'a>(&'a self, variables: &mut Vec<&'a str>) {
+        match self {
+            Expression::Constant(_) => {}
+            Expression::Variable(name) => {
+                if !variables.contains(&name.as_str()) {
+                    variables.push(name);
+                }
+            }
+            Expression::Not(operand) => operand.collect_variables(variables),
+            Expression::And(left, right) | Expression::Or(left, right) => {
+                left.collect_variables(variables);
+                right.collect_variables(variables);
+            }
+        }
+    }
+
+    /// Minimal sum of products equivalent to the expression
+    pub fn minimize(&self) -> Result<SumOfProducts<'_>, SynthesisError> {
+        let variables = self.variables();
+        if variables.len() > MAX_SYNTHESIS_INPUTS {
+            return Err(SynthesisError::TooManyInputs {
+                input_num: variables.len(),
+            });
+        }
+        let on = (0..1 << variables.len())
+            .filter(|row| self.evaluate(&variables, *row))
+            .collect::<Vec<_>>();
+        Ok(SumOfProducts {
+            implicants: minimize(variables.len(), &on, &[]),
+            inputs: variables,
+        })
+    }
+
+    /// Value of the expression for the variables spelling `row` in binary,
+    /// the first variable being the most significant bit
+    pub fn evaluate(&self, variables: &[&str], row: usize) -> bool {
+        match self {
+            Expression::Constant(value) => *value,
+            Expression::Variable(name) => {
+                let idx = variables.iter().position(|variable| variable == name);
+                idx.is_some_and(|idx| row >> (variables.len() - 1 - idx) & 1 == 1)
+            }
+            Expression::Not(operand) => !operand.evaluate(variables, row),
+            Expression::And(left, right) => {
+                left.evaluate(variables, row) && right.evaluate(variables, row)
+            }
+            Expression::Or(left, right) => {
+                left.evaluate(variables, row) || right.evaluate(variables, row)
+            }
+        }
+    }
+}
+
+struct ExpressionParser {
+    chars: Vec<char>,
+    position: usize,
+}
+
+impl ExpressionParser {
+    fn peek(&mut self) -> Option<char> {
+        while self
+            .chars
+            .get(self.position)
+            .is_some_and(|c| c.is_whitespace())
+        {
+            self.position += 1;
+        }
+        self.chars.get(self.position).copied()
+    }
+
+    fn or(&mut self) -> Result<Expression, SynthesisError> {
+        let mut expression = self.and()?;
+        while self.peek() == Some('|') {
+            self.position += 1;
+            expression = Expression::Or(Box::new(expression), Box::new(self.and()?));
+        }
+        Ok(expression)
+    }
+
+    fn and(&mut self) -> Result<Expression, SynthesisError> {
+        let mut expression = self.unary()?;
+        while self.peek() == Some('&') {
+            self.position += 1;
+            expression = Expression::And(Box::new(expression), Box::new(self.unary()?));
+        }
+        Ok(expression)
+    }
+
+    fn unary(&mut self) -> Result<Expression, SynthesisError> {
+        let position = self.position;
+        match self.peek() {
+            None => Err(SynthesisError::UnexpectedEnd),
+            Some('!') => {
+                self.position += 1;
+                Ok(Expression::Not(Box::new(self.unary()?)))
+            }
+            Some('(') => {
+                self.position += 1;
+                let expression = self.or()?;
+                match self.peek() {
+                    Some(')') => {
+                        self.position += 1;
+                        Ok(expression)
+                    }
+                    Some(_) => Err(SynthesisError::UnexpectedCharacter {
+                        position: self.position,
+                    }),
+                    None => Err(SynthesisError::UnexpectedEnd),
+                }
+            }
+            Some('0') | Some('1') => {
+                self.position += 1;
+                Ok(Expression::Constant(self.chars[self.position - 1] == '1'))
+            }
+            Some(c) if c.is_ascii_alphabetic() || c == '_' => {
+                let start = self.position;
+                while self
+                    .chars
+                    .get(self.position)
+                    .is_some_and(|c| c.is_ascii_alphanumeric() || *c == '_')
+                {
+                    self.position += 1;
+                }
+                Ok(Expression::Variable(
+                    self.chars[start..self.position].iter().collect(),
+                ))
+            }
+            Some(_) => Err(SynthesisError::UnexpectedCharacter {
+                position: position.max(self.position),
+            }),
+        }
+    }
+}
+
+/// Product of the inputs whose bits are set in `value`, negated for the
+/// ones that are not, leaving out the inputs whose bits are set in `mask`
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct Implicant {
+    pub value: usize,
+    pub mask: usize,
+}
+
+impl Implicant {
+    fn covers(&self, minterm: usize) -> bool {
+        minterm & !self.mask == self.value
+    }
+
+    /// `(input, negated)` pairs with input 0 being the most significant bit
+    pub fn literals(&self, input_num: usize) -> Vec<(usize, bool)> {
+        (0..input_num)
+            .filter(|input| self.mask >> (input_num - 1 - input) & 1 == 0)
+            .map(|input| (input, self.value >> (input_num - 1 - input) & 1 == 0))
+            .collect()
+    }
+}
+
+/// Minimal sum of products in the order the implicants were picked
+pub struct SumOfProducts<'a> {
+    pub implicants: Vec<Implicant>,
+    pub inputs: Vec<&'a str>,
+}
+
+impl Display for SumOfProducts<'_> {
+    fn fmt(&self, f: &mut Formatter) -> Result<(), Error> {
+        if self.implicants.is_empty() {
+            return write!(f, "0");
+        }
+        let products = self
+            .implicants
+            .iter()
+            .map(|implicant| {
+                let literals = implicant
+                    .literals(self.inputs.len())
+                    .into_iter()
+                    .map(|(input, negated)| {
+                        format!("{}{}", if negated { "!" } else { "" }, self.inputs[input])
+                    })
+                    .collect::<Vec<_>>();
+                if literals.is_empty() {
+                    "1".to_string()
+                } else {
+                    literals.join(" & ")
+                }
+            })
+            .collect::<Vec<_>>();
+        write!(f, "{}", products.join(" | "))
+    }
+}
+
+/// Quine–McCluskey minimization of the function that is on for the
+/// `on` minterms and may be anything for the `dont_care` ones
+pub fn minimize(input_num: usize, on: &[usize], dont_care: &[usize]) -> Vec<Implicant> {
+    let mut implicants = on
+        .iter()
+        .chain(dont_care)
+        .map(|minterm| Implicant {
+            value: *minterm,
+            mask: 0,
+        })
+        .collect::<BTreeSet<_>>();
+
+    let mut primes: Vec<Implicant> = vec![];
+    while !implicants.is_empty() {
+        let mut combined = BTreeSet::new();
+        let mut used = HashSet::new();
+        for implicant in &implicants {
+            for bit in (0..input_num).map(|bit| 1 << bit) {
+                if implicant.mask & bit != 0 || implicant.value & bit != 0 {
+                    continue;
+                }
+                let neighbour = Implicant {
+                    value: implicant.value | bit,
+                    mask: implicant.mask,
+                };
+                if implicants.contains(&neighbour) {
+                    combined.insert(Implicant {
+                        value: implicant.value,
+                        mask: implicant.mask | bit,
+                    });
+                    used.insert(*implicant);
+                    used.insert(neighbour);
+                }
+            }
+        }
+        primes.extend(
+            implicants
+                .iter()
+                .filter(|implicant| !used.contains(implicant)),
+        );
+        implicants = combined;
+    }
+
+    // the essential primes first, then greedily the ones covering the most
+    let mut uncovered = on.iter().copied().collect::<BTreeSet<_>>();
+    let mut cover = vec![];
+    for minterm in on {
+        let covering = primes
+            .iter()
+            .filter(|prime| prime.covers(*minterm))
+            .collect::<Vec<_>>();
+        if let [essential] = covering[..] {
+            if !cover.contains(essential) {
+                cover.push(*essential);
+                uncovered.retain(|minterm| !essential.covers(*minterm));
+            }
+        }
+    }
+    while !uncovered.is_empty() {
+        let best = *primes
+            .iter()
+            .max_by_key(|prime| {
+                let covered = uncovered.iter().filter(|minterm| prime.covers(**minterm));
+                (covered.count(), prime.mask.count_ones())
+            })
+            .expect("every minterm is covered by a prime");
+        cover.push(best);
+        uncovered.retain(|minterm| !best.covers(*minterm));
+    }
+    cover
+}
+
+/// Builds a circuit of 2-input NANDs for every output of the table in its
+/// minimal two level NAND-NAND form. Undefined outputs and missing rows are
+/// treated as don't cares
+pub fn synthesize(table: &TruthTable) -> Result<Graph, SynthesisError> {
+    let input_num = table.inputs.len();
+    if input_num > MAX_SYNTHESIS_INPUTS {
+        return Err(SynthesisError::TooManyInputs { input_num });
+    }
+    let mut minterms = vec![];
+    for (row_idx, row) in table.rows.iter().enumerate() {
+        let minterm = row
+            .inputs
+            .iter()
+            .try_fold(0, |minterm, input| match input {
+                BitState::On => Ok(minterm << 1 | 1),
+                BitState::Off => Ok(minterm << 1),
+                BitState::Undefined => Err(SynthesisError::UndefinedInput { row: row_idx }),
+            })?;
+        minterms.push((minterm, &row.outputs));
+    }
+
+    let sums = (0..table.outputs.len())
+        .map(|output| {
+            let mut on = vec![];
+            let mut dont_care = (0..1 << input_num).collect::<BTreeSet<_>>();
+            for (minterm, outputs) in &minterms {
+                match outputs[output] {
+                    BitState::On => on.push(*minterm),
+                    BitState::Off => {}
+                    BitState::Undefined => continue,
+                }
+                dont_care.remove(minterm);
+            }
+            minimize(input_num, &on, &dont_care.into_iter().collect::<Vec<_>>())
+        })
+        .collect::<Vec<_>>();
+
+    Ok(NandBuilder::new(input_num).build(&sums))
+}
+
+/// Builds a circuit of 2-input NANDs computing the expression, with the
+/// inputs in the order the variables first appear in
+pub fn synthesize_expression(expression: &str) -> Result<Graph, SynthesisError> {
+    let expression = Expression::parse(expression)?;
+    let sum = expression.minimize()?;
+    Ok(NandBuilder::new(sum.inputs.len()).build(&[sum.implicants]))
+}
+
+/// Builds a circuit of 2-input NANDs gate by gate
+pub(crate) struct NandBuilder {
+    graph: Graph,
+    inputs: Vec<GraphNodeRef>,
+    inverted_inputs: Vec<Option<GraphNodeRef>>,
+}
+
+impl NandBuilder {
+    pub(crate) fn new(input_num: usize) -> NandBuilder {
+        let mut graph = Graph::default();
+        let inputs = (0..input_num)
+            .map(|input| graph.add_node(NodeKind::Input(input)))
+            .collect();
+        NandBuilder {
+            graph,
+            inputs,
+            inverted_inputs: vec![None; input_num],
+        }
+    }
+
+    fn build(mut self, sums: &[Vec<Implicant>]) -> Graph {
+        for (output, sum) in sums.iter().enumerate() {
+            let value = self.sum_of_products(sum);
+            self.output(output, value);
+        }
+        self.graph
+    }
+
+    pub(crate) fn input(&self, input: usize) -> GraphNodeRef {
+        self.inputs[input]
+    }
+
+    pub(crate) fn output(&mut self, output: usize, value: GraphNodeRef) {
+        let output_node = self.graph.add_node(NodeKind::Output(output));
+        self.graph.add_edge(&value, &output_node);
+    }
+
+    pub(crate) fn into_graph(self) -> Graph {
+        self.graph
+    }
+
+    pub(crate) fn nand(&mut self, a: GraphNodeRef, b: GraphNodeRef) -> GraphNodeRef {
+        let gate = self
+            .graph
+            .add_component(DigitalComponent::new(2, 1, Box::new(nand)).with_name("nand"));
+        let first = self
+            .graph
+            .add_node(NodeKind::ComponentInput(ComponentInput::new(gate, 0)));
+        let second = self
+            .graph
+            .add_node(NodeKind::ComponentInput(ComponentInput::new(gate, 1)));
+        self.graph.add_edge(&a, &first);
+        self.graph.add_edge(&b, &second);
+        self.graph
+            .add_node(NodeKind::ComponentOutput(ComponentOutput::new(gate, 0)))
+    }
+
+    pub(crate) fn not(&mut self, a: GraphNodeRef) -> GraphNodeRef {
+        self.nand(a, a)
+    }
+
+    /// Four NANDs, the first one shared by the other three
+    pub(crate) fn xor(&mut self, a: GraphNodeRef, b: GraphNodeRef) -> GraphNodeRef {
+        let both = self.nand(a, b);
+        let a_only = self.nand(a, both);
+        let b_only = self.nand(b, both);
+        self.nand(a_only, b_only)
+    }
+
+    fn constant(&mut self, value: bool) -> GraphNodeRef {
+        let state = if value { BitState::On } else { BitState::Off };
+        let constant = self.graph.add_component(DigitalComponent::constant(state));
+        self.graph
+            .add_node(NodeKind::ComponentOutput(ComponentOutput::new(constant, 0)))
+    }
+
+    fn literal(&mut self, input: usize, negated: bool) -> GraphNodeRef {
+        if !negated {
+            return self.inputs[input];
+        }
+        match self.inverted_inputs[input] {
+            Some(inverted) => inverted,
+            None => {
+                let inverted = self.not(self.inputs[input]);
+                self.inverted_inputs[input] = Some(inverted);
+                inverted
+            }
+        }
+    }
+
+    fn and_all(&mut self, nodes: &[GraphNodeRef]) -> GraphNodeRef {
+        match nodes {
+            [node] => *node,
+            _ => {
+                let nand = self.nand_all(nodes);
+                self.not(nand)
+            }
+        }
+    }
+
+    fn nand_all(&mut self, nodes: &[GraphNodeRef]) -> GraphNodeRef {
+        match nodes {
+            [node] => self.not(*node),
+            [a, b] => self.nand(*a, *b),
+            _ => {
+                let (left, right) = nodes.split_at(nodes.len() / 2);
+                let left = self.and_all(left);
+                let right = self.and_all(right);
+                self.nand(left, right)
+            }
+        }
+    }
+
+    fn sum_of_products(&mut self, sum: &[Implicant]) -> GraphNodeRef {
+        let input_num = self.inputs.len();
+        match sum {
+            [] => return self.constant(false),
+            [implicant] if implicant.literals(input_num).is_empty() => return self.constant(true),
+            [implicant] if implicant.literals(input_num).len() == 1 => {
+                let (input, negated) = implicant.literals(input_num)[0];
+                return self.literal(input, negated);
+            }
+            _ => {}
+        }
+        let inverted_products = sum
+            .iter()
+            .map(|implicant| match implicant.literals(input_num)[..] {
+                [] => self.constant(false),
+                // NAND of a single literal is its complement
+                [(input, negated)] => self.literal(input, !negated),
+                ref literals => {
+                    let literals = literals
+                        .iter()
+                        .map(|(input, negated)| self.literal(*input, *negated))
+                        .collect::<Vec<_>>();
+                    self.nand_all(&literals)
+                }
+            })
+            .collect::<Vec<_>>();
+        self.nand_all(&inverted_products)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::registry::components;
+    use digital_component::{check_equivalence, Equivalence, Statistics};
+
+    fn evaluate(graph: Graph, input_num: usize) -> Vec<bool> {
+        let mut logic = graph.finalize();
+        let mut output = vec![BitState::Undefined];
+        (0..1_usize << input_num)
+            .map(|row| {
+                let inputs = (0..input_num)
+                    .map(|pin| {
+                        if row >> (input_num - 1 - pin) & 1 == 1 {
+                            BitState::On
+                        } else {
+                            BitState::Off
+                        }
+                    })
+                    .collect::<Vec<_>>();
+                logic(&inputs, &mut output);
+                output[0] == BitState::On
+            })
+            .collect()
+    }
+
+    #[test]
+    fn parses_expressions() {
+        let expression = Expression::parse("a & !b | c").unwrap();
+
+        assert_eq!(expression.variables(), vec!["a", "b", "c"]);
+        assert_eq!(
+            expression,
+            Expression::Or(
+                Box::new(Expression::And(
+                    Box::new(Expression::Variable("a".to_string())),
+                    Box::new(Expression::Not(Box::new(Expression::Variable(
+                        "b".to_string()
+                    )))),
+                )),
+                Box::new(Expression::Variable("c".to_string())),
+            )
+        );
+        assert_eq!(
+            Expression::parse("a & (b | c"),
+            Err(SynthesisError::UnexpectedEnd)
+        );
+        assert_eq!(
+            Expression::parse("a + b"),
+            Err(SynthesisError::UnexpectedCharacter { position: 2 })
+        );
+    }
+
+    #[test]
+    fn minimizes_with_dont_cares() {
+        // the textbook example: m(4, 8, 10, 11, 12, 15) + d(9, 14)
+        let sum = minimize(4, &[4, 8, 10, 11, 12, 15], &[9, 14]);
+
+        assert_eq!(
+            SumOfProducts {
+                implicants: sum,
+                inputs: vec!["a", "b", "c", "d"],
+            }
+            .to_string(),
+            "b & !c & !d | a & c | a & !d"
+        );
+    }
+
+    #[test]
+    fn synthesizes_expressions() {
+        for text in [
+            "a & !b | c",
+            "a | !a",
+            "a & !a",
+            "!(a & b) & (c | d) | a & b & c & d",
+        ] {
+            let expression = Expression::parse(text).unwrap();
+            let variables = expression.variables();
+            let expected = (0..1 << variables.len())
+                .map(|row| expression.evaluate(&variables, row))
+                .collect::<Vec<_>>();
+
+            assert_eq!(
+                evaluate(synthesize_expression(text).unwrap(), variables.len()),
+                expected,
+                "{text}"
+            );
+        }
+    }
+
+    #[test]
+    fn synthesizes_two_level_nand_circuits() {
+        let netlist = synthesize_expression("a & !b | c")
+            .unwrap()
+            .flatten(&|_| None);
+
+        // NOT b, the product, NOT c and the sum
+        assert_eq!(Statistics::new(&netlist).primitives.get("nand"), Some(&4));
+    }
+
+    #[test]
+    fn synthesizes_decoder_from_its_table() {
+        let registry = components();
+        let mut decoder = registry.graph("decoder(2)").unwrap().finalize();
+        let table = TruthTable::generate(&mut *decoder, 2, 4).unwrap();
+
+        assert_eq!(
+            check_equivalence(
+                synthesize(&table).unwrap(),
+                registry.graph("decoder(2)").unwrap(),
+                &|name| registry.graph(name)
+            ),
+            Ok(Equivalence::Equivalent)
+        );
+    }
+}
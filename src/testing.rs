@@ -0,0 +1,28 @@
+use crate::registry::components;
+use digital_component::{BitState, BitState::*, Graph, LogicNetwork};
+
+/// The value in `width` bits, most significant first
+pub fn bits(value: usize, width: usize) -> Vec<BitState> {
+    (0..width)
+        .rev()
+        .map(|bit| if value >> bit & 1 == 1 { On } else { Off })
+        .collect()
+}
+
+/// The graph flattened with the known components, which is much quicker to
+/// evaluate than the simulated component when it is checked on many inputs
+pub fn logic_network(graph: Graph) -> LogicNetwork {
+    let registry = components();
+    let mut netlist = graph.flatten(&|name| registry.graph(name));
+    LogicNetwork::new(&mut netlist).unwrap()
+}
+
+/// The outputs of the network for defined inputs
+pub fn evaluate(network: &LogicNetwork, inputs: &[BitState]) -> Vec<BitState> {
+    let inputs = inputs.iter().map(|input| *input == On).collect::<Vec<_>>();
+    network
+        .evaluate(&inputs)
+        .into_iter()
+        .map(|output| if output { On } else { Off })
+        .collect()
+}
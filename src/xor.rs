@@ -0,0 +1,97 @@
+use crate::builder::cascade_graph;
+use crate::nand::nand;
+use crate::not::not;
+use digital_component::{ComponentLogic, ComponentLogicFactory, Graph};
+use parser::parse;
+use std::collections::HashMap;
+
+pub fn xor() -> Box<ComponentLogic> {
+    xor_graph().finalize()
+}
+
+/// 2 input XOR gate of four NANDs, the first one shared by the other three
+pub fn xor_graph() -> Graph {
+    let mut functions: HashMap<&str, Box<ComponentLogicFactory>> = HashMap::new();
+    functions.insert("nand", Box::new(|| Box::new(nand)));
+    parse(
+        "
+                           ┏━━━━━━┓
+         ──┬───────────────┨      ┃
+           │  ┏━━━━━━┓     ┃ nand ┠──┐
+           └──┨      ┃   ┌─┨      ┃  │ ┏━━━━━━┓
+              ┃ nand ┠─┬─┘ ┗━━━━━━┛  └─┨      ┃
+         ──┬──┨      ┃ │               ┃ nand ┠───
+           │  ┗━━━━━━┛ │   ┏━━━━━━┓  ┌─┨      ┃
+           │           └───┨      ┃  │ ┗━━━━━━┛
+           │               ┃ nand ┠──┘
+           └───────────────┨      ┃
+                           ┗━━━━━━┛
+    ",
+        &functions,
+    )
+    .unwrap()
+}
+
+pub fn xnor() -> Box<ComponentLogic> {
+    xnor_graph().finalize()
+}
+
+pub fn xnor_graph() -> Graph {
+    let mut functions: HashMap<&str, Box<ComponentLogicFactory>> = HashMap::new();
+    functions.insert("xor", Box::new(xor));
+    functions.insert("not", Box::new(not));
+    parse(
+        "
+          ┏━━━━━┓   ┏━━━━━┓
+       ───┨     ┃   ┃     ┃
+          ┃ xor ┠───┨ not ┠────
+       ───┨     ┃   ┃     ┃
+          ┗━━━━━┛   ┗━━━━━┛
+    ",
+        &functions,
+    )
+    .unwrap()
+}
+
+/// Cascade n - 1 XOR gates to have an n input XOR gate, which is on when an
+/// odd number of its inputs are
+pub fn cascade_xor(n: usize) -> Box<ComponentLogicFactory> {
+    Box::new(move || -> Box<ComponentLogic> { cascade_xor_graph(n).finalize() })
+}
+
+pub fn cascade_xor_graph(n: usize) -> Graph {
+    cascade_graph(n, "xor", xor)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use digital_component::{BitState::*, TruthTable};
+
+    #[test]
+    fn xors() {
+        let table = TruthTable::generate(&mut *xor(), 2, 1).unwrap();
+        let expected = TruthTable::parse("a,b,y\n0,0,0\n0,1,1\n1,0,1\n1,1,0\n", 2).unwrap();
+
+        assert_eq!(table.mismatches(&expected), vec![]);
+    }
+
+    #[test]
+    fn xnors() {
+        let table = TruthTable::generate(&mut *xnor(), 2, 1).unwrap();
+        let expected = TruthTable::parse("a,b,y\n0,0,1\n0,1,0\n1,0,0\n1,1,1\n", 2).unwrap();
+
+        assert_eq!(table.mismatches(&expected), vec![]);
+    }
+
+    #[test]
+    fn cascades_xor_gates_into_parity() {
+        let table = TruthTable::generate(&mut *cascade_xor(4)(), 4, 1).unwrap();
+
+        for row in table.rows {
+            let ones = row.inputs.iter().filter(|input| **input == On).count();
+            let parity = if ones % 2 == 1 { On } else { Off };
+            assert_eq!(row.outputs, vec![parity], "{:?}", row.inputs);
+        }
+    }
+}